@@ -0,0 +1,52 @@
+-- Maintenance windows and user notifications
+-- Allows technicians to take a microscope out of service for a period of time
+
+-- Bookings can now be cancelled (e.g. by a maintenance window)
+ALTER TABLE bookings DROP CONSTRAINT IF EXISTS bookings_status_check;
+ALTER TABLE bookings ADD CONSTRAINT bookings_status_check
+    CHECK (status IN ('Pending', 'Approved', 'Rejected', 'Cancelled'));
+
+-- Who set a microscope's status. The maintenance sync only puts microscopes back to
+-- Available when a maintenance window set their status, so statuses set by hand are kept.
+ALTER TABLE microscopes ADD COLUMN IF NOT EXISTS status_source VARCHAR(50) NOT NULL DEFAULT 'Manual';
+ALTER TABLE microscopes DROP CONSTRAINT IF EXISTS microscopes_status_source_check;
+ALTER TABLE microscopes ADD CONSTRAINT microscopes_status_source_check
+    CHECK (status_source IN ('Manual', 'MaintenanceWindow'));
+
+-- Maintenance windows table
+CREATE TABLE IF NOT EXISTS maintenance_windows (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    microscope_id VARCHAR(50) NOT NULL REFERENCES microscopes(id),
+    kind VARCHAR(50) NOT NULL DEFAULT 'Maintenance' CHECK (kind IN ('Maintenance', 'Offline')),
+    starts_at TIMESTAMPTZ NOT NULL,
+    ends_at TIMESTAMPTZ NOT NULL,
+    reason TEXT,
+    created_by UUID NOT NULL REFERENCES users(id),
+    cancelled_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT maintenance_window_valid CHECK (ends_at > starts_at)
+);
+
+-- Notifications table (messages shown to users in the UI)
+CREATE TABLE IF NOT EXISTS notifications (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    kind VARCHAR(50) NOT NULL,
+    message TEXT NOT NULL,
+    booking_id UUID REFERENCES bookings(id) ON DELETE SET NULL,
+    session_id UUID REFERENCES sessions(id) ON DELETE SET NULL,
+    read_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_maintenance_microscope_time ON maintenance_windows(microscope_id, starts_at, ends_at);
+CREATE INDEX IF NOT EXISTS idx_maintenance_active ON maintenance_windows(starts_at, ends_at) WHERE cancelled_at IS NULL;
+
+CREATE INDEX IF NOT EXISTS idx_notifications_user ON notifications(user_id, created_at);
+CREATE INDEX IF NOT EXISTS idx_notifications_unread ON notifications(user_id) WHERE read_at IS NULL;
+
+DROP TRIGGER IF EXISTS update_maintenance_windows_updated_at ON maintenance_windows;
+CREATE TRIGGER update_maintenance_windows_updated_at BEFORE UPDATE ON maintenance_windows
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
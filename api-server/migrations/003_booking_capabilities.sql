@@ -0,0 +1,4 @@
+-- Equipment capability requirements for bookings
+-- Matched against microscopes.specs, e.g. {"max_magnification": "1000x", "type": "compound"}
+
+ALTER TABLE bookings ADD COLUMN IF NOT EXISTS required_capabilities JSONB;
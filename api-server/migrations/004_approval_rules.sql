@@ -0,0 +1,49 @@
+-- Booking approval rules
+-- Rules are evaluated in priority order when a booking is created; the first matching
+-- enabled rule decides whether the booking is auto-approved or waits for a teacher.
+
+CREATE TABLE IF NOT EXISTS approval_rules (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    name VARCHAR(255) NOT NULL,
+    priority INTEGER NOT NULL DEFAULT 100, -- Lower runs first
+    action VARCHAR(50) NOT NULL CHECK (action IN ('AutoApprove', 'RequireApproval')),
+    requester_roles TEXT[] NOT NULL DEFAULT '{}', -- Empty matches any role
+    duration_under_minutes INTEGER, -- Matches bookings strictly shorter than this
+    duration_over_minutes INTEGER, -- Matches bookings strictly longer than this
+    weekdays_only BOOLEAN NOT NULL DEFAULT FALSE,
+    microscope_id VARCHAR(50) REFERENCES microscopes(id) ON DELETE CASCADE,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Outcome of rule evaluation for each booking
+CREATE TABLE IF NOT EXISTS booking_approval_decisions (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    booking_id UUID NOT NULL REFERENCES bookings(id) ON DELETE CASCADE,
+    rule_id UUID REFERENCES approval_rules(id) ON DELETE SET NULL,
+    rule_name VARCHAR(255) NOT NULL, -- Kept even if the rule is later deleted
+    action VARCHAR(50) NOT NULL CHECK (action IN ('AutoApprove', 'RequireApproval')),
+    decided_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_approval_rules_priority ON approval_rules(priority) WHERE enabled;
+CREATE INDEX IF NOT EXISTS idx_approval_decisions_booking ON booking_approval_decisions(booking_id);
+
+DROP TRIGGER IF EXISTS update_approval_rules_updated_at ON approval_rules;
+CREATE TRIGGER update_approval_rules_updated_at BEFORE UPDATE ON approval_rules
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+-- Default rules (only if they don't exist). Long bookings need approval whoever makes
+-- them, so that rule runs before the teacher/admin auto-approve rule.
+INSERT INTO approval_rules (name, priority, action, requester_roles)
+SELECT 'Auto-approve teacher and admin bookings', 10, 'AutoApprove', ARRAY['Teacher', 'Admin']
+WHERE NOT EXISTS (SELECT 1 FROM approval_rules WHERE name = 'Auto-approve teacher and admin bookings');
+
+INSERT INTO approval_rules (name, priority, action, duration_over_minutes)
+SELECT 'Bookings over 2 hours need approval', 5, 'RequireApproval', 120
+WHERE NOT EXISTS (SELECT 1 FROM approval_rules WHERE name = 'Bookings over 2 hours need approval');
+
+INSERT INTO approval_rules (name, priority, action, requester_roles, duration_under_minutes, weekdays_only)
+SELECT 'Auto-approve short weekday student bookings', 30, 'AutoApprove', ARRAY['Student'], 60, TRUE
+WHERE NOT EXISTS (SELECT 1 FROM approval_rules WHERE name = 'Auto-approve short weekday student bookings');
@@ -0,0 +1,28 @@
+-- Append-only audit history for bookings
+-- booking_id is deliberately not a foreign key so history survives booking deletion.
+
+CREATE TABLE IF NOT EXISTS booking_events (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    booking_id UUID NOT NULL,
+    event_type VARCHAR(50) NOT NULL CHECK (event_type IN ('Created', 'Updated', 'Approved', 'Rejected', 'Cancelled', 'Deleted')),
+    actor_id UUID REFERENCES users(id) ON DELETE SET NULL, -- NULL for system actions such as approval rules
+    note TEXT,
+    before JSONB, -- Booking snapshot before the change (NULL on create)
+    after JSONB, -- Booking snapshot after the change (NULL on delete)
+    changes JSONB NOT NULL DEFAULT '{}', -- Changed fields as {"field": {"from": ..., "to": ...}}
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_booking_events_booking ON booking_events(booking_id, created_at);
+
+-- Reject edits to recorded history
+CREATE OR REPLACE FUNCTION prevent_booking_event_changes()
+RETURNS TRIGGER AS $$
+BEGIN
+    RAISE EXCEPTION 'booking_events is append-only';
+END;
+$$ language 'plpgsql';
+
+DROP TRIGGER IF EXISTS booking_events_append_only ON booking_events;
+CREATE TRIGGER booking_events_append_only BEFORE UPDATE OR DELETE ON booking_events
+    FOR EACH ROW EXECUTE FUNCTION prevent_booking_event_changes();
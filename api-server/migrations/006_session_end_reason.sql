@@ -0,0 +1,5 @@
+-- Record who ended a session and why
+-- Staff can end or abort sessions belonging to other users.
+
+ALTER TABLE sessions ADD COLUMN IF NOT EXISTS ended_by UUID REFERENCES users(id) ON DELETE SET NULL;
+ALTER TABLE sessions ADD COLUMN IF NOT EXISTS end_reason TEXT;
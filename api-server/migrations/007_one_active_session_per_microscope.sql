@@ -0,0 +1,17 @@
+-- Only one active session may hold a microscope at a time
+-- Older duplicate active sessions are aborted so the unique index can be built.
+
+UPDATE sessions s
+SET status = 'Aborted',
+    ended_at = GREATEST(NOW(), s.started_at + INTERVAL '1 second'),
+    end_reason = 'Superseded by a newer active session on the same microscope'
+WHERE s.status = 'Active'
+  AND EXISTS (
+      SELECT 1 FROM sessions newer
+      WHERE newer.microscope_id = s.microscope_id
+        AND newer.status = 'Active'
+        AND (newer.started_at, newer.id) > (s.started_at, s.id)
+  );
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_sessions_one_active_per_microscope
+    ON sessions(microscope_id) WHERE status = 'Active';
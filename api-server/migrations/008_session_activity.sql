@@ -0,0 +1,5 @@
+-- Session activity tracking for automatic timeouts
+-- last_activity_at is bumped by microscope commands and captures.
+
+ALTER TABLE sessions ADD COLUMN IF NOT EXISTS last_activity_at TIMESTAMPTZ;
+ALTER TABLE sessions ADD COLUMN IF NOT EXISTS end_warning_sent_at TIMESTAMPTZ;
@@ -0,0 +1,3 @@
+-- Session presence: last heartbeat received from the UI of the session's user
+
+ALTER TABLE sessions ADD COLUMN IF NOT EXISTS last_seen_at TIMESTAMPTZ;
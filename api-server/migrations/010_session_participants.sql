@@ -0,0 +1,27 @@
+-- Session handover and co-participants
+-- sessions.user_id stays the current owner; everyone else who shares the session
+-- (co-participants and previous owners) is listed in session_participants.
+
+CREATE TABLE IF NOT EXISTS session_participants (
+    session_id UUID NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    added_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    added_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (session_id, user_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_session_participants_user ON session_participants(user_id);
+
+-- Record of every transfer and added participant
+CREATE TABLE IF NOT EXISTS session_handovers (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    session_id UUID NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+    kind VARCHAR(50) NOT NULL CHECK (kind IN ('Transfer', 'AddParticipant')),
+    from_user_id UUID REFERENCES users(id) ON DELETE SET NULL, -- Previous owner (NULL when adding a participant)
+    to_user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    performed_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    note TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_session_handovers_session ON session_handovers(session_id, created_at);
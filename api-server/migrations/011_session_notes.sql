@@ -0,0 +1,20 @@
+-- Lab notebook entries written during a session
+-- body is Markdown; it is rendered to HTML when served.
+
+CREATE TABLE IF NOT EXISTS session_notes (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    session_id UUID NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+    author_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    kind VARCHAR(50) NOT NULL CHECK (kind IN ('Text', 'Image', 'Measurement')),
+    body TEXT NOT NULL DEFAULT '',
+    image_id UUID REFERENCES images(id) ON DELETE SET NULL, -- Image the entry refers to (Image entries)
+    measurement JSONB, -- {"name": ..., "value": ..., "unit": ...} (Measurement entries)
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_session_notes_session ON session_notes(session_id, created_at);
+
+DROP TRIGGER IF EXISTS update_session_notes_updated_at ON session_notes;
+CREATE TRIGGER update_session_notes_updated_at BEFORE UPDATE ON session_notes
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
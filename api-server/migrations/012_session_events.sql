@@ -0,0 +1,42 @@
+-- Chronological log of what happened during a session
+-- Written by the session, microscope and notebook handlers and served as the session timeline.
+
+CREATE TABLE IF NOT EXISTS session_events (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    session_id UUID NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+    event_type VARCHAR(50) NOT NULL CHECK (event_type IN (
+        'SessionStarted', 'Command', 'Focus', 'TrackingStarted', 'TrackingStopped',
+        'Capture', 'NoteAdded', 'NoteUpdated', 'Handover', 'SessionEnded'
+    )),
+    actor_id UUID REFERENCES users(id) ON DELETE SET NULL, -- NULL for system actions such as the scheduler
+    details JSONB NOT NULL DEFAULT '{}',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_session_events_session ON session_events(session_id, created_at);
+
+-- Backfill what we know about sessions that predate the log
+INSERT INTO session_events (session_id, event_type, actor_id, details, created_at)
+SELECT s.id, 'SessionStarted', s.user_id,
+       jsonb_build_object('microscope_id', s.microscope_id, 'booking_id', s.booking_id),
+       s.started_at
+FROM sessions s
+WHERE NOT EXISTS (SELECT 1 FROM session_events e WHERE e.session_id = s.id);
+
+INSERT INTO session_events (session_id, event_type, details, created_at)
+SELECT i.session_id, 'Capture',
+       jsonb_build_object('image_id', i.id, 'filename', i.filename),
+       i.captured_at
+FROM images i
+WHERE NOT EXISTS (
+    SELECT 1 FROM session_events e
+    WHERE e.session_id = i.session_id AND e.event_type = 'Capture' AND e.details->>'image_id' = i.id::text
+);
+
+INSERT INTO session_events (session_id, event_type, actor_id, details, created_at)
+SELECT s.id, 'SessionEnded', s.ended_by,
+       jsonb_build_object('status', s.status, 'reason', s.end_reason),
+       s.ended_at
+FROM sessions s
+WHERE s.ended_at IS NOT NULL
+  AND NOT EXISTS (SELECT 1 FROM session_events e WHERE e.session_id = s.id AND e.event_type = 'SessionEnded');
@@ -0,0 +1,19 @@
+-- Checksums and reference counts for stored image files
+-- images.sha256 is NULL for images stored before checksums were recorded, until the
+-- integrity check hashes their files.
+
+ALTER TABLE images ADD COLUMN IF NOT EXISTS sha256 CHAR(64);
+
+CREATE INDEX IF NOT EXISTS idx_images_sha256 ON images(sha256);
+
+-- Number of images using each stored file. With content-addressed storage several images
+-- share one file, which may only be deleted once nothing references it.
+CREATE TABLE IF NOT EXISTS stored_files (
+    file_path VARCHAR(500) PRIMARY KEY,
+    ref_count INTEGER NOT NULL CHECK (ref_count > 0),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+INSERT INTO stored_files (file_path, ref_count)
+SELECT file_path, COUNT(*) FROM images GROUP BY file_path
+ON CONFLICT (file_path) DO NOTHING;
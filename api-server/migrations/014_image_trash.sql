@@ -0,0 +1,16 @@
+-- Soft-deleted images
+-- A deleted image stays in the trash, hidden from every listing, until it is restored or the
+-- purge job removes it and its file once IMAGE_TRASH_RETENTION_DAYS have passed.
+
+ALTER TABLE images ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ;
+ALTER TABLE images ADD COLUMN IF NOT EXISTS deleted_by UUID REFERENCES users(id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS idx_images_deleted_at ON images(deleted_at) WHERE deleted_at IS NOT NULL;
+
+-- Deleting and restoring images is recorded in the session timeline
+ALTER TABLE session_events DROP CONSTRAINT IF EXISTS session_events_event_type_check;
+ALTER TABLE session_events ADD CONSTRAINT session_events_event_type_check CHECK (event_type IN (
+    'SessionStarted', 'Command', 'Focus', 'TrackingStarted', 'TrackingStopped',
+    'Capture', 'NoteAdded', 'NoteUpdated', 'Handover', 'SessionEnded',
+    'ImageDeleted', 'ImageRestored'
+));
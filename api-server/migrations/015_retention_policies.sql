@@ -0,0 +1,50 @@
+-- Image retention policies
+-- The retention job evaluates enabled policies in priority order for every image; the first
+-- policy matching the image's session decides how long it is kept. Expired images are moved
+-- to the trash, from which the purge job deletes them and their files.
+
+CREATE TABLE IF NOT EXISTS retention_policies (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    name VARCHAR(255) NOT NULL,
+    priority INTEGER NOT NULL DEFAULT 100, -- Lower runs first
+    owner_roles TEXT[] NOT NULL DEFAULT '{}', -- Role of the session owner; empty matches any role
+    microscope_id VARCHAR(50) REFERENCES microscopes(id) ON DELETE CASCADE,
+    retain_days INTEGER CHECK (retain_days >= 0), -- NULL keeps matching images forever
+    after_term_end BOOLEAN NOT NULL DEFAULT FALSE, -- Count from the end of the capture's term
+    keep_starred BOOLEAN NOT NULL DEFAULT TRUE,
+    keep_exported BOOLEAN NOT NULL DEFAULT TRUE,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_retention_policies_priority ON retention_policies(priority) WHERE enabled;
+
+DROP TRIGGER IF EXISTS update_retention_policies_updated_at ON retention_policies;
+CREATE TRIGGER update_retention_policies_updated_at BEFORE UPDATE ON retention_policies
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+-- Teaching terms, for policies counted from the end of the term an image was captured in
+CREATE TABLE IF NOT EXISTS academic_terms (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    name VARCHAR(255) NOT NULL,
+    start_date DATE NOT NULL,
+    end_date DATE NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT academic_term_dates CHECK (end_date >= start_date)
+);
+
+CREATE INDEX IF NOT EXISTS idx_academic_terms_dates ON academic_terms(start_date, end_date);
+
+-- Images worth keeping regardless of retention
+ALTER TABLE images ADD COLUMN IF NOT EXISTS starred BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Last time the session was exported
+ALTER TABLE sessions ADD COLUMN IF NOT EXISTS exported_at TIMESTAMPTZ;
+
+-- Example policy, disabled until terms are set up
+INSERT INTO retention_policies (name, priority, owner_roles, retain_days, after_term_end, enabled)
+SELECT 'Delete student captures 180 days after term end', 100, ARRAY['Student'], 180, TRUE, FALSE
+WHERE NOT EXISTS (
+    SELECT 1 FROM retention_policies WHERE name = 'Delete student captures 180 days after term end'
+);
@@ -0,0 +1,10 @@
+-- Per-user storage quotas
+-- Captures and uploads count against the session owner, whose images (including those in the
+-- trash, which still take up space) may not exceed storage_quota_bytes. NULL uses the
+-- USER_STORAGE_QUOTA default.
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS storage_quota_bytes BIGINT;
+
+ALTER TABLE users DROP CONSTRAINT IF EXISTS users_storage_quota_bytes_check;
+ALTER TABLE users ADD CONSTRAINT users_storage_quota_bytes_check
+    CHECK (storage_quota_bytes IS NULL OR storage_quota_bytes >= 0);
@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 use std::{env, path::Path};
 
+use crate::services::signed_url;
+
 /// Application configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
@@ -9,6 +11,9 @@ pub struct Config {
     pub auth: AuthConfig,
     pub file_storage: FileStorageConfig,
     pub ia: IAConfig,
+    pub booking: BookingConfig,
+    pub scheduler: SchedulerConfig,
+    pub session: SessionConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -35,6 +40,45 @@ pub struct FileStorageConfig {
     pub base_path: String,
     pub max_file_size: u64, // in bytes
     pub allowed_types: Vec<String>,
+    pub url_signing_secret: String, // HMAC key for signed download URLs
+    pub signed_url_ttl: u64,        // in seconds
+    pub content_addressed: bool,    // store each distinct content once, under its SHA-256
+    pub backend: StorageBackendKind,
+    pub s3: S3Config,                  // only used by the S3 backend
+    pub trash_retention_days: i64,     // deleted images can be restored for this long
+    pub user_quota_bytes: Option<u64>, // default per-user storage quota; None is unlimited
+}
+
+/// Where uploaded files are stored
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackendKind {
+    Local,  // under `base_path` on the local filesystem
+    S3,     // in an S3-compatible bucket (AWS, MinIO, ...)
+    Memory, // in memory, lost on restart (development and testing)
+}
+
+impl std::str::FromStr for StorageBackendKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "local" => Ok(Self::Local),
+            "s3" => Ok(Self::S3),
+            "memory" => Ok(Self::Memory),
+            other => Err(format!("Unknown storage backend: {}", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct S3Config {
+    pub bucket: String,
+    pub region: String,
+    pub endpoint: Option<String>, // for S3-compatible services such as MinIO
+    pub access_key_id: Option<String>,
+    pub secret_access_key: Option<String>,
+    pub allow_http: bool, // allow a plain-HTTP endpoint
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -45,6 +89,25 @@ pub struct IAConfig {
     pub mock_mode: bool, // Enable mock mode for development/testing
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BookingConfig {
+    pub timezone: String, // IANA name used to interpret booking dates and slots
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchedulerConfig {
+    pub interval: u64,                    // in seconds
+    pub session_end_warning_minutes: i64, // warn this long before a booking ends
+    pub session_grace_minutes: i64,       // end sessions this long after their booking ends
+    pub session_idle_minutes: i64,        // abort sessions idle this long (0 disables)
+    pub storage_interval: u64,            // in seconds, for storage maintenance jobs (0 disables)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionConfig {
+    pub presence_timeout: i64, // seconds since the last heartbeat before a user counts as away
+}
+
 impl Config {
     /// Load configuration from environment variables
     pub fn from_env() -> Result<Self, Box<dyn std::error::Error>> {
@@ -89,6 +152,39 @@ impl Config {
                 "image/tiff".to_string(),
                 "image/bmp".to_string(),
             ],
+            url_signing_secret: env::var("FILE_URL_SECRET")
+                .unwrap_or_else(|_| signed_url::derive_secret(&auth.jwt_secret)),
+            signed_url_ttl: env::var("FILE_URL_TTL")
+                .unwrap_or_else(|_| "300".to_string()) // 5 minutes
+                .parse()?,
+            content_addressed: env::var("FILE_STORAGE_CONTENT_ADDRESSED")
+                .unwrap_or_else(|_| "false".to_string())
+                .parse()
+                .unwrap_or(false),
+            backend: env::var("FILE_STORAGE_BACKEND")
+                .unwrap_or_else(|_| "local".to_string())
+                .parse()?,
+            s3: S3Config {
+                bucket: env::var("S3_BUCKET").unwrap_or_default(),
+                region: env::var("S3_REGION").unwrap_or_else(|_| "us-east-1".to_string()),
+                endpoint: env::var("S3_ENDPOINT").ok(),
+                access_key_id: env::var("S3_ACCESS_KEY_ID").ok(),
+                secret_access_key: env::var("S3_SECRET_ACCESS_KEY").ok(),
+                allow_http: env::var("S3_ALLOW_HTTP")
+                    .unwrap_or_else(|_| "false".to_string())
+                    .parse()
+                    .unwrap_or(false),
+            },
+            trash_retention_days: env::var("IMAGE_TRASH_RETENTION_DAYS")
+                .unwrap_or_else(|_| "30".to_string())
+                .parse()?,
+            user_quota_bytes: match env::var("USER_STORAGE_QUOTA")
+                .unwrap_or_else(|_| "0".to_string()) // unlimited
+                .parse()?
+            {
+                0 => None,
+                quota => Some(quota),
+            },
         };
 
         let ia = IAConfig {
@@ -104,12 +200,46 @@ impl Config {
                 .unwrap_or(false),
         };
 
+        let timezone = env::var("LAB_TIMEZONE").unwrap_or_else(|_| "UTC".to_string());
+        timezone
+            .parse::<chrono_tz::Tz>()
+            .map_err(|_| format!("Invalid LAB_TIMEZONE: {}", timezone))?;
+
+        let booking = BookingConfig { timezone };
+
+        let scheduler = SchedulerConfig {
+            interval: env::var("SCHEDULER_INTERVAL")
+                .unwrap_or_else(|_| "60".to_string())
+                .parse()?,
+            session_end_warning_minutes: env::var("SESSION_END_WARNING_MINUTES")
+                .unwrap_or_else(|_| "10".to_string())
+                .parse()?,
+            session_grace_minutes: env::var("SESSION_GRACE_MINUTES")
+                .unwrap_or_else(|_| "5".to_string())
+                .parse()?,
+            session_idle_minutes: env::var("SESSION_IDLE_MINUTES")
+                .unwrap_or_else(|_| "30".to_string())
+                .parse()?,
+            storage_interval: env::var("STORAGE_JOBS_INTERVAL")
+                .unwrap_or_else(|_| "86400".to_string()) // daily
+                .parse()?,
+        };
+
+        let session = SessionConfig {
+            presence_timeout: env::var("SESSION_PRESENCE_TIMEOUT")
+                .unwrap_or_else(|_| "90".to_string())
+                .parse()?,
+        };
+
         Ok(Config {
             server,
             database,
             auth,
             file_storage,
             ia,
+            booking,
+            scheduler,
+            session,
         })
     }
 }
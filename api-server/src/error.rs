@@ -5,6 +5,8 @@ use axum::{
 use serde_json::json;
 use thiserror::Error;
 
+use crate::services::file_storage::FileStorageError;
+
 /// Main application error type
 #[derive(Error, Debug)]
 pub enum AppError {
@@ -21,7 +23,7 @@ pub enum AppError {
     Validation(String),
 
     #[error("File storage error: {0}")]
-    FileStorage(#[from] crate::services::file_storage::FileStorageError),
+    FileStorage(#[from] FileStorageError),
 
     #[error("IA client error: {0}")]
     IAClient(#[from] crate::services::ia_client::IAClientError),
@@ -63,6 +65,13 @@ impl AppError {
             AppError::Validation(_) | AppError::BadRequest(_) => StatusCode::BAD_REQUEST,
             AppError::NotFound(_) => StatusCode::NOT_FOUND,
             AppError::Conflict(_) => StatusCode::CONFLICT,
+            AppError::FileStorage(FileStorageError::FileTooLarge(..)) => {
+                StatusCode::PAYLOAD_TOO_LARGE
+            }
+            AppError::FileStorage(FileStorageError::InvalidFileType(_)) => {
+                StatusCode::UNSUPPORTED_MEDIA_TYPE
+            }
+            AppError::FileStorage(FileStorageError::QuotaExceeded(..)) => StatusCode::FORBIDDEN,
             AppError::Database(_)
             | AppError::FileStorage(_)
             | AppError::IAClient(_)
@@ -104,7 +113,10 @@ impl AppError {
             | AppError::Validation(_)
             | AppError::NotFound(_)
             | AppError::Conflict(_)
-            | AppError::BadRequest(_) => false, // These are expected client errors
+            | AppError::BadRequest(_)
+            | AppError::FileStorage(FileStorageError::FileTooLarge(..))
+            | AppError::FileStorage(FileStorageError::InvalidFileType(_))
+            | AppError::FileStorage(FileStorageError::QuotaExceeded(..)) => false, // These are expected client errors
             _ => true, // Server errors should be logged as errors
         }
     }
@@ -140,12 +152,19 @@ impl IntoResponse for AppError {
             _ => self.to_string(),
         };
 
-        let body = json!({
+        let mut body = json!({
             "success": false,
             "error": error_message,
             "code": status_code.as_u16(),
         });
 
+        // Let clients show how much space is left without parsing the message
+        if let AppError::FileStorage(FileStorageError::QuotaExceeded(used, limit, size)) = self {
+            body["used_bytes"] = json!(used);
+            body["limit_bytes"] = json!(limit);
+            body["file_bytes"] = json!(size);
+        }
+
         (status_code, Json(body)).into_response()
     }
 }
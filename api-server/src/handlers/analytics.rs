@@ -0,0 +1,273 @@
+use axum::{
+    extract::{Query, State},
+    response::Json,
+    Extension,
+};
+use chrono::{Duration, NaiveDate, Utc};
+use serde::Deserialize;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{
+    middleware::auth::Claims,
+    models::{
+        ApiResponse, DailyUsage, HourlyDemand, Microscope, MicroscopeUsage, RequesterUsage,
+        UsageSummary, UserRole,
+    },
+    services::{analytics, database::AnalyticsFilter},
+    AppError, AppState,
+};
+
+/// Days covered when no `from` is given
+const DEFAULT_PERIOD_DAYS: i64 = 30;
+/// Longest period a single request may cover
+const MAX_PERIOD_DAYS: i64 = 366;
+
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct AnalyticsQuery {
+    /// First day (lab-local), inclusive; defaults to 30 days before `to`
+    #[schema(example = "2024-01-01", format = "date")]
+    pub from: Option<String>,
+    /// Last day (lab-local), inclusive; defaults to today
+    #[schema(example = "2024-01-31", format = "date")]
+    pub to: Option<String>,
+    #[schema(example = "bio-1")]
+    pub microscope_id: Option<String>,
+    /// Booking group name
+    #[schema(example = "Biology 101")]
+    pub group: Option<String>,
+    /// Number of requesters to return (top requesters only)
+    #[schema(example = 10)]
+    pub limit: Option<i64>,
+}
+
+fn require_staff(claims: &Claims) -> Result<(), AppError> {
+    match claims.role {
+        UserRole::Teacher | UserRole::Admin => Ok(()),
+        UserRole::Student => Err(AppError::Authorization(
+            "Only teachers and admins can view analytics".to_string(),
+        )),
+    }
+}
+
+fn parse_date(name: &str, value: Option<&str>) -> Result<Option<NaiveDate>, AppError> {
+    value
+        .map(|raw| {
+            NaiveDate::parse_from_str(raw, "%Y-%m-%d")
+                .map_err(|_| AppError::BadRequest(format!("Invalid {} date: {}", name, raw)))
+        })
+        .transpose()
+}
+
+/// Check the caller and resolve the reporting period and filters
+fn resolve_filter<'a>(
+    state: &'a AppState,
+    claims: &Claims,
+    query: &'a AnalyticsQuery,
+) -> Result<AnalyticsFilter<'a>, AppError> {
+    require_staff(claims)?;
+
+    let to = parse_date("to", query.to.as_deref())?.unwrap_or_else(|| Utc::now().date_naive());
+    let from = parse_date("from", query.from.as_deref())?
+        .unwrap_or(to - Duration::days(DEFAULT_PERIOD_DAYS - 1));
+
+    if from > to {
+        return Err(AppError::BadRequest(
+            "from must not be after to".to_string(),
+        ));
+    }
+    if period_days(from, to) > MAX_PERIOD_DAYS {
+        return Err(AppError::BadRequest(format!(
+            "Period cannot be longer than {} days",
+            MAX_PERIOD_DAYS
+        )));
+    }
+
+    Ok(AnalyticsFilter {
+        from,
+        to,
+        microscope_id: query.microscope_id.as_deref(),
+        group_name: query.group.as_deref(),
+        timezone: &state.config.booking.timezone,
+    })
+}
+
+fn period_days(from: NaiveDate, to: NaiveDate) -> i64 {
+    (to - from).num_days() + 1
+}
+
+/// Microscopes the capacity is computed over
+async fn microscopes_in_scope(
+    state: &AppState,
+    filter: &AnalyticsFilter<'_>,
+) -> Result<Vec<Microscope>, AppError> {
+    let microscopes = state.db.list_microscopes().await?;
+
+    match filter.microscope_id {
+        Some(id) => {
+            let microscopes: Vec<Microscope> =
+                microscopes.into_iter().filter(|m| m.id == id).collect();
+            if microscopes.is_empty() {
+                return Err(AppError::NotFound(format!("Microscope {} not found", id)));
+            }
+            Ok(microscopes)
+        }
+        None => Ok(microscopes),
+    }
+}
+
+/// Usage totals for the period: booked vs used minutes, no-shows and captures
+#[utoipa::path(
+    get,
+    path = "/api/analytics/summary",
+    tag = "analytics",
+    params(AnalyticsQuery),
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Usage totals", body = ApiResponse<UsageSummary>),
+        (status = 400, description = "Invalid period", body = ApiResponse<String>),
+        (status = 403, description = "Teacher or admin only", body = ApiResponse<String>),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn get_usage_summary(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Query(query): Query<AnalyticsQuery>,
+) -> Result<Json<ApiResponse<UsageSummary>>, AppError> {
+    let filter = resolve_filter(&state, &claims, &query)?;
+    let microscopes = microscopes_in_scope(&state, &filter).await?;
+    let rows = state.db.get_usage_by_microscope_day(&filter).await?;
+
+    Ok(Json(ApiResponse::success(UsageSummary {
+        from: filter.from,
+        to: filter.to,
+        totals: analytics::summarize(
+            &rows,
+            microscopes.len(),
+            period_days(filter.from, filter.to),
+        ),
+    })))
+}
+
+/// Utilization per day of the period
+#[utoipa::path(
+    get,
+    path = "/api/analytics/daily",
+    tag = "analytics",
+    params(AnalyticsQuery),
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Usage per day, including days without activity", body = ApiResponse<Vec<DailyUsage>>),
+        (status = 400, description = "Invalid period", body = ApiResponse<String>),
+        (status = 403, description = "Teacher or admin only", body = ApiResponse<String>),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn get_daily_usage(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Query(query): Query<AnalyticsQuery>,
+) -> Result<Json<ApiResponse<Vec<DailyUsage>>>, AppError> {
+    let filter = resolve_filter(&state, &claims, &query)?;
+    let microscopes = microscopes_in_scope(&state, &filter).await?;
+    let rows = state.db.get_usage_by_microscope_day(&filter).await?;
+
+    Ok(Json(ApiResponse::success(analytics::daily(
+        &rows,
+        filter.from,
+        filter.to,
+        microscopes.len(),
+    ))))
+}
+
+/// Utilization per microscope over the period
+#[utoipa::path(
+    get,
+    path = "/api/analytics/microscopes",
+    tag = "analytics",
+    params(AnalyticsQuery),
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Usage per microscope", body = ApiResponse<Vec<MicroscopeUsage>>),
+        (status = 400, description = "Invalid period", body = ApiResponse<String>),
+        (status = 403, description = "Teacher or admin only", body = ApiResponse<String>),
+        (status = 404, description = "Microscope not found", body = ApiResponse<String>),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn get_microscope_usage(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Query(query): Query<AnalyticsQuery>,
+) -> Result<Json<ApiResponse<Vec<MicroscopeUsage>>>, AppError> {
+    let filter = resolve_filter(&state, &claims, &query)?;
+    let microscopes = microscopes_in_scope(&state, &filter).await?;
+    let rows = state.db.get_usage_by_microscope_day(&filter).await?;
+
+    Ok(Json(ApiResponse::success(analytics::by_microscope(
+        &rows,
+        &microscopes,
+        period_days(filter.from, filter.to),
+    ))))
+}
+
+/// Booking demand by hour of the day
+#[utoipa::path(
+    get,
+    path = "/api/analytics/hourly",
+    tag = "analytics",
+    params(AnalyticsQuery),
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Bookings overlapping each hour (0-23)", body = ApiResponse<Vec<HourlyDemand>>),
+        (status = 400, description = "Invalid period", body = ApiResponse<String>),
+        (status = 403, description = "Teacher or admin only", body = ApiResponse<String>),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn get_hourly_demand(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Query(query): Query<AnalyticsQuery>,
+) -> Result<Json<ApiResponse<Vec<HourlyDemand>>>, AppError> {
+    let filter = resolve_filter(&state, &claims, &query)?;
+    let demand = state.db.get_hourly_booking_demand(&filter).await?;
+
+    Ok(Json(ApiResponse::success(demand)))
+}
+
+/// Users making the most booking requests
+#[utoipa::path(
+    get,
+    path = "/api/analytics/requesters",
+    tag = "analytics",
+    params(AnalyticsQuery),
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Top requesters by number of bookings", body = ApiResponse<Vec<RequesterUsage>>),
+        (status = 400, description = "Invalid period", body = ApiResponse<String>),
+        (status = 403, description = "Teacher or admin only", body = ApiResponse<String>),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn get_top_requesters(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Query(query): Query<AnalyticsQuery>,
+) -> Result<Json<ApiResponse<Vec<RequesterUsage>>>, AppError> {
+    let filter = resolve_filter(&state, &claims, &query)?;
+    let limit = query.limit.unwrap_or(10).clamp(1, 50);
+    let requesters = state.db.get_top_requesters(&filter, limit).await?;
+
+    Ok(Json(ApiResponse::success(requesters)))
+}
@@ -0,0 +1,216 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+    Extension,
+};
+use chrono::Utc;
+use serde::Deserialize;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{
+    middleware::auth::{ensure_admin, Claims},
+    models::{ApiResponse, ApprovalAction, ApprovalRule, UserRole},
+    AppError, AppState,
+};
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ApprovalRuleRequest {
+    #[schema(example = "Auto-approve short weekday student bookings")]
+    pub name: String,
+    /// Rules are evaluated in ascending priority; the first match decides
+    #[schema(example = 30)]
+    pub priority: i32,
+    pub action: ApprovalAction,
+    /// Roles the rule applies to (empty matches any role)
+    #[serde(default)]
+    pub requester_roles: Vec<UserRole>,
+    /// Match bookings shorter than this many minutes
+    #[schema(example = 60)]
+    pub duration_under_minutes: Option<i32>,
+    /// Match bookings longer than this many minutes
+    pub duration_over_minutes: Option<i32>,
+    /// Only match bookings on Monday to Friday
+    #[serde(default)]
+    pub weekdays_only: bool,
+    /// Only match bookings for this microscope
+    #[schema(example = "bio-1")]
+    pub microscope_id: Option<String>,
+    #[schema(example = true)]
+    pub enabled: Option<bool>,
+}
+
+fn validate_rule(request: &ApprovalRuleRequest) -> Result<(), AppError> {
+    if request.name.trim().is_empty() {
+        return Err(AppError::BadRequest("Rule name is required".to_string()));
+    }
+
+    if let (Some(under), Some(over)) = (
+        request.duration_under_minutes,
+        request.duration_over_minutes,
+    ) {
+        if under <= over + 1 {
+            return Err(AppError::BadRequest(
+                "Duration bounds can never match a booking".to_string(),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn build_rule(id: Uuid, request: ApprovalRuleRequest) -> ApprovalRule {
+    ApprovalRule {
+        id,
+        name: request.name,
+        priority: request.priority,
+        action: request.action,
+        requester_roles: request.requester_roles,
+        duration_under_minutes: request.duration_under_minutes,
+        duration_over_minutes: request.duration_over_minutes,
+        weekdays_only: request.weekdays_only,
+        microscope_id: request.microscope_id,
+        enabled: request.enabled.unwrap_or(true),
+        created_at: Utc::now(),
+    }
+}
+
+/// List approval rules in evaluation order (teacher/admin only)
+#[utoipa::path(
+    get,
+    path = "/api/approval-rules",
+    tag = "approval-rules",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Approval rules ordered by priority", body = ApiResponse<Vec<ApprovalRule>>),
+        (status = 403, description = "Insufficient permissions", body = ApiResponse<String>),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn list_approval_rules(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<ApiResponse<Vec<ApprovalRule>>>, AppError> {
+    match claims.role {
+        UserRole::Teacher | UserRole::Admin => {}
+        UserRole::Student => {
+            return Err(AppError::Authorization(
+                "Only teachers and admins can view approval rules".to_string(),
+            ))
+        }
+    }
+
+    let rules = state.db.list_approval_rules().await?;
+    Ok(Json(ApiResponse::success(rules)))
+}
+
+/// Create an approval rule (admin only)
+#[utoipa::path(
+    post,
+    path = "/api/approval-rules",
+    tag = "approval-rules",
+    request_body = ApprovalRuleRequest,
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Approval rule created", body = ApiResponse<ApprovalRule>),
+        (status = 400, description = "Invalid rule", body = ApiResponse<String>),
+        (status = 403, description = "Insufficient permissions", body = ApiResponse<String>),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn create_approval_rule(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(request): Json<ApprovalRuleRequest>,
+) -> Result<Json<ApiResponse<ApprovalRule>>, AppError> {
+    ensure_admin(&claims, "manage approval rules")?;
+    validate_rule(&request)?;
+
+    let rule = state
+        .db
+        .upsert_approval_rule(&build_rule(Uuid::new_v4(), request))
+        .await?;
+
+    tracing::info!("Created approval rule {} ({})", rule.id, rule.name);
+    Ok(Json(ApiResponse::success(rule)))
+}
+
+/// Replace an approval rule (admin only)
+#[utoipa::path(
+    put,
+    path = "/api/approval-rules/{id}",
+    tag = "approval-rules",
+    params(
+        ("id" = Uuid, Path, description = "Approval rule ID")
+    ),
+    request_body = ApprovalRuleRequest,
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Approval rule updated", body = ApiResponse<ApprovalRule>),
+        (status = 400, description = "Invalid rule", body = ApiResponse<String>),
+        (status = 403, description = "Insufficient permissions", body = ApiResponse<String>),
+        (status = 404, description = "Approval rule not found", body = ApiResponse<String>),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn update_approval_rule(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(rule_id): Path<Uuid>,
+    Json(request): Json<ApprovalRuleRequest>,
+) -> Result<Json<ApiResponse<ApprovalRule>>, AppError> {
+    ensure_admin(&claims, "manage approval rules")?;
+    validate_rule(&request)?;
+
+    if !state.db.approval_rule_exists(rule_id).await? {
+        return Err(AppError::NotFound("Approval rule not found".to_string()));
+    }
+
+    let rule = state
+        .db
+        .upsert_approval_rule(&build_rule(rule_id, request))
+        .await?;
+
+    tracing::info!("Updated approval rule {} ({})", rule.id, rule.name);
+    Ok(Json(ApiResponse::success(rule)))
+}
+
+/// Delete an approval rule (admin only)
+#[utoipa::path(
+    delete,
+    path = "/api/approval-rules/{id}",
+    tag = "approval-rules",
+    params(
+        ("id" = Uuid, Path, description = "Approval rule ID")
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 204, description = "Approval rule deleted"),
+        (status = 403, description = "Insufficient permissions", body = ApiResponse<String>),
+        (status = 404, description = "Approval rule not found", body = ApiResponse<String>),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn delete_approval_rule(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(rule_id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    ensure_admin(&claims, "manage approval rules")?;
+
+    if state.db.delete_approval_rule(rule_id).await? == 0 {
+        return Err(AppError::NotFound("Approval rule not found".to_string()));
+    }
+
+    tracing::info!("Deleted approval rule {}", rule_id);
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -1,17 +1,24 @@
+use std::collections::HashMap;
+
 use axum::{
     extract::{Path, Query, State},
     http::StatusCode,
     response::Json,
     Extension,
 };
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 use validator::Validate;
 
 use crate::{
     middleware::auth::Claims,
-    models::{ApiResponse, Booking, BookingStatus, UserRole},
+    models::{
+        ApiResponse, ApprovalAction, ApprovalDecision, Booking, BookingEvent, BookingEventType,
+        BookingStatus, Microscope, MicroscopeType, PageRequest, Paginated, RequiredCapabilities,
+        UserRole,
+    },
+    services::{approval_rules, database::BookingFilter},
     AppError, AppState,
 };
 
@@ -38,6 +45,8 @@ pub struct CreateBookingRequest {
     pub group_name: Option<String>,
     #[schema(example = 4)]
     pub attendees: Option<i32>,
+    /// Equipment the chosen microscope must provide
+    pub required_capabilities: Option<RequiredCapabilities>,
 }
 
 #[derive(Debug, Deserialize, ToSchema)]
@@ -48,7 +57,47 @@ pub struct UpdateBookingRequest {
     pub group_name: Option<String>,
     #[schema(example = 6)]
     pub attendees: Option<i32>,
+    /// Not accepted here: use `POST /api/bookings/{id}/approve` or `/reject`
+    pub status: Option<BookingStatus>,
+}
+
+/// Maximum number of bookings accepted by one bulk decision request
+const MAX_BULK_DECISIONS: usize = 500;
+
+#[derive(Debug, Clone, Copy, Deserialize, ToSchema)]
+pub enum BookingDecision {
+    Approve,
+    Reject,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct BulkBookingDecisionRequest {
+    pub booking_ids: Vec<Uuid>,
+    pub decision: BookingDecision,
+    /// Stored as the rejection reason and in the audit history
+    #[schema(example = "Lab closed for exams")]
+    pub reason: Option<String>,
+    /// Roll back the whole batch if any booking cannot be decided (default: false)
+    #[schema(example = false)]
+    pub atomic: Option<bool>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BookingDecisionResult {
+    pub booking_id: Uuid,
+    pub success: bool,
+    /// Booking status after the batch
     pub status: Option<BookingStatus>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BulkBookingDecisionResponse {
+    pub results: Vec<BookingDecisionResult>,
+    pub succeeded: usize,
+    pub failed: usize,
+    /// True when `atomic` was set and a failure discarded the whole batch
+    pub rolled_back: bool,
 }
 
 #[derive(Debug, Deserialize, ToSchema, IntoParams)]
@@ -65,6 +114,22 @@ pub struct BookingQuery {
     pub page: Option<u64>,
     #[schema(example = 20)]
     pub limit: Option<u64>,
+    /// Cursor from a previous page's `next_cursor`; pass it empty to start paging by cursor.
+    /// Cursor pages are in creation order and cannot be combined with `page`.
+    pub cursor: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct AvailabilityQuery {
+    #[schema(example = "2024-01-15", format = "date")]
+    pub date: String,
+    #[schema(example = 540)]
+    pub slot_start: i32,
+    #[schema(example = 600)]
+    pub slot_end: i32,
+    #[schema(example = 1000)]
+    pub min_magnification: Option<u32>,
+    pub microscope_type: Option<MicroscopeType>,
 }
 
 /// List bookings with filtering
@@ -77,7 +142,8 @@ pub struct BookingQuery {
         ("bearer_auth" = [])
     ),
     responses(
-        (status = 200, description = "List of bookings", body = ApiResponse<Vec<Booking>>),
+        (status = 200, description = "List of bookings", body = ApiResponse<Paginated<Booking>>),
+        (status = 400, description = "Invalid date, page or cursor", body = ApiResponse<String>),
         (status = 401, description = "Unauthorized")
     )
 )]
@@ -85,42 +151,50 @@ pub async fn list_bookings(
     State(state): State<AppState>,
     Extension(claims): Extension<Claims>,
     Query(query): Query<BookingQuery>,
-) -> Result<Json<ApiResponse<Vec<Booking>>>, AppError> {
-    let bookings = if let Some(user_id) = query.user_id {
+) -> Result<Json<ApiResponse<Paginated<Booking>>>, AppError> {
+    let page = PageRequest::new(query.page, query.limit, query.cursor.as_deref(), 20, 100)
+        .map_err(AppError::BadRequest)?;
+
+    let filter = if let Some(user_id) = query.user_id {
         // Get bookings for specific user (if admin/teacher or own bookings)
         match claims.role {
-            UserRole::Admin | UserRole::Teacher => state.db.get_bookings_by_user(user_id).await?,
+            UserRole::Admin | UserRole::Teacher => {}
             UserRole::Student => {
-                if user_id == claims.user_id {
-                    state.db.get_bookings_by_user(user_id).await?
-                } else {
+                if user_id != claims.user_id {
                     return Err(AppError::Authorization(
                         "Cannot view other users' bookings".to_string(),
                     ));
                 }
             }
         }
+        BookingFilter {
+            requester_id: Some(user_id),
+            ..Default::default()
+        }
     } else if let (Some(microscope_id), Some(date_str)) = (&query.microscope_id, &query.date) {
         // Get bookings by microscope and date
         let date = chrono::NaiveDate::parse_from_str(date_str, "%Y-%m-%d")
             .map_err(|_| AppError::BadRequest("Invalid date format".to_string()))?;
-        state
-            .db
-            .get_bookings_by_date_and_microscope(microscope_id, date)
-            .await?
+        BookingFilter {
+            microscope_id: Some(microscope_id),
+            date: Some(date),
+            ..Default::default()
+        }
     } else {
         // For students, only show their own bookings unless they specify microscope+date
-        match claims.role {
-            UserRole::Student => state.db.get_bookings_by_user(claims.user_id).await?,
-            UserRole::Teacher | UserRole::Admin => {
-                // For now, return user's own bookings by default
-                // TODO: Implement full booking listing for admins/teachers
-                state.db.get_bookings_by_user(claims.user_id).await?
-            }
+        // For now, teachers and admins also get their own bookings by default
+        // TODO: Implement full booking listing for admins/teachers
+        BookingFilter {
+            requester_id: Some(claims.user_id),
+            ..Default::default()
         }
     };
 
-    Ok(Json(ApiResponse::success(bookings)))
+    let (bookings, total, next_cursor) = state.db.list_bookings(&filter, &page).await?;
+
+    Ok(Json(ApiResponse::success(
+        Paginated::new(bookings, total, page).with_next_cursor(next_cursor),
+    )))
 }
 
 /// Create new booking
@@ -152,6 +226,43 @@ pub async fn create_booking(
     let date = chrono::NaiveDate::parse_from_str(&request.date, "%Y-%m-%d")
         .map_err(|_| AppError::BadRequest("Invalid date format".to_string()))?;
 
+    let microscope = state
+        .db
+        .get_microscope_by_id(&request.microscope_id)
+        .await?
+        .ok_or(AppError::NotFound("Microscope not found".to_string()))?;
+
+    // Check the microscope provides the requested equipment
+    if let Some(capabilities) = &request.required_capabilities {
+        let unmet = capabilities.unmet_by(&microscope.specs);
+        if !unmet.is_empty() {
+            let alternatives: Vec<String> = find_available_microscopes(
+                &state,
+                date,
+                request.slot_start,
+                request.slot_end,
+                capabilities,
+            )
+            .await?
+            .into_iter()
+            .map(|m| m.id)
+            .collect();
+
+            let suggestion = if alternatives.is_empty() {
+                "No suitable microscope is free for this slot".to_string()
+            } else {
+                format!("Available alternatives: {}", alternatives.join(", "))
+            };
+
+            return Ok(Json(ApiResponse::error(format!(
+                "Microscope {} does not meet the required capabilities ({}). {}",
+                microscope.id,
+                unmet.join(", "),
+                suggestion
+            ))));
+        }
+    }
+
     // Check for conflicts
     let has_conflicts = state
         .db
@@ -170,6 +281,24 @@ pub async fn create_booking(
         )));
     }
 
+    // Check the microscope is not scheduled for maintenance
+    if let Some(window) = state
+        .db
+        .find_maintenance_conflict(
+            &request.microscope_id,
+            date,
+            request.slot_start,
+            request.slot_end,
+            &state.config.booking.timezone,
+        )
+        .await?
+    {
+        return Ok(Json(ApiResponse::error(format!(
+            "Microscope is unavailable ({:?}) from {} to {}",
+            window.kind, window.starts_at, window.ends_at
+        ))));
+    }
+
     // Get user information (fallback to claims if not present in DB)
     let user = state
         .db
@@ -177,7 +306,7 @@ pub async fn create_booking(
         .await?
         .ok_or(AppError::NotFound("User not found".to_string()))?;
 
-    let booking = Booking {
+    let mut booking = Booking {
         id: Uuid::new_v4(),
         microscope_id: request.microscope_id,
         date,
@@ -190,15 +319,146 @@ pub async fn create_booking(
         requester_name: user.name,
         status: BookingStatus::Pending,
         approved_by: None,
+        required_capabilities: request.required_capabilities,
         created_at: chrono::Utc::now().into(),
     };
 
-    // Save to database
-    let created_booking = state.db.create_booking(&booking).await?;
+    // Apply the first matching approval rule
+    let rules = state.db.list_approval_rules().await?;
+    let outcome = approval_rules::evaluate(&rules, &booking, claims.role);
+    if outcome.action == ApprovalAction::AutoApprove {
+        booking.status = BookingStatus::Approved;
+    }
+
+    // Save the booking with its approval decision and Created event, or none of them
+    let mut tx = state.db.begin_transaction().await?;
+    let created_booking = state.db.create_booking(&mut tx, &booking).await?;
+
+    state
+        .db
+        .record_approval_decision(
+            &mut tx,
+            created_booking.id,
+            outcome.rule_id,
+            &outcome.rule_name,
+            outcome.action,
+        )
+        .await?;
+
+    state
+        .db
+        .record_booking_event_tx(
+            &mut tx,
+            created_booking.id,
+            BookingEventType::Created,
+            Some(claims.user_id),
+            Some(&format!("Approval rule: {}", outcome.rule_name)),
+            None,
+            Some(&created_booking),
+        )
+        .await?;
+
+    tx.commit().await?;
+
+    tracing::info!(
+        "Booking {} evaluated by rule \"{}\": {:?}",
+        created_booking.id,
+        outcome.rule_name,
+        outcome.action
+    );
 
     Ok(Json(ApiResponse::success(created_booking)))
 }
 
+/// Search microscopes that are free for a slot and meet the requested specs
+#[utoipa::path(
+    get,
+    path = "/api/bookings/availability",
+    tag = "bookings",
+    params(AvailabilityQuery),
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Microscopes available for the slot", body = ApiResponse<Vec<Microscope>>),
+        (status = 400, description = "Invalid date or slot", body = ApiResponse<String>),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn search_availability(
+    State(state): State<AppState>,
+    Query(query): Query<AvailabilityQuery>,
+) -> Result<Json<ApiResponse<Vec<Microscope>>>, AppError> {
+    let date = chrono::NaiveDate::parse_from_str(&query.date, "%Y-%m-%d")
+        .map_err(|_| AppError::BadRequest("Invalid date format".to_string()))?;
+
+    if query.slot_end <= query.slot_start {
+        return Err(AppError::BadRequest(
+            "slot_end must be after slot_start".to_string(),
+        ));
+    }
+
+    let capabilities = RequiredCapabilities {
+        min_magnification: query.min_magnification,
+        microscope_type: query.microscope_type,
+    };
+
+    let microscopes = find_available_microscopes(
+        &state,
+        date,
+        query.slot_start,
+        query.slot_end,
+        &capabilities,
+    )
+    .await?;
+
+    Ok(Json(ApiResponse::success(microscopes)))
+}
+
+/// Microscopes meeting `capabilities` with no booking or maintenance overlapping the slot
+async fn find_available_microscopes(
+    state: &AppState,
+    date: chrono::NaiveDate,
+    slot_start: i32,
+    slot_end: i32,
+    capabilities: &RequiredCapabilities,
+) -> Result<Vec<Microscope>, AppError> {
+    let mut available = Vec::new();
+
+    for microscope in state.db.list_microscopes().await? {
+        if !capabilities.is_met_by(&microscope.specs) {
+            continue;
+        }
+
+        if state
+            .db
+            .check_booking_conflicts(&microscope.id, date, slot_start, slot_end, None)
+            .await?
+        {
+            continue;
+        }
+
+        if state
+            .db
+            .find_maintenance_conflict(
+                &microscope.id,
+                date,
+                slot_start,
+                slot_end,
+                &state.config.booking.timezone,
+            )
+            .await?
+            .is_some()
+        {
+            continue;
+        }
+
+        available.push(microscope);
+    }
+
+    Ok(available)
+}
+
 /// Get booking by ID
 #[utoipa::path(
     get,
@@ -243,6 +503,50 @@ pub async fn get_booking(
     }
 }
 
+/// Get the approval decisions recorded for a booking
+#[utoipa::path(
+    get,
+    path = "/api/bookings/{id}/approval",
+    tag = "bookings",
+    params(
+        ("id" = Uuid, Path, description = "Booking ID")
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Approval decisions, oldest first", body = ApiResponse<Vec<ApprovalDecision>>),
+        (status = 403, description = "Cannot view other users' bookings", body = ApiResponse<String>),
+        (status = 404, description = "Booking not found", body = ApiResponse<String>),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn get_booking_approval(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(booking_id): Path<Uuid>,
+) -> Result<Json<ApiResponse<Vec<ApprovalDecision>>>, AppError> {
+    let booking = state
+        .db
+        .get_booking_by_id(booking_id)
+        .await?
+        .ok_or(AppError::NotFound("Booking not found".to_string()))?;
+
+    match claims.role {
+        UserRole::Admin | UserRole::Teacher => {}
+        UserRole::Student => {
+            if booking.requester_id != claims.user_id {
+                return Err(AppError::Authorization(
+                    "Cannot view other users' bookings".to_string(),
+                ));
+            }
+        }
+    }
+
+    let decisions = state.db.get_approval_decisions(booking_id).await?;
+    Ok(Json(ApiResponse::success(decisions)))
+}
+
 /// Update booking
 #[utoipa::path(
     put,
@@ -257,23 +561,79 @@ pub async fn get_booking(
     ),
     responses(
         (status = 200, description = "Booking updated successfully", body = ApiResponse<Booking>),
-        (status = 400, description = "Invalid booking data", body = ApiResponse<String>),
+        (status = 400, description = "Invalid booking data, or a status change (use approve/reject)", body = ApiResponse<String>),
         (status = 403, description = "Insufficient permissions", body = ApiResponse<String>),
         (status = 404, description = "Booking not found", body = ApiResponse<String>),
         (status = 401, description = "Unauthorized")
     )
 )]
 pub async fn update_booking(
-    State(_state): State<AppState>,
-    Extension(_claims): Extension<Claims>,
-    Path(_booking_id): Path<Uuid>,
-    Json(_request): Json<UpdateBookingRequest>,
-) -> Result<Json<ApiResponse<String>>, AppError> {
-    // TODO: Implement booking updates - this requires additional database methods
-    // For now, return an error indicating the feature is not yet implemented
-    Ok(Json(ApiResponse::error(
-        "Booking updates not yet implemented".to_string(),
-    )))
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(booking_id): Path<Uuid>,
+    Json(request): Json<UpdateBookingRequest>,
+) -> Result<Json<ApiResponse<Booking>>, AppError> {
+    // Status changes go through approve/reject so rules, overlaps and notifications apply
+    if request.status.is_some() {
+        return Err(AppError::BadRequest(
+            "Booking status cannot be changed here; use POST /api/bookings/{id}/approve or /api/bookings/{id}/reject".to_string(),
+        ));
+    }
+
+    // Lock the row so the audit entry's before snapshot is the one this update replaced
+    let mut tx = state.db.begin_transaction().await?;
+    let before = state
+        .db
+        .lock_bookings(&mut tx, &[booking_id])
+        .await?
+        .pop()
+        .ok_or(AppError::NotFound("Booking not found".to_string()))?;
+
+    // Owners may edit the details of their own bookings
+    match claims.role {
+        UserRole::Teacher | UserRole::Admin => {}
+        UserRole::Student => {
+            if before.requester_id != claims.user_id {
+                return Err(AppError::Authorization(
+                    "You can only update your own bookings".to_string(),
+                ));
+            }
+        }
+    }
+
+    if let Some(title) = &request.title {
+        if title.trim().is_empty() {
+            return Ok(Json(ApiResponse::error("Invalid booking data".to_string())));
+        }
+    }
+
+    let booking = state
+        .db
+        .update_booking_details(
+            &mut tx,
+            booking_id,
+            request.title.as_deref(),
+            request.group_name.as_deref(),
+            request.attendees,
+        )
+        .await?;
+
+    state
+        .db
+        .record_booking_event_tx(
+            &mut tx,
+            booking_id,
+            BookingEventType::Updated,
+            Some(claims.user_id),
+            None,
+            Some(&before),
+            Some(&booking),
+        )
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse::success(booking)))
 }
 
 /// Delete booking
@@ -299,6 +659,8 @@ pub async fn delete_booking(
     Extension(claims): Extension<Claims>,
     Path(booking_id): Path<Uuid>,
 ) -> Result<StatusCode, AppError> {
+    let before = state.db.get_booking_by_id(booking_id).await?;
+
     let deleted_rows = match claims.role {
         UserRole::Teacher | UserRole::Admin => state.db.delete_booking(booking_id).await?,
         _ => {
@@ -325,6 +687,20 @@ pub async fn delete_booking(
         }
     };
 
+    if deleted_rows > 0 {
+        state
+            .db
+            .record_booking_event(
+                booking_id,
+                BookingEventType::Deleted,
+                Some(claims.user_id),
+                None,
+                before.as_ref(),
+                None,
+            )
+            .await?;
+    }
+
     tracing::info!(deleted_rows, "deleted booking with id {:?}", booking_id);
     Ok(StatusCode::NO_CONTENT)
 }
@@ -362,11 +738,40 @@ pub async fn approve_booking(
         }
     }
 
+    // Lock the row so the audit entry's before snapshot is the one this change replaced
+    let mut tx = state.db.begin_transaction().await?;
+    let before = state
+        .db
+        .lock_bookings(&mut tx, &[booking_id])
+        .await?
+        .pop()
+        .ok_or(AppError::NotFound("Booking not found".to_string()))?;
+
     let booking = state
         .db
-        .update_booking_status(booking_id, BookingStatus::Approved, Some(claims.user_id))
+        .update_booking_status(
+            &mut tx,
+            booking_id,
+            BookingStatus::Approved,
+            Some(claims.user_id),
+        )
+        .await?;
+
+    state
+        .db
+        .record_booking_event_tx(
+            &mut tx,
+            booking_id,
+            BookingEventType::Approved,
+            Some(claims.user_id),
+            None,
+            Some(&before),
+            Some(&booking),
+        )
         .await?;
 
+    tx.commit().await?;
+
     Ok(Json(ApiResponse::success(booking)))
 }
 
@@ -403,10 +808,281 @@ pub async fn reject_booking(
         }
     }
 
+    // Lock the row so the audit entry's before snapshot is the one this change replaced
+    let mut tx = state.db.begin_transaction().await?;
+    let before = state
+        .db
+        .lock_bookings(&mut tx, &[booking_id])
+        .await?
+        .pop()
+        .ok_or(AppError::NotFound("Booking not found".to_string()))?;
+
     let booking = state
         .db
-        .update_booking_status(booking_id, BookingStatus::Rejected, Some(claims.user_id))
+        .update_booking_status(
+            &mut tx,
+            booking_id,
+            BookingStatus::Rejected,
+            Some(claims.user_id),
+        )
         .await?;
 
+    state
+        .db
+        .record_booking_event_tx(
+            &mut tx,
+            booking_id,
+            BookingEventType::Rejected,
+            Some(claims.user_id),
+            None,
+            Some(&before),
+            Some(&booking),
+        )
+        .await?;
+
+    tx.commit().await?;
+
     Ok(Json(ApiResponse::success(booking)))
-}
\ No newline at end of file
+}
+
+/// Get the audit history of a booking (teacher/admin only)
+#[utoipa::path(
+    get,
+    path = "/api/bookings/{id}/history",
+    tag = "bookings",
+    params(
+        ("id" = Uuid, Path, description = "Booking ID")
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Booking events, oldest first (kept after deletion)", body = ApiResponse<Vec<BookingEvent>>),
+        (status = 403, description = "Insufficient permissions", body = ApiResponse<String>),
+        (status = 404, description = "Booking not found", body = ApiResponse<String>),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn get_booking_history(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(booking_id): Path<Uuid>,
+) -> Result<Json<ApiResponse<Vec<BookingEvent>>>, AppError> {
+    match claims.role {
+        UserRole::Teacher | UserRole::Admin => {}
+        UserRole::Student => {
+            return Err(AppError::Authorization(
+                "Only teachers and admins can view booking history".to_string(),
+            ))
+        }
+    }
+
+    let events = state.db.get_booking_events(booking_id).await?;
+
+    if events.is_empty() && state.db.get_booking_by_id(booking_id).await?.is_none() {
+        return Err(AppError::NotFound("Booking not found".to_string()));
+    }
+
+    Ok(Json(ApiResponse::success(events)))
+}
+
+/// Approve or reject many pending bookings in one transaction (teacher/admin only)
+///
+/// Each booking is reported individually. Pending bookings in the batch that overlap each
+/// other, or that overlap an already approved booking, are not approved.
+#[utoipa::path(
+    post,
+    path = "/api/bookings/decisions",
+    tag = "bookings",
+    request_body = BulkBookingDecisionRequest,
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Per-booking results", body = ApiResponse<BulkBookingDecisionResponse>),
+        (status = 400, description = "Empty or oversized batch", body = ApiResponse<String>),
+        (status = 403, description = "Insufficient permissions", body = ApiResponse<String>),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn bulk_decide_bookings(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(request): Json<BulkBookingDecisionRequest>,
+) -> Result<Json<ApiResponse<BulkBookingDecisionResponse>>, AppError> {
+    match claims.role {
+        UserRole::Teacher | UserRole::Admin => {}
+        UserRole::Student => {
+            return Err(AppError::Authorization(
+                "Only teachers and admins can approve or reject bookings".to_string(),
+            ))
+        }
+    }
+
+    // Keep the caller's order but decide each booking once
+    let mut booking_ids = Vec::with_capacity(request.booking_ids.len());
+    for id in request.booking_ids {
+        if !booking_ids.contains(&id) {
+            booking_ids.push(id);
+        }
+    }
+
+    if booking_ids.is_empty() {
+        return Err(AppError::BadRequest("No bookings given".to_string()));
+    }
+    if booking_ids.len() > MAX_BULK_DECISIONS {
+        return Err(AppError::BadRequest(format!(
+            "At most {} bookings can be decided at once",
+            MAX_BULK_DECISIONS
+        )));
+    }
+
+    let (status, event_type) = match request.decision {
+        BookingDecision::Approve => (BookingStatus::Approved, BookingEventType::Approved),
+        BookingDecision::Reject => (BookingStatus::Rejected, BookingEventType::Rejected),
+    };
+    let reason = request.reason.as_deref();
+
+    let mut tx = state.db.begin_transaction().await?;
+    let locked: HashMap<Uuid, Booking> = state
+        .db
+        .lock_bookings(&mut tx, &booking_ids)
+        .await?
+        .into_iter()
+        .map(|b| (b.id, b))
+        .collect();
+
+    let mut errors: HashMap<Uuid, String> = HashMap::new();
+    for id in &booking_ids {
+        match locked.get(id) {
+            None => {
+                errors.insert(*id, "Booking not found".to_string());
+            }
+            Some(booking) if booking.status != BookingStatus::Pending => {
+                errors.insert(*id, format!("Booking is {:?}, not pending", booking.status));
+            }
+            Some(_) => {}
+        }
+    }
+
+    if let BookingDecision::Approve = request.decision {
+        let candidates: Vec<&Booking> = booking_ids
+            .iter()
+            .filter(|id| !errors.contains_key(id))
+            .filter_map(|id| locked.get(id))
+            .collect();
+
+        // Approving both sides of an overlap would double-book the microscope
+        let mut overlaps: HashMap<Uuid, Vec<Uuid>> = HashMap::new();
+        for (i, a) in candidates.iter().enumerate() {
+            for b in &candidates[i + 1..] {
+                if a.microscope_id == b.microscope_id
+                    && a.date == b.date
+                    && a.slot_start < b.slot_end
+                    && b.slot_start < a.slot_end
+                {
+                    overlaps.entry(a.id).or_default().push(b.id);
+                    overlaps.entry(b.id).or_default().push(a.id);
+                }
+            }
+        }
+
+        for booking in candidates {
+            if let Some(others) = overlaps.get(&booking.id) {
+                let others: Vec<String> = others.iter().map(|id| id.to_string()).collect();
+                errors.insert(
+                    booking.id,
+                    format!(
+                        "Overlaps pending booking(s) {} in this batch",
+                        others.join(", ")
+                    ),
+                );
+            } else if let Some(approved) = state.db.find_approved_overlap(&mut tx, booking).await? {
+                errors.insert(
+                    booking.id,
+                    format!("Overlaps approved booking {}", approved),
+                );
+            }
+        }
+    }
+
+    let mut results = Vec::with_capacity(booking_ids.len());
+    for id in &booking_ids {
+        if let Some(error) = errors.remove(id) {
+            results.push(BookingDecisionResult {
+                booking_id: *id,
+                success: false,
+                status: locked.get(id).map(|b| b.status),
+                error: Some(error),
+            });
+            continue;
+        }
+
+        let before = &locked[id];
+        state
+            .db
+            .set_booking_decision(&mut tx, *id, status, claims.user_id, reason)
+            .await?;
+
+        let after = Booking {
+            status,
+            approved_by: match status {
+                BookingStatus::Approved => Some(claims.user_id),
+                _ => before.approved_by,
+            },
+            ..before.clone()
+        };
+        state
+            .db
+            .record_booking_event_tx(
+                &mut tx,
+                *id,
+                event_type,
+                Some(claims.user_id),
+                reason,
+                Some(before),
+                Some(&after),
+            )
+            .await?;
+
+        results.push(BookingDecisionResult {
+            booking_id: *id,
+            success: true,
+            status: Some(status),
+            error: None,
+        });
+    }
+
+    let failed = results.iter().filter(|r| !r.success).count();
+    let rolled_back = request.atomic.unwrap_or(false) && failed > 0;
+
+    if rolled_back {
+        tx.rollback().await?;
+        for result in results.iter_mut().filter(|r| r.success) {
+            result.success = false;
+            result.status = Some(BookingStatus::Pending);
+            result.error = Some("Not applied: batch rolled back".to_string());
+        }
+    } else {
+        tx.commit().await?;
+    }
+
+    let succeeded = results.iter().filter(|r| r.success).count();
+
+    tracing::info!(
+        "Bulk {:?} of {} booking(s) by {}: {} succeeded, {} failed{}",
+        request.decision,
+        booking_ids.len(),
+        claims.user_id,
+        succeeded,
+        results.len() - succeeded,
+        if rolled_back { " (rolled back)" } else { "" }
+    );
+
+    Ok(Json(ApiResponse::success(BulkBookingDecisionResponse {
+        succeeded,
+        failed: results.len() - succeeded,
+        results,
+        rolled_back,
+    })))
+}
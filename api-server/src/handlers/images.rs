@@ -1,20 +1,36 @@
+use std::time::SystemTime;
+
 use axum::{
-    extract::{Path, Query, State},
-    http::{HeaderMap, StatusCode},
-    response::{Json, Response},
+    body::Body,
+    extract::{multipart::MultipartError, Multipart, Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
     Extension,
 };
-use chrono::NaiveDate;
-use serde::Deserialize;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
 use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 
 use crate::{
+    handlers::sessions::ensure_session_access,
     middleware::auth::Claims,
-    models::{ApiResponse, Image, UserRole},
-    AppState,
+    models::{
+        ApiResponse, Image, ImageMetadata, ImageRendition, PageRequest, Paginated,
+        SessionEventType, SessionStatus, TrashedImage, UserRole,
+    },
+    services::{
+        conditional::{if_range_matches, is_not_modified, parse_byte_range},
+        database::ImageFilter,
+        file_storage::FileStorageError,
+        filenames, image_info, session_timeline, signed_url, storage_quota,
+    },
+    AppError, AppState,
 };
 
+/// Allowance on top of `max_file_size` for the rest of an upload form
+pub const UPLOAD_FORM_OVERHEAD: usize = 1024 * 1024;
+
 #[derive(Debug, Deserialize, ToSchema, IntoParams)]
 pub struct ImageQuery {
     pub session_id: Option<Uuid>,
@@ -29,6 +45,45 @@ pub struct ImageQuery {
     pub page: Option<u64>,
     #[schema(example = 20)]
     pub limit: Option<u64>,
+    /// Cursor from a previous page's `next_cursor`; pass it empty to start paging by cursor.
+    /// Cursor pages are in creation order and cannot be combined with `page`.
+    pub cursor: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct TrashQuery {
+    #[schema(example = 1)]
+    pub page: Option<u64>,
+    #[schema(example = 20)]
+    pub limit: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct StarImageRequest {
+    /// Starred images can be exempted from retention policies
+    pub starred: bool,
+}
+
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct ImageFileQuery {
+    /// Serve a downscaled WebP rendition instead of the original
+    pub size: Option<ImageRendition>,
+}
+
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct SignedFileQuery {
+    /// Unix time the URL stops working
+    pub expires: i64,
+    pub signature: String,
+    pub size: Option<ImageRendition>,
+}
+
+/// Download URL that works without a bearer token until it expires
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SignedImageUrl {
+    #[schema(example = "/files/3f2b...?expires=1705329000&signature=9c1e...")]
+    pub url: String,
+    pub expires_at: DateTime<Utc>,
 }
 
 /// Get image metadata by ID
@@ -80,21 +135,27 @@ pub async fn get_image(
     path = "/api/images/{id}/file",
     tag = "images",
     params(
-        ("id" = Uuid, Path, description = "Image ID")
+        ("id" = Uuid, Path, description = "Image ID"),
+        ImageFileQuery
     ),
     security(
         ("bearer_auth" = [])
     ),
     responses(
-        (status = 200, description = "Image file content", content_type = "image/jpeg"),
+        (status = 200, description = "Image file content, or a WebP rendition when `size` is given", content_type = "image/jpeg"),
+        (status = 206, description = "Requested byte range of the file"),
+        (status = 304, description = "Not modified since the ETag or date the client has"),
         (status = 403, description = "Access denied"),
-        (status = 404, description = "Image not found")
+        (status = 404, description = "Image not found"),
+        (status = 416, description = "Requested range is outside the file")
     )
 )]
 pub async fn serve_image_file(
     State(state): State<AppState>,
     Extension(claims): Extension<Claims>,
     Path(image_id): Path<Uuid>,
+    Query(query): Query<ImageFileQuery>,
+    headers: HeaderMap,
 ) -> Result<Response, StatusCode> {
     let image = state
         .db
@@ -108,32 +169,120 @@ pub async fn serve_image_file(
         return Err(StatusCode::FORBIDDEN);
     }
 
-    // Read actual file from storage
-    use axum::response::IntoResponse;
+    let file = resolve_file(&state, &image, query.size).await?;
+    stream_file(&state, &headers, &file).await
+}
 
-    let file_contents = state
-        .file_store
-        .read_file(&image.file_path)
+/// Create a signed, time-limited download URL for an image
+///
+/// The URL can be used without a bearer token (e.g. in `<img src>`), so it is only
+/// handed out after the usual access check.
+#[utoipa::path(
+    get,
+    path = "/api/images/{id}/url",
+    tag = "images",
+    params(
+        ("id" = Uuid, Path, description = "Image ID"),
+        ImageFileQuery
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Signed download URL", body = ApiResponse<SignedImageUrl>),
+        (status = 403, description = "Access denied"),
+        (status = 404, description = "Image not found")
+    )
+)]
+pub async fn get_image_download_url(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(image_id): Path<Uuid>,
+    Query(query): Query<ImageFileQuery>,
+) -> Result<Json<ApiResponse<SignedImageUrl>>, StatusCode> {
+    let image = state
+        .db
+        .get_image_by_id(image_id)
         .await
-        .map_err(|e| {
-            tracing::error!("Failed to read file {}: {}", image.file_path, e);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?;
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
 
-    let mut headers = HeaderMap::new();
-    headers.insert("content-type", image.content_type.parse().unwrap());
-    headers.insert(
-        "content-length",
-        file_contents.len().to_string().parse().unwrap(),
+    if !can_access_image(&state, &claims, &image).await {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let expires_at =
+        Utc::now() + Duration::seconds(state.config.file_storage.signed_url_ttl as i64);
+    let signature = signed_url::sign(
+        &state.config.file_storage.url_signing_secret,
+        image.id,
+        query.size,
+        expires_at,
     );
-    headers.insert(
-        "content-disposition",
-        format!("inline; filename=\"{}\"", image.filename)
-            .parse()
-            .unwrap(),
+
+    let mut url = format!(
+        "/files/{}?expires={}&signature={}",
+        image.id,
+        expires_at.timestamp(),
+        signature
     );
+    if let Some(size) = query.size {
+        url.push_str(&format!("&size={}", size.as_str()));
+    }
 
-    Ok((headers, file_contents).into_response())
+    Ok(Json(ApiResponse::success(SignedImageUrl {
+        url,
+        expires_at: DateTime::from_timestamp(expires_at.timestamp(), 0).unwrap(),
+    })))
+}
+
+/// Download an image through a signed URL from `/api/images/{id}/url` (no bearer token)
+#[utoipa::path(
+    get,
+    path = "/files/{id}",
+    tag = "images",
+    params(
+        ("id" = Uuid, Path, description = "Image ID"),
+        SignedFileQuery
+    ),
+    responses(
+        (status = 200, description = "Image file content", content_type = "image/jpeg"),
+        (status = 206, description = "Requested byte range of the file"),
+        (status = 304, description = "Not modified since the ETag or date the client has"),
+        (status = 403, description = "Signature invalid or expired"),
+        (status = 404, description = "Image not found")
+    )
+)]
+pub async fn serve_signed_image_file(
+    State(state): State<AppState>,
+    Path(image_id): Path<Uuid>,
+    Query(query): Query<SignedFileQuery>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    if !signed_url::verify(
+        &state.config.file_storage.url_signing_secret,
+        image_id,
+        query.size,
+        query.expires,
+        &query.signature,
+        Utc::now(),
+    ) {
+        tracing::warn!(
+            "Rejected invalid or expired download URL for image {}",
+            image_id
+        );
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let image = state
+        .db
+        .get_image_by_id(image_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let file = resolve_file(&state, &image, query.size).await?;
+    stream_file(&state, &headers, &file).await
 }
 
 /// Get latest image for a session
@@ -170,9 +319,14 @@ pub async fn get_latest_image_for_session(
     // Permission checking based on user role
     match claims.role {
         UserRole::Student => {
-            if session.user_id != claims.user_id {
+            if !state
+                .db
+                .is_session_participant(session.id, claims.user_id)
+                .await
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+            {
                 tracing::warn!(
-                    "User {} attempted to access session {} they do not own",
+                    "User {} attempted to access session {} they do not participate in",
                     claims.user_id,
                     session_id
                 );
@@ -195,6 +349,503 @@ pub async fn get_latest_image_for_session(
     Ok(Json(ApiResponse::success(image)))
 }
 
+/// Multipart form for uploading an image
+#[derive(Debug, ToSchema)]
+pub struct UploadImageForm {
+    /// The image; its type is detected from the content, not the filename
+    #[schema(value_type = String, format = Binary)]
+    pub file: Vec<u8>,
+    /// Also run object detection and classification on the IA system
+    #[schema(example = false)]
+    pub analyze: Option<bool>,
+}
+
+struct UploadedFile {
+    filename: String,
+    content: Vec<u8>,
+    analyze: bool,
+}
+
+/// Upload an image taken outside the IA system (e.g. with a phone adaptor) to an active session
+#[utoipa::path(
+    post,
+    path = "/api/sessions/{session_id}/images",
+    tag = "images",
+    params(
+        ("session_id" = Uuid, Path, description = "Session ID")
+    ),
+    request_body(content = UploadImageForm, content_type = "multipart/form-data"),
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Image stored; `message` is set if the requested analysis failed", body = ApiResponse<Image>),
+        (status = 400, description = "Malformed form, undecodable image or session not active", body = ApiResponse<String>),
+        (status = 403, description = "Access denied to session, or the file does not fit in the session owner's storage quota (the body gives `used_bytes`, `limit_bytes` and `file_bytes`)", body = ApiResponse<String>),
+        (status = 404, description = "Session not found", body = ApiResponse<String>),
+        (status = 413, description = "File larger than the configured maximum", body = ApiResponse<String>),
+        (status = 415, description = "Content is not one of the allowed image types", body = ApiResponse<String>),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn upload_image(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(session_id): Path<Uuid>,
+    mut multipart: Multipart,
+) -> Result<Json<ApiResponse<Image>>, AppError> {
+    let session = state
+        .db
+        .get_session_by_id(session_id)
+        .await?
+        .ok_or(AppError::NotFound("Session not found".to_string()))?;
+    ensure_session_access(&state, &claims, &session).await?;
+    if session.status != SessionStatus::Active {
+        return Err(AppError::BadRequest(
+            "Images can only be uploaded to an active session".to_string(),
+        ));
+    }
+
+    let upload = read_upload_form(&mut multipart, state.config.file_storage.max_file_size).await?;
+
+    // Name the file after its detected format, so store_file checks the real type against
+    // the allowed types instead of whatever extension the client sent
+    let format = image_info::sniff(&upload.content)
+        .map_err(|_| FileStorageError::InvalidFileType("application/octet-stream".to_string()))?;
+    let filename = format!(
+        "{}.{}",
+        filenames::sanitize_stem(&upload.filename),
+        format.extensions_str()[0]
+    );
+
+    let quota = storage_quota::quota_for_session(&state, session.id).await?;
+    let stored_file = state
+        .file_store
+        .store_file(&filename, &upload.content, session.id, quota)
+        .await?;
+
+    let content_type = stored_file.content_type.clone();
+    let content = upload.content;
+    let (content, info) = tokio::task::spawn_blocking(move || {
+        let info = image_info::inspect(&content, &content_type);
+        (content, info)
+    })
+    .await
+    .map_err(|e| AppError::Internal(e.to_string()))?;
+    let info = match info {
+        Ok(info) => info,
+        Err(e) => {
+            discard_stored_file(&state, &stored_file.file_path).await;
+            return Err(AppError::BadRequest(format!(
+                "Image could not be read: {}",
+                e
+            )));
+        }
+    };
+
+    let mut metadata = ImageMetadata::default();
+    let mut message = None;
+    if upload.analyze {
+        match state
+            .ia_client
+            .analyze_image(
+                &session.microscope_id,
+                &stored_file.filename,
+                &stored_file.content_type,
+                content.clone(),
+            )
+            .await
+        {
+            Ok(analysis) => metadata = analysis,
+            Err(e) => {
+                tracing::warn!(
+                    "Failed to analyze uploaded image {}: {}",
+                    stored_file.filename,
+                    e
+                );
+                message = Some("Image stored, but the IA system could not analyze it".to_string());
+            }
+        }
+    }
+    info.exif.apply_to(&mut metadata);
+
+    let image = Image {
+        id: stored_file.id,
+        session_id: session.id,
+        filename: stored_file.filename.clone(),
+        file_path: stored_file.file_path.clone(),
+        content_type: stored_file.content_type.clone(),
+        file_size: stored_file.file_size as i64,
+        width: Some(info.width as i32),
+        height: Some(info.height as i32),
+        metadata,
+        captured_at: Utc::now(),
+        sha256: Some(stored_file.sha256.clone()),
+        starred: false,
+    };
+
+    if let Err(e) = storage_quota::create_image(&state, &image).await {
+        discard_stored_file(&state, &stored_file.file_path).await;
+        return Err(e);
+    }
+    ensure_stored_file(&state, &stored_file.file_path, &content).await?;
+
+    session_timeline::record(
+        &state,
+        session.id,
+        SessionEventType::Capture,
+        Some(claims.user_id),
+        serde_json::json!({
+            "image_id": image.id,
+            "filename": image.filename,
+            "microscope_id": session.microscope_id,
+            "source": "upload",
+            "original_filename": upload.filename,
+            "analyzed": upload.analyze && message.is_none(),
+        }),
+    )
+    .await;
+
+    tracing::info!(
+        "Stored uploaded image {} for session {} ({})",
+        image.id,
+        session.id,
+        image.filename
+    );
+
+    let mut response = ApiResponse::success(image);
+    response.message = message;
+    Ok(Json(response))
+}
+
+/// Read the `file` and `analyze` fields; other fields are ignored
+async fn read_upload_form(
+    multipart: &mut Multipart,
+    max_file_size: u64,
+) -> Result<UploadedFile, AppError> {
+    let mut file = None;
+    let mut analyze = false;
+
+    'fields: while let Some(mut field) = multipart.next_field().await.map_err(multipart_error)? {
+        match field.name() {
+            Some("file") => {
+                let filename = field.file_name().unwrap_or("upload").to_string();
+                let mut content = Vec::new();
+                while let Some(chunk) = field.chunk().await.map_err(multipart_error)? {
+                    content.extend_from_slice(&chunk);
+                    if content.len() as u64 > max_file_size {
+                        // No need to read the rest: store_file rejects it as too large
+                        file = Some((filename, content));
+                        break 'fields;
+                    }
+                }
+                file = Some((filename, content));
+            }
+            Some("analyze") => {
+                let value = field.text().await.map_err(multipart_error)?;
+                analyze = match value.trim() {
+                    "true" | "1" | "on" => true,
+                    "false" | "0" | "off" | "" => false,
+                    other => {
+                        return Err(AppError::BadRequest(format!(
+                            "Invalid analyze value: {}",
+                            other
+                        )))
+                    }
+                };
+            }
+            _ => {}
+        }
+    }
+
+    let (filename, content) =
+        file.ok_or_else(|| AppError::BadRequest("Missing file field".to_string()))?;
+    if content.is_empty() {
+        return Err(AppError::BadRequest("Uploaded file is empty".to_string()));
+    }
+
+    Ok(UploadedFile {
+        filename,
+        content,
+        analyze,
+    })
+}
+
+fn multipart_error(e: MultipartError) -> AppError {
+    AppError::BadRequest(format!("Invalid multipart form: {}", e.body_text()))
+}
+
+/// Remove a stored file whose image could not be recorded, unless other images share it
+pub(crate) async fn discard_stored_file(state: &AppState, file_path: &str) {
+    let result: Result<(), sqlx::Error> = async {
+        let mut tx = state.db.begin_transaction().await?;
+        state.db.lock_stored_file(&mut tx, file_path).await?;
+
+        if !state.db.is_file_referenced(&mut tx, file_path).await? {
+            if let Err(e) = state.file_store.delete_file(file_path).await {
+                tracing::warn!("Failed to clean up file {}: {}", file_path, e);
+            }
+        }
+        tx.commit().await
+    }
+    .await;
+
+    if let Err(e) = result {
+        tracing::warn!("Not cleaning up file {}: {}", file_path, e);
+    }
+}
+
+/// Make sure a content-addressed file an image was just created for is still in storage.
+///
+/// The upload may have found the file already stored and skipped writing it, just before
+/// the trash purge (or a failed upload) deleted it. With the image's reference committed,
+/// the file can no longer be deleted, so rewriting it under the file lock settles the race.
+pub(crate) async fn ensure_stored_file(
+    state: &AppState,
+    file_path: &str,
+    content: &[u8],
+) -> Result<(), AppError> {
+    if !state.config.file_storage.content_addressed {
+        return Ok(());
+    }
+
+    let mut tx = state.db.begin_transaction().await?;
+    state.db.lock_stored_file(&mut tx, file_path).await?;
+    state.file_store.restore_file(file_path, content).await?;
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Move an image to the trash
+///
+/// The image disappears from listings and its file stops being served. It can be restored
+/// until it is purged `IMAGE_TRASH_RETENTION_DAYS` after deletion.
+#[utoipa::path(
+    delete,
+    path = "/api/images/{id}",
+    tag = "images",
+    params(
+        ("id" = Uuid, Path, description = "Image ID")
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Image moved to the trash", body = ApiResponse<TrashedImage>),
+        (status = 403, description = "Only the session owner or staff can delete images", body = ApiResponse<String>),
+        (status = 404, description = "Image not found", body = ApiResponse<String>),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn delete_image(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(image_id): Path<Uuid>,
+) -> Result<Json<ApiResponse<TrashedImage>>, AppError> {
+    let image = state
+        .db
+        .get_image_by_id(image_id)
+        .await?
+        .ok_or(AppError::NotFound("Image not found".to_string()))?;
+    ensure_image_owner(&state, &claims, image.session_id).await?;
+
+    if !state.db.trash_image(image_id, Some(claims.user_id)).await? {
+        return Err(AppError::NotFound("Image not found".to_string()));
+    }
+    let trashed = state
+        .db
+        .get_trashed_image(image_id, trash_retention(&state))
+        .await?
+        .ok_or(AppError::NotFound("Image not found".to_string()))?;
+
+    session_timeline::record(
+        &state,
+        image.session_id,
+        SessionEventType::ImageDeleted,
+        Some(claims.user_id),
+        serde_json::json!({
+            "image_id": image.id,
+            "filename": image.filename,
+            "purge_after": trashed.purge_after,
+        }),
+    )
+    .await;
+    tracing::info!(
+        "User {} moved image {} to the trash",
+        claims.user_id,
+        image_id
+    );
+
+    Ok(Json(ApiResponse::success(trashed)))
+}
+
+/// Restore an image from the trash
+#[utoipa::path(
+    post,
+    path = "/api/images/{id}/restore",
+    tag = "images",
+    params(
+        ("id" = Uuid, Path, description = "Image ID")
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Restored image", body = ApiResponse<Image>),
+        (status = 403, description = "Only the session owner or staff can restore images", body = ApiResponse<String>),
+        (status = 404, description = "Image not in the trash", body = ApiResponse<String>),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn restore_image(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(image_id): Path<Uuid>,
+) -> Result<Json<ApiResponse<Image>>, AppError> {
+    let trashed = state
+        .db
+        .get_trashed_image(image_id, trash_retention(&state))
+        .await?
+        .ok_or(AppError::NotFound("Image not in the trash".to_string()))?;
+    ensure_image_owner(&state, &claims, trashed.image.session_id).await?;
+
+    if !state.db.restore_image(image_id).await? {
+        return Err(AppError::NotFound("Image not in the trash".to_string()));
+    }
+
+    session_timeline::record(
+        &state,
+        trashed.image.session_id,
+        SessionEventType::ImageRestored,
+        Some(claims.user_id),
+        serde_json::json!({
+            "image_id": trashed.image.id,
+            "filename": trashed.image.filename,
+        }),
+    )
+    .await;
+    tracing::info!("User {} restored image {}", claims.user_id, image_id);
+
+    Ok(Json(ApiResponse::success(trashed.image)))
+}
+
+/// List images in the trash
+///
+/// Students see the images deleted from sessions they own; staff see the whole trash.
+#[utoipa::path(
+    get,
+    path = "/api/images/trash",
+    tag = "images",
+    params(TrashQuery),
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Trashed images, most recently deleted first", body = ApiResponse<Paginated<TrashedImage>>),
+        (status = 400, description = "Invalid page", body = ApiResponse<String>),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn list_trash(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Query(query): Query<TrashQuery>,
+) -> Result<Json<ApiResponse<Paginated<TrashedImage>>>, AppError> {
+    let page =
+        PageRequest::new(query.page, query.limit, None, 20, 100).map_err(AppError::BadRequest)?;
+
+    let owner_id = match claims.role {
+        UserRole::Admin | UserRole::Teacher => None,
+        UserRole::Student => Some(claims.user_id),
+    };
+    let (images, total) = state
+        .db
+        .list_trashed_images(owner_id, trash_retention(&state), page.limit, page.offset)
+        .await?;
+
+    Ok(Json(ApiResponse::success(Paginated::new(
+        images, total, page,
+    ))))
+}
+
+/// Star or unstar an image
+///
+/// Retention policies can be set to never expire starred images.
+#[utoipa::path(
+    put,
+    path = "/api/images/{id}/starred",
+    tag = "images",
+    params(
+        ("id" = Uuid, Path, description = "Image ID")
+    ),
+    request_body = StarImageRequest,
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Updated image", body = ApiResponse<Image>),
+        (status = 403, description = "Only the session owner or staff can star images", body = ApiResponse<String>),
+        (status = 404, description = "Image not found", body = ApiResponse<String>),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn set_image_starred(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(image_id): Path<Uuid>,
+    Json(request): Json<StarImageRequest>,
+) -> Result<Json<ApiResponse<Image>>, AppError> {
+    let image = state
+        .db
+        .get_image_by_id(image_id)
+        .await?
+        .ok_or(AppError::NotFound("Image not found".to_string()))?;
+    ensure_image_owner(&state, &claims, image.session_id).await?;
+
+    if !state
+        .db
+        .set_image_starred(image_id, request.starred)
+        .await?
+    {
+        return Err(AppError::NotFound("Image not found".to_string()));
+    }
+
+    Ok(Json(ApiResponse::success(Image {
+        starred: request.starred,
+        ..image
+    })))
+}
+
+/// Only the session's owner and staff may delete, restore or star its images
+async fn ensure_image_owner(
+    state: &AppState,
+    claims: &Claims,
+    session_id: Uuid,
+) -> Result<(), AppError> {
+    match claims.role {
+        UserRole::Admin | UserRole::Teacher => Ok(()),
+        UserRole::Student => {
+            let session = state
+                .db
+                .get_session_by_id(session_id)
+                .await?
+                .ok_or(AppError::NotFound("Session not found".to_string()))?;
+            if session.user_id != claims.user_id {
+                return Err(AppError::Authorization(
+                    "Only the session owner or staff can delete, restore or star its images"
+                        .to_string(),
+                ));
+            }
+            Ok(())
+        }
+    }
+}
+
+fn trash_retention(state: &AppState) -> Duration {
+    Duration::days(state.config.file_storage.trash_retention_days)
+}
+
 /// Get all images for a session
 #[utoipa::path(
     get,
@@ -208,7 +859,8 @@ pub async fn get_latest_image_for_session(
         ("bearer_auth" = [])
     ),
     responses(
-        (status = 200, description = "List of images for session", body = ApiResponse<Vec<Image>>),
+        (status = 200, description = "List of images for session", body = ApiResponse<Paginated<Image>>),
+        (status = 400, description = "Invalid date, page or cursor"),
         (status = 403, description = "Access denied to session", body = ApiResponse<String>),
         (status = 401, description = "Unauthorized")
     )
@@ -217,8 +869,10 @@ pub async fn get_all_images_for_session(
     State(state): State<AppState>,
     Extension(claims): Extension<Claims>,
     Path(session_id): Path<Uuid>,
-    Query(_query): Query<ImageQuery>,
-) -> Result<Json<ApiResponse<Vec<Image>>>, StatusCode> {
+    Query(query): Query<ImageQuery>,
+) -> Result<Json<ApiResponse<Paginated<Image>>>, StatusCode> {
+    let page = page_request(&query)?;
+
     // Check if user has access to this session
     let session = state
         .db
@@ -230,9 +884,14 @@ pub async fn get_all_images_for_session(
     // Permission checking based on user role
     match claims.role {
         UserRole::Student => {
-            if session.user_id != claims.user_id {
+            if !state
+                .db
+                .is_session_participant(session.id, claims.user_id)
+                .await
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+            {
                 tracing::warn!(
-                    "User {} attempted to list images for session {} they do not own",
+                    "User {} attempted to list images for session {} they do not participate in",
                     claims.user_id,
                     session_id
                 );
@@ -244,14 +903,23 @@ pub async fn get_all_images_for_session(
         }
     }
 
-    // Get all images for the session
-    let images = state
+    let filter = ImageFilter {
+        user_id: None,
+        session_id: Some(session.id),
+        tags: query.tags,
+        date_from: parse_date_param("date_from", query.date_from.as_deref())?,
+        date_to: parse_date_param("date_to", query.date_to.as_deref())?,
+    };
+
+    let (images, total, next_cursor) = state
         .db
-        .get_images_by_session(session_id)
+        .search_images(&filter, &page)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    Ok(Json(ApiResponse::success(images)))
+    Ok(Json(ApiResponse::success(
+        Paginated::new(images, total, page).with_next_cursor(next_cursor),
+    )))
 }
 
 /// Get all images for a user
@@ -267,7 +935,8 @@ pub async fn get_all_images_for_session(
         ("bearer_auth" = [])
     ),
     responses(
-        (status = 200, description = "List of images for user", body = ApiResponse<Vec<Image>>),
+        (status = 200, description = "List of images for user", body = ApiResponse<Paginated<Image>>),
+        (status = 400, description = "Invalid date, page or cursor"),
         (status = 403, description = "Access denied - can only access own images unless admin/teacher", body = ApiResponse<String>),
         (status = 401, description = "Unauthorized")
     )
@@ -277,7 +946,7 @@ pub async fn get_all_images_for_user(
     Extension(claims): Extension<Claims>,
     Path(user_id): Path<Uuid>,
     Query(query): Query<ImageQuery>,
-) -> Result<Json<ApiResponse<Vec<Image>>>, StatusCode> {
+) -> Result<Json<ApiResponse<Paginated<Image>>>, StatusCode> {
     // Check permissions - users can only access their own images unless they're admin/teacher
     match claims.role {
         UserRole::Student => {
@@ -296,21 +965,26 @@ pub async fn get_all_images_for_user(
     }
 
     // Extract pagination and filtering parameters
-    let limit = query.limit.unwrap_or(20).min(100);
-    let page = query.page.unwrap_or(1).max(1);
-    let offset = (page - 1) * limit;
+    let page = page_request(&query)?;
 
-    let date_from = parse_date_param("date_from", query.date_from.as_deref())?;
-    let date_to = parse_date_param("date_to", query.date_to.as_deref())?;
+    let filter = ImageFilter {
+        user_id: Some(user_id),
+        session_id: None,
+        tags: query.tags,
+        date_from: parse_date_param("date_from", query.date_from.as_deref())?,
+        date_to: parse_date_param("date_to", query.date_to.as_deref())?,
+    };
 
     // Get images with filtering
-    let images = state
+    let (images, total, next_cursor) = state
         .db
-        .get_images_by_user(user_id, limit, offset, query.tags, date_from, date_to)
+        .search_images(&filter, &page)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    Ok(Json(ApiResponse::success(images)))
+    Ok(Json(ApiResponse::success(
+        Paginated::new(images, total, page).with_next_cursor(next_cursor),
+    )))
 }
 
 /// Search images by metadata tags
@@ -323,7 +997,8 @@ pub async fn get_all_images_for_user(
         ("bearer_auth" = [])
     ),
     responses(
-        (status = 200, description = "Search results", body = ApiResponse<Vec<Image>>),
+        (status = 200, description = "Search results", body = ApiResponse<Paginated<Image>>),
+        (status = 400, description = "Invalid date, page or cursor"),
         (status = 401, description = "Unauthorized")
     )
 )]
@@ -331,14 +1006,9 @@ pub async fn search_images(
     State(state): State<AppState>,
     Extension(claims): Extension<Claims>,
     Query(query): Query<ImageQuery>,
-) -> Result<Json<ApiResponse<Vec<Image>>>, StatusCode> {
+) -> Result<Json<ApiResponse<Paginated<Image>>>, StatusCode> {
     // Extract pagination parameters
-    let limit = query.limit.unwrap_or(20).min(100);
-    let page = query.page.unwrap_or(1).max(1);
-    let offset = (page - 1) * limit;
-
-    let date_from = parse_date_param("date_from", query.date_from.as_deref())?;
-    let date_to = parse_date_param("date_to", query.date_to.as_deref())?;
+    let page = page_request(&query)?;
 
     // Role-based filtering - students can only search their own images
     let user_id = match claims.role {
@@ -346,22 +1016,176 @@ pub async fn search_images(
         UserRole::Teacher | UserRole::Admin => query.user_id,
     };
 
+    let filter = ImageFilter {
+        user_id,
+        session_id: query.session_id,
+        tags: query.tags,
+        date_from: parse_date_param("date_from", query.date_from.as_deref())?,
+        date_to: parse_date_param("date_to", query.date_to.as_deref())?,
+    };
+
     // Search images with filters
-    let images = state
+    let (images, total, next_cursor) = state
         .db
-        .search_images(
-            user_id,
-            query.session_id,
-            query.tags,
-            date_from,
-            date_to,
-            limit,
-            offset,
-        )
+        .search_images(&filter, &page)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    Ok(Json(ApiResponse::success(images)))
+    Ok(Json(ApiResponse::success(
+        Paginated::new(images, total, page).with_next_cursor(next_cursor),
+    )))
+}
+
+/// A stored file to serve: the original image or a rendition
+struct ServedFile {
+    path: String,
+    content_type: String,
+    filename: String,
+    /// Content hash recorded at upload, if known
+    sha256: Option<String>,
+}
+
+/// The original or a (lazily generated) rendition of an image
+async fn resolve_file(
+    state: &AppState,
+    image: &Image,
+    size: Option<ImageRendition>,
+) -> Result<ServedFile, StatusCode> {
+    let file = match size {
+        None => ServedFile {
+            path: image.file_path.clone(),
+            content_type: image.content_type.clone(),
+            filename: image.filename.clone(),
+            sha256: image.sha256.clone(),
+        },
+        Some(size) => {
+            let rendition_path = state
+                .file_store
+                .get_or_create_rendition(&image.file_path, size)
+                .await
+                .map_err(|e| {
+                    tracing::error!(
+                        "Failed to create {:?} rendition of {}: {}",
+                        size,
+                        image.file_path,
+                        e
+                    );
+                    StatusCode::INTERNAL_SERVER_ERROR
+                })?;
+            let filename = std::path::Path::new(&rendition_path)
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            ServedFile {
+                path: rendition_path,
+                content_type: "image/webp".to_string(),
+                filename,
+                sha256: None,
+            }
+        }
+    };
+
+    Ok(file)
+}
+
+/// Stream a stored file, answering conditional requests with 304 and `Range` requests
+/// with 206 partial content. The ETag is the SHA-256 of the file contents: the one recorded
+/// at upload for originals, otherwise hashed from storage (and cached).
+async fn stream_file(
+    state: &AppState,
+    request_headers: &HeaderMap,
+    file: &ServedFile,
+) -> Result<Response, StatusCode> {
+    let file_path = file.path.as_str();
+
+    let storage_error = |e: FileStorageError| {
+        tracing::error!("Failed to serve file {}: {}", file_path, e);
+        match e {
+            FileStorageError::FileNotFound(_) => StatusCode::NOT_FOUND,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    };
+
+    let metadata = state
+        .file_store
+        .get_file_metadata(file_path)
+        .await
+        .map_err(storage_error)?;
+    let sha256 = match &file.sha256 {
+        Some(sha256) => sha256.clone(),
+        None => state
+            .file_store
+            .content_hash(file_path)
+            .await
+            .map_err(storage_error)?,
+    };
+    let etag = format!("\"{}\"", sha256);
+    // HTTP dates have second precision
+    let last_modified = metadata
+        .modified
+        .and_then(|dt| DateTime::from_timestamp(dt.timestamp(), 0))
+        .map(SystemTime::from);
+
+    let mut headers = HeaderMap::new();
+    headers.insert(header::ETAG, etag.parse().unwrap());
+    if let Some(modified) = last_modified {
+        headers.insert(
+            header::LAST_MODIFIED,
+            httpdate::fmt_http_date(modified).parse().unwrap(),
+        );
+    }
+    headers.insert(header::CACHE_CONTROL, "private, no-cache".parse().unwrap());
+    headers.insert(header::ACCEPT_RANGES, "bytes".parse().unwrap());
+
+    if is_not_modified(request_headers, &etag, last_modified) {
+        return Ok((StatusCode::NOT_MODIFIED, headers).into_response());
+    }
+
+    headers.insert(header::CONTENT_TYPE, file.content_type.parse().unwrap());
+    headers.insert(
+        header::CONTENT_DISPOSITION,
+        filenames::content_disposition("inline", &file.filename),
+    );
+
+    let size = metadata.size;
+    let range = match request_headers.get(header::RANGE) {
+        Some(range) if if_range_matches(request_headers, &etag, last_modified) => {
+            match parse_byte_range(range.to_str().unwrap_or_default(), size) {
+                Some(Ok(range)) => Some(range),
+                Some(Err(())) => {
+                    headers.insert(
+                        header::CONTENT_RANGE,
+                        format!("bytes */{}", size).parse().unwrap(),
+                    );
+                    return Ok((StatusCode::RANGE_NOT_SATISFIABLE, headers).into_response());
+                }
+                None => None,
+            }
+        }
+        _ => None,
+    };
+
+    let (status, range) = match range {
+        Some((start, end)) => {
+            headers.insert(
+                header::CONTENT_RANGE,
+                format!("bytes {}-{}/{}", start, end, size).parse().unwrap(),
+            );
+            (StatusCode::PARTIAL_CONTENT, Some(start..end + 1))
+        }
+        None => (StatusCode::OK, None),
+    };
+    let length = range.as_ref().map_or(size, |range| range.end - range.start);
+    headers.insert(header::CONTENT_LENGTH, length.to_string().parse().unwrap());
+
+    let stream = state
+        .file_store
+        .read_stream(file_path, range)
+        .await
+        .map_err(storage_error)?;
+
+    let body = Body::from_stream(stream);
+    Ok((status, headers, body).into_response())
 }
 
 /// Check if user can access an image based on role and ownership
@@ -369,13 +1193,13 @@ async fn can_access_image(state: &AppState, claims: &Claims, image: &Image) -> b
     match claims.role {
         UserRole::Admin | UserRole::Teacher => true, // Admin and teachers can access all images
         UserRole::Student => {
-            // Students can only access images from their own sessions
-            // Check if image belongs to user's session via database lookup
-            if let Ok(Some(session)) = state.db.get_session_by_id(image.session_id).await {
-                session.user_id == claims.user_id
-            } else {
-                false
-            }
+            // Students can only access images from sessions they own or share
+            // (co-participants and previous owners after a handover)
+            state
+                .db
+                .is_session_participant(image.session_id, claims.user_id)
+                .await
+                .unwrap_or(false)
         }
     }
 }
@@ -392,3 +1216,10 @@ fn parse_date_param(name: &str, value: Option<&str>) -> Result<Option<NaiveDate>
         Ok(None)
     }
 }
+
+fn page_request(query: &ImageQuery) -> Result<PageRequest, StatusCode> {
+    PageRequest::new(query.page, query.limit, query.cursor.as_deref(), 20, 100).map_err(|e| {
+        tracing::warn!("{}", e);
+        StatusCode::BAD_REQUEST
+    })
+}
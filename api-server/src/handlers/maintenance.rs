@@ -0,0 +1,277 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+    Extension,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::{
+    middleware::auth::Claims,
+    models::{
+        ApiResponse, Booking, BookingEventType, MaintenanceKind, MaintenanceWindow,
+        NotificationKind, UserRole,
+    },
+    AppError, AppState,
+};
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateMaintenanceWindowRequest {
+    #[schema(example = "2024-01-15T08:00:00Z")]
+    pub starts_at: DateTime<Utc>,
+    #[schema(example = "2024-01-15T12:00:00Z")]
+    pub ends_at: DateTime<Utc>,
+    /// Status the microscope is put into while the window is in effect (default: Maintenance)
+    pub kind: Option<MaintenanceKind>,
+    #[schema(example = "Objective lens replacement")]
+    pub reason: Option<String>,
+    /// Cancel overlapping bookings instead of only flagging them
+    #[schema(example = false)]
+    pub auto_cancel: Option<bool>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MaintenanceWindowResponse {
+    pub window: MaintenanceWindow,
+    /// Bookings overlapping the window (cancelled if `auto_cancel` was set)
+    pub conflicting_bookings: Vec<Booking>,
+}
+
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct MaintenanceQuery {
+    /// Include past and cancelled windows
+    #[schema(example = false)]
+    pub include_past: Option<bool>,
+}
+
+/// Schedule a maintenance window (teacher/admin only)
+#[utoipa::path(
+    post,
+    path = "/api/microscope/{microscope_id}/maintenance",
+    tag = "maintenance",
+    params(
+        ("microscope_id" = String, Path, description = "Microscope identifier")
+    ),
+    request_body = CreateMaintenanceWindowRequest,
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Maintenance window scheduled", body = ApiResponse<MaintenanceWindowResponse>),
+        (status = 400, description = "Invalid time range", body = ApiResponse<String>),
+        (status = 403, description = "Insufficient permissions", body = ApiResponse<String>),
+        (status = 404, description = "Microscope not found", body = ApiResponse<String>),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn create_maintenance_window(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(microscope_id): Path<String>,
+    Json(request): Json<CreateMaintenanceWindowRequest>,
+) -> Result<Json<ApiResponse<MaintenanceWindowResponse>>, AppError> {
+    match claims.role {
+        UserRole::Teacher | UserRole::Admin => {}
+        UserRole::Student => {
+            return Err(AppError::Authorization(
+                "Only teachers and admins can schedule maintenance".to_string(),
+            ))
+        }
+    }
+
+    if request.ends_at <= request.starts_at {
+        return Err(AppError::BadRequest(
+            "Maintenance window must end after it starts".to_string(),
+        ));
+    }
+
+    if !state.db.microscope_exists(&microscope_id).await? {
+        return Err(AppError::NotFound("Microscope not found".to_string()));
+    }
+
+    let window = MaintenanceWindow {
+        id: Uuid::new_v4(),
+        microscope_id: microscope_id.clone(),
+        kind: request.kind.unwrap_or(MaintenanceKind::Maintenance),
+        starts_at: request.starts_at,
+        ends_at: request.ends_at,
+        reason: request.reason,
+        created_by: claims.user_id,
+        cancelled_at: None,
+        created_at: Utc::now(),
+    };
+
+    // Record the window and cancel the bookings it displaces together, so a failure cannot
+    // leave a window whose conflicting bookings are still approved
+    let mut tx = state.db.begin_transaction().await?;
+    let window = state.db.create_maintenance_window(&mut tx, &window).await?;
+
+    let overlapping = state
+        .db
+        .get_bookings_overlapping_range(
+            &mut tx,
+            &microscope_id,
+            window.starts_at,
+            window.ends_at,
+            &state.config.booking.timezone,
+        )
+        .await?;
+
+    let auto_cancel = request.auto_cancel.unwrap_or(false);
+    let reason = window
+        .reason
+        .clone()
+        .unwrap_or_else(|| "scheduled maintenance".to_string());
+    let mut conflicting_bookings = Vec::with_capacity(overlapping.len());
+
+    for booking in overlapping {
+        let booking = if auto_cancel {
+            let cancel_reason = format!("Microscope unavailable: {}", reason);
+            let cancelled = state
+                .db
+                .cancel_booking(&mut tx, booking.id, Some(&cancel_reason))
+                .await?;
+            state
+                .db
+                .record_booking_event_tx(
+                    &mut tx,
+                    booking.id,
+                    BookingEventType::Cancelled,
+                    Some(claims.user_id),
+                    Some(&cancel_reason),
+                    Some(&booking),
+                    Some(&cancelled),
+                )
+                .await?;
+            cancelled
+        } else {
+            booking
+        };
+        conflicting_bookings.push(booking);
+    }
+
+    tx.commit().await?;
+
+    for booking in &conflicting_bookings {
+        let (kind, message) = if auto_cancel {
+            let message = format!(
+                "Your booking \"{}\" on {} was cancelled because {} is unavailable ({}).",
+                booking.title, booking.date, microscope_id, reason
+            );
+            (NotificationKind::BookingCancelled, message)
+        } else {
+            let message = format!(
+                "Your booking \"{}\" on {} overlaps a maintenance window on {} ({}). Please rebook.",
+                booking.title, booking.date, microscope_id, reason
+            );
+            (NotificationKind::MaintenanceConflict, message)
+        };
+
+        if let Err(e) = state
+            .db
+            .create_notification(booking.requester_id, kind, &message, Some(booking.id), None)
+            .await
+        {
+            tracing::warn!(
+                "Failed to notify user {} about booking {}: {}",
+                booking.requester_id,
+                booking.id,
+                e
+            );
+        }
+    }
+
+    // Apply the new status straight away if the window has already started
+    state.db.sync_microscope_maintenance_status().await?;
+
+    tracing::info!(
+        "Scheduled maintenance window {} on microscope {} ({} conflicting bookings, auto_cancel: {})",
+        window.id,
+        microscope_id,
+        conflicting_bookings.len(),
+        auto_cancel
+    );
+
+    Ok(Json(ApiResponse::success(MaintenanceWindowResponse {
+        window,
+        conflicting_bookings,
+    })))
+}
+
+/// List maintenance windows for a microscope
+#[utoipa::path(
+    get,
+    path = "/api/microscope/{microscope_id}/maintenance",
+    tag = "maintenance",
+    params(
+        ("microscope_id" = String, Path, description = "Microscope identifier"),
+        MaintenanceQuery
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Upcoming and current maintenance windows", body = ApiResponse<Vec<MaintenanceWindow>>),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn list_maintenance_windows(
+    State(state): State<AppState>,
+    Path(microscope_id): Path<String>,
+    Query(query): Query<MaintenanceQuery>,
+) -> Result<Json<ApiResponse<Vec<MaintenanceWindow>>>, AppError> {
+    let windows = state
+        .db
+        .list_maintenance_windows(&microscope_id, query.include_past.unwrap_or(false))
+        .await?;
+
+    Ok(Json(ApiResponse::success(windows)))
+}
+
+/// Cancel a maintenance window (teacher/admin only)
+#[utoipa::path(
+    delete,
+    path = "/api/maintenance/{id}",
+    tag = "maintenance",
+    params(
+        ("id" = Uuid, Path, description = "Maintenance window ID")
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 204, description = "Maintenance window cancelled"),
+        (status = 403, description = "Insufficient permissions", body = ApiResponse<String>),
+        (status = 404, description = "Maintenance window not found or already cancelled", body = ApiResponse<String>),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn cancel_maintenance_window(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(window_id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    match claims.role {
+        UserRole::Teacher | UserRole::Admin => {}
+        UserRole::Student => {
+            return Err(AppError::Authorization(
+                "Only teachers and admins can cancel maintenance".to_string(),
+            ))
+        }
+    }
+
+    if state.db.cancel_maintenance_window(window_id).await? == 0 {
+        return Err(AppError::NotFound(
+            "Maintenance window not found".to_string(),
+        ));
+    }
+
+    // Return the microscope to service straight away if this window was in effect
+    state.db.sync_microscope_maintenance_status().await?;
+
+    tracing::info!("Cancelled maintenance window {}", window_id);
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -2,14 +2,22 @@ use axum::{
     extract::{Path, State},
     http::StatusCode,
     response::Json,
+    Extension,
 };
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 use uuid::Uuid;
 
 use crate::{
-    models::{ApiResponse, CommandType, MicroscopeCommand},
-    services::ia_client::IAClient,
+    error::AppError,
+    handlers::images::{discard_stored_file, ensure_stored_file},
+    middleware::auth::Claims,
+    models::{ApiResponse, CommandType, MicroscopeCommand, SessionEventType},
+    services::{
+        file_storage::FileStorageError, ia_client::IAClient, image_info, session_timeline,
+        storage_quota,
+    },
     AppState,
 };
 
@@ -33,13 +41,30 @@ use crate::{
 )]
 pub async fn send_command(
     State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
     Path(microscope_id): Path<String>,
     Json(command): Json<MicroscopeCommand>,
 ) -> Result<Json<ApiResponse<CommandResponse>>, StatusCode> {
     let ia_client = IAClient::new(&state.config.ia);
 
     match ia_client.send_command(&microscope_id, &command).await {
-        Ok(response) => Ok(Json(ApiResponse::success(response))),
+        Ok(response) => {
+            record_activity(&state, &microscope_id).await;
+            session_timeline::record_on_microscope(
+                &state,
+                &microscope_id,
+                SessionEventType::Command,
+                Some(claims.user_id),
+                serde_json::json!({
+                    "command_type": command.command_type,
+                    "parameters": command.parameters,
+                    "success": response.success,
+                    "message": response.message,
+                }),
+            )
+            .await;
+            Ok(Json(ApiResponse::success(response)))
+        }
         Err(e) => {
             tracing::error!(
                 "Failed to send command to microscope {}: {}",
@@ -75,7 +100,10 @@ pub async fn get_status(
     let ia_client = IAClient::new(&state.config.ia);
 
     match ia_client.get_status(&microscope_id).await {
-        Ok(status) => Ok(Json(ApiResponse::success(status))),
+        Ok(mut status) => {
+            status.presence = session_presence(&state, &microscope_id).await;
+            Ok(Json(ApiResponse::success(status)))
+        }
         Err(e) => {
             tracing::error!(
                 "Failed to get status for microscope {}: {}",
@@ -102,18 +130,37 @@ pub async fn get_status(
     responses(
         (status = 200, description = "Image captured successfully", body = ApiResponse<CaptureResponse>),
         (status = 500, description = "Failed to capture image"),
+        (status = 502, description = "IA system returned an image that is not a valid allowed type"),
+        (status = 403, description = "The image does not fit in the session owner's storage quota"),
         (status = 401, description = "Unauthorized")
     )
 )]
 pub async fn capture_image(
     State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
     Path(microscope_id): Path<String>,
     Json(request): Json<CaptureRequest>,
 ) -> Result<Json<ApiResponse<CaptureResponse>>, StatusCode> {
     let ia_client = IAClient::new(&state.config.ia);
 
+    // Don't take a picture that could not be stored
+    let quota = storage_quota::quota_for_session(&state, request.session_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if let Some(quota) = quota.filter(|quota| quota.used_bytes >= quota.limit_bytes) {
+        tracing::warn!(
+            "Rejected capture for session {}: storage quota exceeded ({} of {} bytes used)",
+            request.session_id,
+            quota.used_bytes,
+            quota.limit_bytes
+        );
+        return Err(StatusCode::FORBIDDEN);
+    }
+
     match ia_client.capture_image(&microscope_id, &request).await {
         Ok(response) => {
+            record_activity(&state, &microscope_id).await;
+
             // Download the image file from IA system
             let image_bytes = match ia_client
                 .download_image(&microscope_id, &response.image_id)
@@ -130,13 +177,43 @@ pub async fn capture_image(
                 }
             };
 
+            // Check the payload really is the image type its filename claims, and read
+            // its dimensions and acquisition tags
+            let content_type = match state.file_store.allowed_content_type(&response.filename) {
+                Ok(content_type) => content_type,
+                Err(e) => {
+                    tracing::error!("Rejected image {} from IA system: {}", response.filename, e);
+                    return Err(StatusCode::BAD_GATEWAY);
+                }
+            };
+            let (image_bytes, info) = tokio::task::spawn_blocking(move || {
+                let info = image_info::inspect(&image_bytes, &content_type);
+                (image_bytes, info)
+            })
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+            let info = match info {
+                Ok(info) => info,
+                Err(e) => {
+                    tracing::error!("Rejected image {} from IA system: {}", response.filename, e);
+                    return Err(StatusCode::BAD_GATEWAY);
+                }
+            };
+
+            let mut metadata = response.metadata.clone();
+            info.exif.apply_to(&mut metadata);
+
             // Store image file in file storage
             let stored_file = match state
                 .file_store
-                .store_file(&response.filename, &image_bytes, request.session_id)
+                .store_file(&response.filename, &image_bytes, request.session_id, quota)
                 .await
             {
                 Ok(file_info) => file_info,
+                Err(e @ FileStorageError::QuotaExceeded(..)) => {
+                    tracing::warn!("Rejected image file {}: {}", response.filename, e);
+                    return Err(StatusCode::FORBIDDEN);
+                }
                 Err(e) => {
                     tracing::error!("Failed to store image file {}: {}", response.filename, e);
                     return Err(StatusCode::INTERNAL_SERVER_ERROR);
@@ -151,30 +228,46 @@ pub async fn capture_image(
                 file_path: stored_file.file_path.clone(),
                 content_type: stored_file.content_type.clone(),
                 file_size: stored_file.file_size as i64,
-                width: None,  // Could be extracted from image bytes if needed
-                height: None, // Could be extracted from image bytes if needed
-                metadata: response.metadata.clone(),
+                width: Some(info.width as i32),
+                height: Some(info.height as i32),
+                metadata: metadata.clone(),
                 captured_at: chrono::Utc::now(),
+                sha256: Some(stored_file.sha256.clone()),
+                starred: false,
             };
 
             // Save image metadata to database
-            if let Err(e) = state.db.create_image(&image).await {
+            if let Err(e) = storage_quota::create_image(&state, &image).await {
+                // Try to clean up the stored file
+                discard_stored_file(&state, &stored_file.file_path).await;
+                if let AppError::FileStorage(FileStorageError::QuotaExceeded(..)) = e {
+                    tracing::warn!("Rejected image {}: {}", response.image_id, e);
+                    return Err(StatusCode::FORBIDDEN);
+                }
                 tracing::error!(
                     "Failed to save image metadata to database for image {}: {}",
                     response.image_id,
                     e
                 );
-                // Try to clean up the stored file
-                if let Err(cleanup_err) = state.file_store.delete_file(&stored_file.file_path).await
-                {
-                    tracing::warn!(
-                        "Failed to clean up file {} after database error: {}",
-                        stored_file.file_path,
-                        cleanup_err
-                    );
-                }
                 return Err(StatusCode::INTERNAL_SERVER_ERROR);
             }
+            if let Err(e) = ensure_stored_file(&state, &stored_file.file_path, &image_bytes).await {
+                tracing::error!("Failed to store image {}: {}", response.image_id, e);
+                return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            }
+
+            session_timeline::record(
+                &state,
+                request.session_id,
+                SessionEventType::Capture,
+                Some(claims.user_id),
+                serde_json::json!({
+                    "image_id": image.id,
+                    "filename": image.filename,
+                    "microscope_id": microscope_id,
+                }),
+            )
+            .await;
 
             tracing::info!(
                 "Successfully captured and stored image {} for session {} ({})",
@@ -183,7 +276,10 @@ pub async fn capture_image(
                 stored_file.filename
             );
 
-            Ok(Json(ApiResponse::success(response)))
+            Ok(Json(ApiResponse::success(CaptureResponse {
+                metadata,
+                ..response
+            })))
         }
         Err(e) => {
             tracing::error!(
@@ -215,6 +311,7 @@ pub async fn capture_image(
 )]
 pub async fn auto_focus(
     State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
     Path(microscope_id): Path<String>,
 ) -> Result<Json<ApiResponse<FocusResponse>>, StatusCode> {
     let ia_client = IAClient::new(&state.config.ia);
@@ -225,11 +322,26 @@ pub async fn auto_focus(
     };
 
     match ia_client.send_command(&microscope_id, &command).await {
-        Ok(response) => Ok(Json(ApiResponse::success(FocusResponse {
-            success: response.success,
-            focus_score: response.data.get("focus_score").and_then(|v| v.as_f64()),
-            message: response.message,
-        }))),
+        Ok(response) => {
+            record_activity(&state, &microscope_id).await;
+            let focus = FocusResponse {
+                success: response.success,
+                focus_score: response.data.get("focus_score").and_then(|v| v.as_f64()),
+                message: response.message,
+            };
+            session_timeline::record_on_microscope(
+                &state,
+                &microscope_id,
+                SessionEventType::Focus,
+                Some(claims.user_id),
+                serde_json::json!({
+                    "success": focus.success,
+                    "focus_score": focus.focus_score,
+                }),
+            )
+            .await;
+            Ok(Json(ApiResponse::success(focus)))
+        }
         Err(e) => {
             tracing::error!("Failed to auto focus microscope {}: {}", microscope_id, e);
             Err(StatusCode::INTERNAL_SERVER_ERROR)
@@ -257,6 +369,7 @@ pub async fn auto_focus(
 )]
 pub async fn start_tracking(
     State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
     Path(microscope_id): Path<String>,
     Json(request): Json<TrackingRequest>,
 ) -> Result<Json<ApiResponse<TrackingResponse>>, StatusCode> {
@@ -268,14 +381,30 @@ pub async fn start_tracking(
     };
 
     match ia_client.send_command(&microscope_id, &command).await {
-        Ok(response) => Ok(Json(ApiResponse::success(TrackingResponse {
-            tracking_id: response
-                .data
-                .get("tracking_id")
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string()),
-            status: "started".to_string(),
-        }))),
+        Ok(response) => {
+            record_activity(&state, &microscope_id).await;
+            let tracking = TrackingResponse {
+                tracking_id: response
+                    .data
+                    .get("tracking_id")
+                    .and_then(|v| v.as_str())
+                    .map(|s| s.to_string()),
+                status: "started".to_string(),
+            };
+            session_timeline::record_on_microscope(
+                &state,
+                &microscope_id,
+                SessionEventType::TrackingStarted,
+                Some(claims.user_id),
+                serde_json::json!({
+                    "tracking_id": tracking.tracking_id,
+                    "target_object": command.parameters.get("target_object"),
+                    "detection_threshold": command.parameters.get("detection_threshold"),
+                }),
+            )
+            .await;
+            Ok(Json(ApiResponse::success(tracking)))
+        }
         Err(e) => {
             tracing::error!(
                 "Failed to start tracking on microscope {}: {}",
@@ -306,6 +435,7 @@ pub async fn start_tracking(
 )]
 pub async fn stop_tracking(
     State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
     Path(microscope_id): Path<String>,
 ) -> Result<Json<ApiResponse<TrackingResponse>>, StatusCode> {
     let ia_client = IAClient::new(&state.config.ia);
@@ -316,10 +446,21 @@ pub async fn stop_tracking(
     };
 
     match ia_client.send_command(&microscope_id, &command).await {
-        Ok(_response) => Ok(Json(ApiResponse::success(TrackingResponse {
-            tracking_id: None,
-            status: "stopped".to_string(),
-        }))),
+        Ok(_response) => {
+            record_activity(&state, &microscope_id).await;
+            session_timeline::record_on_microscope(
+                &state,
+                &microscope_id,
+                SessionEventType::TrackingStopped,
+                Some(claims.user_id),
+                serde_json::json!({}),
+            )
+            .await;
+            Ok(Json(ApiResponse::success(TrackingResponse {
+                tracking_id: None,
+                status: "stopped".to_string(),
+            })))
+        }
         Err(e) => {
             tracing::error!(
                 "Failed to stop tracking on microscope {}: {}",
@@ -331,6 +472,54 @@ pub async fn stop_tracking(
     }
 }
 
+/// Look up the user holding the microscope, from our own session records
+async fn session_presence(state: &AppState, microscope_id: &str) -> Option<SessionPresence> {
+    let session = match state
+        .db
+        .get_active_session_by_microscope(microscope_id)
+        .await
+    {
+        Ok(session) => session?,
+        Err(e) => {
+            tracing::warn!(
+                "Failed to load active session for microscope {}: {}",
+                microscope_id,
+                e
+            );
+            return None;
+        }
+    };
+
+    let user_name = match state.db.get_user_by_id(session.user_id).await {
+        Ok(user) => user.map(|u| u.name),
+        Err(e) => {
+            tracing::warn!("Failed to load user {}: {}", session.user_id, e);
+            None
+        }
+    };
+
+    let session = session.with_presence(state.config.session.presence_timeout, Utc::now());
+    Some(SessionPresence {
+        session_id: session.id,
+        user_id: session.user_id,
+        user_name,
+        started_at: session.started_at,
+        last_seen_at: session.last_seen_at,
+        present: session.present,
+    })
+}
+
+/// Mark the microscope's active session as in use so it is not aborted as idle
+async fn record_activity(state: &AppState, microscope_id: &str) {
+    if let Err(e) = state.db.touch_session_activity(microscope_id).await {
+        tracing::warn!(
+            "Failed to record session activity on microscope {}: {}",
+            microscope_id,
+            e
+        );
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct CommandResponse {
     #[schema(example = true)]
@@ -347,6 +536,9 @@ pub struct MicroscopeStatus {
     #[schema(example = true)]
     pub is_connected: bool,
     pub current_session: Option<Uuid>,
+    /// Who holds the microscope's active session and whether they are still there
+    #[serde(default)]
+    pub presence: Option<SessionPresence>,
     pub position: Position,
     pub focus: FocusInfo,
     #[schema(example = "400x")]
@@ -356,6 +548,18 @@ pub struct MicroscopeStatus {
     pub tracking_active: bool,
 }
 
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SessionPresence {
+    pub session_id: Uuid,
+    pub user_id: Uuid,
+    #[schema(example = "Student User")]
+    pub user_name: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub last_seen_at: Option<DateTime<Utc>>,
+    #[schema(example = true)]
+    pub present: bool,
+}
+
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct Position {
     #[schema(example = 100.5)]
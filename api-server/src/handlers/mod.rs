@@ -3,11 +3,18 @@ use utoipa;
 
 use crate::models::ApiResponse;
 
+pub mod analytics;
+pub mod approval_rules;
 pub mod auth;
 pub mod bookings;
 pub mod images;
+pub mod maintenance;
 pub mod microscope;
+pub mod notifications;
+pub mod retention;
+pub mod session_notes;
 pub mod sessions;
+pub mod storage;
 
 /// Health check endpoint
 #[utoipa::path(
@@ -0,0 +1,95 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+    Extension,
+};
+use serde::Deserialize;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::{
+    middleware::auth::Claims,
+    models::{ApiResponse, Notification},
+    AppError, AppState,
+};
+
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct NotificationQuery {
+    #[schema(example = true)]
+    pub unread_only: Option<bool>,
+    #[schema(example = 1)]
+    pub page: Option<u64>,
+    #[schema(example = 20)]
+    pub limit: Option<u64>,
+}
+
+/// List the current user's notifications
+#[utoipa::path(
+    get,
+    path = "/api/notifications",
+    tag = "notifications",
+    params(NotificationQuery),
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Notifications, newest first", body = ApiResponse<Vec<Notification>>),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn list_notifications(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Query(query): Query<NotificationQuery>,
+) -> Result<Json<ApiResponse<Vec<Notification>>>, AppError> {
+    let limit = query.limit.unwrap_or(20).min(100);
+    let page = query.page.unwrap_or(1).max(1);
+    let offset = (page - 1) * limit;
+
+    let notifications = state
+        .db
+        .list_notifications(
+            claims.user_id,
+            query.unread_only.unwrap_or(false),
+            limit,
+            offset,
+        )
+        .await?;
+
+    Ok(Json(ApiResponse::success(notifications)))
+}
+
+/// Mark a notification as read
+#[utoipa::path(
+    post,
+    path = "/api/notifications/{id}/read",
+    tag = "notifications",
+    params(
+        ("id" = Uuid, Path, description = "Notification ID")
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 204, description = "Notification marked as read"),
+        (status = 404, description = "Notification not found", body = ApiResponse<String>),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn mark_notification_read(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(notification_id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    if state
+        .db
+        .mark_notification_read(notification_id, claims.user_id)
+        .await?
+        == 0
+    {
+        return Err(AppError::NotFound("Notification not found".to_string()));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,363 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+    Extension,
+};
+use chrono::{NaiveDate, Utc};
+use serde::Deserialize;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::{
+    middleware::auth::{ensure_admin, Claims},
+    models::{AcademicTerm, ApiResponse, RetentionPolicy, RetentionReport, UserRole},
+    services::retention,
+    AppError, AppState,
+};
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct RetentionPolicyRequest {
+    #[schema(example = "Delete student captures 180 days after term end")]
+    pub name: String,
+    /// Policies are evaluated in ascending priority; the first match decides
+    #[schema(example = 100)]
+    pub priority: i32,
+    /// Roles of the session owner the policy applies to (empty matches any role)
+    #[serde(default)]
+    pub owner_roles: Vec<UserRole>,
+    /// Only match images captured on this microscope
+    #[schema(example = "bio-1")]
+    pub microscope_id: Option<String>,
+    /// Days matching images are kept; omit to keep them forever
+    #[schema(example = 180)]
+    pub retain_days: Option<i32>,
+    /// Count `retain_days` from the end of the term the image was captured in
+    #[serde(default)]
+    pub after_term_end: bool,
+    /// Never expire starred images (default true)
+    pub keep_starred: Option<bool>,
+    /// Never expire images from exported sessions (default true)
+    pub keep_exported: Option<bool>,
+    #[schema(example = true)]
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct AcademicTermRequest {
+    #[schema(example = "Semester 1 2026")]
+    pub name: String,
+    #[schema(example = "2026-02-23")]
+    pub start_date: NaiveDate,
+    /// Last day of the term
+    #[schema(example = "2026-06-26")]
+    pub end_date: NaiveDate,
+}
+
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct RetentionRunQuery {
+    /// Only report which images would be moved to the trash
+    pub dry_run: Option<bool>,
+}
+
+async fn validate_policy(
+    state: &AppState,
+    request: &RetentionPolicyRequest,
+) -> Result<(), AppError> {
+    if request.name.trim().is_empty() {
+        return Err(AppError::BadRequest("Policy name is required".to_string()));
+    }
+
+    if request.retain_days.is_some_and(|days| days < 0) {
+        return Err(AppError::BadRequest(
+            "retain_days cannot be negative".to_string(),
+        ));
+    }
+
+    if let Some(microscope_id) = &request.microscope_id {
+        if !state.db.microscope_exists(microscope_id).await? {
+            return Err(AppError::BadRequest(format!(
+                "Unknown microscope: {}",
+                microscope_id
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+fn build_policy(id: Uuid, request: RetentionPolicyRequest) -> RetentionPolicy {
+    RetentionPolicy {
+        id,
+        name: request.name,
+        priority: request.priority,
+        owner_roles: request.owner_roles,
+        microscope_id: request.microscope_id,
+        retain_days: request.retain_days,
+        after_term_end: request.after_term_end,
+        keep_starred: request.keep_starred.unwrap_or(true),
+        keep_exported: request.keep_exported.unwrap_or(true),
+        enabled: request.enabled.unwrap_or(true),
+        created_at: Utc::now(),
+    }
+}
+
+/// List retention policies in evaluation order (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/retention/policies",
+    tag = "retention",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Retention policies ordered by priority", body = ApiResponse<Vec<RetentionPolicy>>),
+        (status = 403, description = "Admin only", body = ApiResponse<String>),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn list_retention_policies(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<ApiResponse<Vec<RetentionPolicy>>>, AppError> {
+    ensure_admin(&claims, "manage retention policies")?;
+
+    let policies = state.db.list_retention_policies().await?;
+    Ok(Json(ApiResponse::success(policies)))
+}
+
+/// Create a retention policy (admin only)
+#[utoipa::path(
+    post,
+    path = "/api/retention/policies",
+    tag = "retention",
+    request_body = RetentionPolicyRequest,
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Retention policy created", body = ApiResponse<RetentionPolicy>),
+        (status = 400, description = "Invalid policy", body = ApiResponse<String>),
+        (status = 403, description = "Admin only", body = ApiResponse<String>),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn create_retention_policy(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(request): Json<RetentionPolicyRequest>,
+) -> Result<Json<ApiResponse<RetentionPolicy>>, AppError> {
+    ensure_admin(&claims, "manage retention policies")?;
+    validate_policy(&state, &request).await?;
+
+    let policy = state
+        .db
+        .upsert_retention_policy(&build_policy(Uuid::new_v4(), request))
+        .await?;
+
+    tracing::info!("Created retention policy {} ({})", policy.id, policy.name);
+    Ok(Json(ApiResponse::success(policy)))
+}
+
+/// Replace a retention policy (admin only)
+#[utoipa::path(
+    put,
+    path = "/api/retention/policies/{id}",
+    tag = "retention",
+    params(
+        ("id" = Uuid, Path, description = "Retention policy ID")
+    ),
+    request_body = RetentionPolicyRequest,
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Retention policy updated", body = ApiResponse<RetentionPolicy>),
+        (status = 400, description = "Invalid policy", body = ApiResponse<String>),
+        (status = 403, description = "Admin only", body = ApiResponse<String>),
+        (status = 404, description = "Retention policy not found", body = ApiResponse<String>),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn update_retention_policy(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(policy_id): Path<Uuid>,
+    Json(request): Json<RetentionPolicyRequest>,
+) -> Result<Json<ApiResponse<RetentionPolicy>>, AppError> {
+    ensure_admin(&claims, "manage retention policies")?;
+    validate_policy(&state, &request).await?;
+
+    if !state.db.retention_policy_exists(policy_id).await? {
+        return Err(AppError::NotFound("Retention policy not found".to_string()));
+    }
+
+    let policy = state
+        .db
+        .upsert_retention_policy(&build_policy(policy_id, request))
+        .await?;
+
+    tracing::info!("Updated retention policy {} ({})", policy.id, policy.name);
+    Ok(Json(ApiResponse::success(policy)))
+}
+
+/// Delete a retention policy (admin only)
+#[utoipa::path(
+    delete,
+    path = "/api/retention/policies/{id}",
+    tag = "retention",
+    params(
+        ("id" = Uuid, Path, description = "Retention policy ID")
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 204, description = "Retention policy deleted"),
+        (status = 403, description = "Admin only", body = ApiResponse<String>),
+        (status = 404, description = "Retention policy not found", body = ApiResponse<String>),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn delete_retention_policy(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(policy_id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    ensure_admin(&claims, "manage retention policies")?;
+
+    if state.db.delete_retention_policy(policy_id).await? == 0 {
+        return Err(AppError::NotFound("Retention policy not found".to_string()));
+    }
+
+    tracing::info!("Deleted retention policy {}", policy_id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// List academic terms (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/retention/terms",
+    tag = "retention",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Academic terms ordered by start date", body = ApiResponse<Vec<AcademicTerm>>),
+        (status = 403, description = "Admin only", body = ApiResponse<String>),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn list_academic_terms(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<ApiResponse<Vec<AcademicTerm>>>, AppError> {
+    ensure_admin(&claims, "manage retention policies")?;
+
+    let terms = state.db.list_academic_terms().await?;
+    Ok(Json(ApiResponse::success(terms)))
+}
+
+/// Add an academic term, used by policies counted from the end of term (admin only)
+#[utoipa::path(
+    post,
+    path = "/api/retention/terms",
+    tag = "retention",
+    request_body = AcademicTermRequest,
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Academic term created", body = ApiResponse<AcademicTerm>),
+        (status = 400, description = "Invalid term", body = ApiResponse<String>),
+        (status = 403, description = "Admin only", body = ApiResponse<String>),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn create_academic_term(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(request): Json<AcademicTermRequest>,
+) -> Result<Json<ApiResponse<AcademicTerm>>, AppError> {
+    ensure_admin(&claims, "manage retention policies")?;
+
+    if request.name.trim().is_empty() {
+        return Err(AppError::BadRequest("Term name is required".to_string()));
+    }
+    if request.end_date < request.start_date {
+        return Err(AppError::BadRequest(
+            "Term cannot end before it starts".to_string(),
+        ));
+    }
+
+    let term = state
+        .db
+        .create_academic_term(&request.name, request.start_date, request.end_date)
+        .await?;
+
+    tracing::info!("Created academic term {} ({})", term.id, term.name);
+    Ok(Json(ApiResponse::success(term)))
+}
+
+/// Delete an academic term (admin only)
+#[utoipa::path(
+    delete,
+    path = "/api/retention/terms/{id}",
+    tag = "retention",
+    params(
+        ("id" = Uuid, Path, description = "Academic term ID")
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 204, description = "Academic term deleted"),
+        (status = 403, description = "Admin only", body = ApiResponse<String>),
+        (status = 404, description = "Academic term not found", body = ApiResponse<String>),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn delete_academic_term(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(term_id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    ensure_admin(&claims, "manage retention policies")?;
+
+    if state.db.delete_academic_term(term_id).await? == 0 {
+        return Err(AppError::NotFound("Academic term not found".to_string()));
+    }
+
+    tracing::info!("Deleted academic term {}", term_id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Apply the retention policies now
+///
+/// Expired images are moved to the trash, where they can be restored until the trash is
+/// purged, unless `dry_run` is set. The same job runs every `STORAGE_JOBS_INTERVAL` seconds.
+#[utoipa::path(
+    post,
+    path = "/api/retention/run",
+    tag = "retention",
+    params(RetentionRunQuery),
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Retention report", body = ApiResponse<RetentionReport>),
+        (status = 403, description = "Admin only", body = ApiResponse<String>),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn run_retention(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Query(query): Query<RetentionRunQuery>,
+) -> Result<Json<ApiResponse<RetentionReport>>, AppError> {
+    ensure_admin(&claims, "manage retention policies")?;
+
+    let report = retention::apply_policies(&state, query.dry_run.unwrap_or(false)).await?;
+
+    Ok(Json(ApiResponse::success(report)))
+}
@@ -0,0 +1,323 @@
+use axum::{
+    extract::{Path, State},
+    response::Json,
+    Extension,
+};
+use chrono::Utc;
+use serde::Deserialize;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{
+    handlers::sessions::ensure_session_access,
+    middleware::auth::Claims,
+    models::{
+        ApiResponse, Measurement, Session, SessionEventType, SessionNote, SessionNoteKind,
+        SessionStatus, UserRole,
+    },
+    services::session_timeline,
+    AppError, AppState,
+};
+
+/// Longest notebook entry accepted, in bytes of Markdown
+const MAX_NOTE_LENGTH: usize = 20_000;
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateSessionNoteRequest {
+    pub kind: SessionNoteKind,
+    /// Entry text in Markdown (required for `Text` entries)
+    #[schema(example = "Cells in **metaphase** near the top-left of the slide")]
+    pub body: Option<String>,
+    /// Image from this session the entry refers to (required for `Image` entries)
+    pub image_id: Option<Uuid>,
+    /// Recorded value (required for `Measurement` entries)
+    pub measurement: Option<Measurement>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateSessionNoteRequest {
+    /// Replacement Markdown text; omitted fields are left unchanged
+    #[schema(example = "Cells in **anaphase** near the top-left of the slide")]
+    pub body: Option<String>,
+    pub image_id: Option<Uuid>,
+    pub measurement: Option<Measurement>,
+}
+
+async fn get_session(state: &AppState, session_id: Uuid) -> Result<Session, AppError> {
+    state
+        .db
+        .get_session_by_id(session_id)
+        .await?
+        .ok_or(AppError::NotFound("Session not found".to_string()))
+}
+
+/// Students write in the notebook of sessions they share, while the session is active.
+/// Teachers and admins can add entries at any time, e.g. when reviewing a session.
+async fn ensure_can_write(
+    state: &AppState,
+    claims: &Claims,
+    session: &Session,
+) -> Result<(), AppError> {
+    ensure_session_access(state, claims, session).await?;
+
+    match claims.role {
+        UserRole::Student => {
+            if session.status != SessionStatus::Active {
+                return Err(AppError::BadRequest(
+                    "Notebook entries can only be written while the session is active".to_string(),
+                ));
+            }
+        }
+        UserRole::Teacher | UserRole::Admin => {}
+    }
+    Ok(())
+}
+
+async fn validate_note(
+    state: &AppState,
+    session: &Session,
+    kind: SessionNoteKind,
+    body: &str,
+    image_id: Option<Uuid>,
+    measurement: Option<&Measurement>,
+) -> Result<(), AppError> {
+    if body.len() > MAX_NOTE_LENGTH {
+        return Err(AppError::BadRequest(format!(
+            "Notebook entries are limited to {} characters",
+            MAX_NOTE_LENGTH
+        )));
+    }
+
+    match kind {
+        SessionNoteKind::Text => {
+            if body.trim().is_empty() {
+                return Err(AppError::BadRequest("Text entries need a body".to_string()));
+            }
+        }
+        SessionNoteKind::Image => {
+            if image_id.is_none() {
+                return Err(AppError::BadRequest(
+                    "Image entries need an image_id".to_string(),
+                ));
+            }
+        }
+        SessionNoteKind::Measurement => {
+            let Some(measurement) = measurement else {
+                return Err(AppError::BadRequest(
+                    "Measurement entries need a measurement".to_string(),
+                ));
+            };
+            if measurement.name.trim().is_empty() || !measurement.value.is_finite() {
+                return Err(AppError::BadRequest(
+                    "Measurements need a name and a finite value".to_string(),
+                ));
+            }
+        }
+    }
+
+    if measurement.is_some() && kind != SessionNoteKind::Measurement {
+        return Err(AppError::BadRequest(
+            "Only measurement entries can record a measurement".to_string(),
+        ));
+    }
+
+    if let Some(image_id) = image_id {
+        let image = state
+            .db
+            .get_image_by_id(image_id)
+            .await?
+            .ok_or(AppError::NotFound("Image not found".to_string()))?;
+        if image.session_id != session.id {
+            return Err(AppError::BadRequest(
+                "Image does not belong to this session".to_string(),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// List the notebook entries of a session, oldest first
+#[utoipa::path(
+    get,
+    path = "/api/sessions/{id}/notes",
+    tag = "sessions",
+    params(
+        ("id" = Uuid, Path, description = "Session ID")
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Notebook entries", body = ApiResponse<Vec<SessionNote>>),
+        (status = 403, description = "Access denied - can only view own or shared sessions unless admin/teacher", body = ApiResponse<String>),
+        (status = 404, description = "Session not found", body = ApiResponse<String>),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn list_session_notes(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(session_id): Path<Uuid>,
+) -> Result<Json<ApiResponse<Vec<SessionNote>>>, AppError> {
+    let session = get_session(&state, session_id).await?;
+    ensure_session_access(&state, &claims, &session).await?;
+
+    let notes = state.db.list_session_notes(session.id).await?;
+    Ok(Json(ApiResponse::success(notes)))
+}
+
+/// Add a notebook entry to a session
+///
+/// Entries are text, linked to one of the session's images, or a measurement. The body
+/// supports simple Markdown.
+#[utoipa::path(
+    post,
+    path = "/api/sessions/{id}/notes",
+    tag = "sessions",
+    params(
+        ("id" = Uuid, Path, description = "Session ID")
+    ),
+    request_body = CreateSessionNoteRequest,
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Notebook entry added", body = ApiResponse<SessionNote>),
+        (status = 400, description = "Invalid entry, or session no longer active", body = ApiResponse<String>),
+        (status = 403, description = "Access denied - can only write in own or shared sessions unless admin/teacher", body = ApiResponse<String>),
+        (status = 404, description = "Session or image not found", body = ApiResponse<String>),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn create_session_note(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(session_id): Path<Uuid>,
+    Json(request): Json<CreateSessionNoteRequest>,
+) -> Result<Json<ApiResponse<SessionNote>>, AppError> {
+    let session = get_session(&state, session_id).await?;
+    ensure_can_write(&state, &claims, &session).await?;
+
+    let body = request.body.unwrap_or_default();
+    validate_note(
+        &state,
+        &session,
+        request.kind,
+        &body,
+        request.image_id,
+        request.measurement.as_ref(),
+    )
+    .await?;
+
+    let now = Utc::now();
+    let note = state
+        .db
+        .create_session_note(&SessionNote {
+            id: Uuid::new_v4(),
+            session_id: session.id,
+            author_id: Some(claims.user_id),
+            kind: request.kind,
+            body,
+            body_html: String::new(),
+            image_id: request.image_id,
+            measurement: request.measurement,
+            created_at: now,
+            updated_at: now,
+        })
+        .await?;
+
+    session_timeline::record(
+        &state,
+        session.id,
+        SessionEventType::NoteAdded,
+        Some(claims.user_id),
+        serde_json::json!({ "note_id": note.id, "kind": note.kind }),
+    )
+    .await;
+
+    tracing::info!(
+        "Added {:?} notebook entry {} to session {}",
+        note.kind,
+        note.id,
+        session.id
+    );
+    Ok(Json(ApiResponse::success(note)))
+}
+
+/// Edit a notebook entry (author only)
+#[utoipa::path(
+    put,
+    path = "/api/sessions/{id}/notes/{note_id}",
+    tag = "sessions",
+    params(
+        ("id" = Uuid, Path, description = "Session ID"),
+        ("note_id" = Uuid, Path, description = "Notebook entry ID")
+    ),
+    request_body = UpdateSessionNoteRequest,
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Notebook entry updated", body = ApiResponse<SessionNote>),
+        (status = 400, description = "Invalid entry, or session no longer active", body = ApiResponse<String>),
+        (status = 403, description = "Access denied - only the author can edit an entry", body = ApiResponse<String>),
+        (status = 404, description = "Session, entry or image not found", body = ApiResponse<String>),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn update_session_note(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path((session_id, note_id)): Path<(Uuid, Uuid)>,
+    Json(request): Json<UpdateSessionNoteRequest>,
+) -> Result<Json<ApiResponse<SessionNote>>, AppError> {
+    let session = get_session(&state, session_id).await?;
+    ensure_can_write(&state, &claims, &session).await?;
+
+    let note = state
+        .db
+        .get_session_note(note_id)
+        .await?
+        .filter(|note| note.session_id == session.id)
+        .ok_or(AppError::NotFound("Notebook entry not found".to_string()))?;
+
+    if note.author_id != Some(claims.user_id) {
+        return Err(AppError::Authorization(
+            "Only the author can edit a notebook entry".to_string(),
+        ));
+    }
+
+    // Fields left out of the request keep their current value
+    validate_note(
+        &state,
+        &session,
+        note.kind,
+        request.body.as_deref().unwrap_or(&note.body),
+        request.image_id.or(note.image_id),
+        request.measurement.as_ref().or(note.measurement.as_ref()),
+    )
+    .await?;
+
+    let note = state
+        .db
+        .update_session_note(
+            note.id,
+            request.body.as_deref(),
+            request.image_id,
+            request.measurement.as_ref(),
+        )
+        .await?
+        .ok_or(AppError::NotFound("Notebook entry not found".to_string()))?;
+
+    session_timeline::record(
+        &state,
+        session.id,
+        SessionEventType::NoteUpdated,
+        Some(claims.user_id),
+        serde_json::json!({ "note_id": note.id, "kind": note.kind }),
+    )
+    .await;
+
+    Ok(Json(ApiResponse::success(note)))
+}
@@ -1,16 +1,33 @@
 use axum::{
+    body::Body,
     extract::{Path, Query, State},
-    response::Json,
+    http::header,
+    response::{IntoResponse, Json, Response},
     Extension,
 };
-use serde::Deserialize;
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use futures::{stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::sync::oneshot;
+use tokio_util::io::ReaderStream;
 use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 use validator::Validate;
 
 use crate::{
     middleware::auth::Claims,
-    models::{ApiResponse, Session, SessionStatus, UserRole},
+    models::{
+        ApiResponse, HandoverKind, NotificationKind, PageRequest, Paginated, Session, SessionEvent,
+        SessionEventType, SessionHandover, SessionNote, SessionParticipant, SessionSortField,
+        SessionStatus, SortOrder, UserRole,
+    },
+    services::{
+        database::{SessionFilter, ACTIVE_SESSION_PER_MICROSCOPE_INDEX},
+        session_export::{self, SessionExport},
+        session_lifecycle::{self, EndSession},
+        session_timeline,
+    },
     AppError, AppState,
 };
 
@@ -32,16 +49,103 @@ pub struct SessionQuery {
     pub status: Option<SessionStatus>,
     #[schema(example = true)]
     pub active_only: Option<bool>,
+    pub booking_id: Option<Uuid>,
+    /// Group name of the booking the session was started from
+    #[schema(example = "Biology 101")]
+    pub group: Option<String>,
+    /// Only sessions started at or after this time
+    #[schema(example = "2024-01-01T00:00:00Z")]
+    pub started_from: Option<DateTime<Utc>>,
+    /// Only sessions started before this time
+    #[schema(example = "2024-02-01T00:00:00Z")]
+    pub started_to: Option<DateTime<Utc>>,
+    pub sort_by: Option<SessionSortField>,
+    pub order: Option<SortOrder>,
     #[schema(example = 1)]
     pub page: Option<u64>,
     #[schema(example = 20)]
     pub limit: Option<u64>,
+    /// Cursor from a previous page's `next_cursor`; pass it empty to start paging by cursor.
+    /// Cursor pages are in creation order and cannot be combined with `page`.
+    pub cursor: Option<String>,
 }
 
 #[derive(Debug, Deserialize, ToSchema)]
 pub struct EndSessionRequest {
     #[schema(example = "Completed cell division observation. Found 15 dividing cells.")]
     pub notes: Option<String>,
+    /// Abort the session instead of completing it (teacher/admin only, requires a reason)
+    #[schema(example = false)]
+    pub abort: Option<bool>,
+    /// Why the session is being ended; recorded on the session and sent to its owner
+    #[schema(example = "Student left the microscope unattended")]
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct HandoverRequest {
+    /// User taking over the session, or joining it as a co-participant
+    pub user_id: Uuid,
+    /// Defaults to `Transfer`
+    pub kind: Option<HandoverKind>,
+    #[schema(example = "Taking over for the afternoon measurements")]
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct TimelineQuery {
+    /// Comma separated event types to include (default: all)
+    #[schema(example = "Command,Capture")]
+    pub types: Option<String>,
+    #[schema(example = 1)]
+    pub page: Option<u64>,
+    #[schema(example = 50)]
+    pub limit: Option<u64>,
+}
+
+/// Session with its lab notebook entries
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SessionDetail {
+    #[serde(flatten)]
+    pub session: Session,
+    /// Notebook entries, oldest first
+    pub notebook: Vec<SessionNote>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SessionParticipants {
+    pub session_id: Uuid,
+    /// Current owner of the session
+    pub owner_id: Uuid,
+    /// Co-participants and previous owners
+    pub participants: Vec<SessionParticipant>,
+    /// Transfers and added participants, oldest first
+    pub handovers: Vec<SessionHandover>,
+}
+
+/// Students may only access sessions they own or share (co-participants and previous owners)
+pub(crate) async fn ensure_session_access(
+    state: &AppState,
+    claims: &Claims,
+    session: &Session,
+) -> Result<(), AppError> {
+    match claims.role {
+        UserRole::Student => {
+            if !state
+                .db
+                .is_session_participant(session.id, claims.user_id)
+                .await?
+            {
+                return Err(AppError::Authorization(
+                    "Access denied - can only view own sessions".to_string(),
+                ));
+            }
+        }
+        UserRole::Teacher | UserRole::Admin => {
+            // Teachers and admins can access all sessions
+        }
+    }
+    Ok(())
 }
 
 /// List sessions with filtering
@@ -54,7 +158,8 @@ pub struct EndSessionRequest {
         ("bearer_auth" = [])
     ),
     responses(
-        (status = 200, description = "List of sessions (filtered by user role)", body = ApiResponse<Vec<Session>>),
+        (status = 200, description = "List of sessions (filtered by user role), with user presence", body = ApiResponse<Paginated<Session>>),
+        (status = 400, description = "Invalid filter, page or cursor", body = ApiResponse<String>),
         (status = 401, description = "Unauthorized")
     )
 )]
@@ -62,37 +167,62 @@ pub async fn list_sessions(
     State(state): State<AppState>,
     Extension(claims): Extension<Claims>,
     Query(query): Query<SessionQuery>,
-) -> Result<Json<ApiResponse<Vec<Session>>>, AppError> {
-    let limit = query.limit.unwrap_or(20).min(100);
-    let page = query.page.unwrap_or(1).max(1);
-    let offset = (page - 1) * limit;
+) -> Result<Json<ApiResponse<Paginated<Session>>>, AppError> {
+    let page = PageRequest::new(query.page, query.limit, query.cursor.as_deref(), 20, 100)
+        .map_err(AppError::BadRequest)?;
+    if page.by_cursor && (query.sort_by.is_some() || query.order.is_some()) {
+        return Err(AppError::BadRequest(
+            "Cursor pages are in creation order and cannot be sorted".to_string(),
+        ));
+    }
+
+    if let (Some(from), Some(to)) = (query.started_from, query.started_to) {
+        if from >= to {
+            return Err(AppError::BadRequest(
+                "started_from must be before started_to".to_string(),
+            ));
+        }
+    }
 
     // Role-based filtering
-    let (microscope_id, user_id) = match claims.role {
+    let user_id = match claims.role {
         UserRole::Student => {
-            // Students can only see their own sessions
-            (query.microscope_id.as_deref(), Some(claims.user_id))
+            // Students can only see sessions they own or participate in
+            Some(claims.user_id)
         }
         UserRole::Teacher | UserRole::Admin => {
             // Teachers and admins can see all sessions with optional filtering
-            (query.microscope_id.as_deref(), query.user_id)
+            query.user_id
         }
     };
 
-    let active_only = query.active_only.unwrap_or(false);
+    let filter = SessionFilter {
+        microscope_id: query.microscope_id.as_deref(),
+        user_id,
+        status: query.status,
+        active_only: query.active_only.unwrap_or(false),
+        booking_id: query.booking_id,
+        group_name: query.group.as_deref(),
+        started_from: query.started_from,
+        started_to: query.started_to,
+    };
 
-    let sessions = state
+    let (sessions, total, next_cursor) = state
         .db
         .list_sessions(
-            microscope_id,
-            user_id,
-            query.status,
-            active_only,
-            limit,
-            offset,
+            &filter,
+            query.sort_by.unwrap_or_default(),
+            query.order.unwrap_or_default(),
+            &page,
         )
         .await?;
 
+    let now = chrono::Utc::now();
+    let timeout = state.config.session.presence_timeout;
+    let sessions = Paginated::new(sessions, total, page)
+        .with_next_cursor(next_cursor)
+        .map(|s| s.with_presence(timeout, now));
+
     Ok(Json(ApiResponse::success(sessions)))
 }
 
@@ -108,6 +238,8 @@ pub async fn list_sessions(
     responses(
         (status = 200, description = "Session started successfully", body = ApiResponse<Session>),
         (status = 400, description = "Invalid session data or microscope unavailable", body = ApiResponse<String>),
+        (status = 409, description = "Microscope already has an active session", body = ApiResponse<String>),
+        (status = 500, description = "IA system rejected the session; nothing was started", body = ApiResponse<String>),
         (status = 401, description = "Unauthorized")
     )
 )]
@@ -128,6 +260,18 @@ pub async fn create_session(
         )));
     }
 
+    // Check the microscope is not out of service
+    if let Some(window) = state
+        .db
+        .get_active_maintenance_window(&request.microscope_id)
+        .await?
+    {
+        return Ok(Json(ApiResponse::error(format!(
+            "Cannot start session - microscope is unavailable ({:?}) until {}",
+            window.kind, window.ends_at
+        ))));
+    }
+
     // Check if user has an approved booking for this time (if booking_id provided)
     if let Some(booking_id) = request.booking_id {
         let booking = state
@@ -176,10 +320,56 @@ pub async fn create_session(
         started_at: chrono::Utc::now(),
         ended_at: None,
         notes: request.notes,
+        ended_by: None,
+        end_reason: None,
+        last_seen_at: None,
+        present: false,
     };
 
-    // Save to database
-    let created_session = state.db.create_session(&session).await?;
+    // Save to database; the partial unique index makes this an atomic claim on the microscope
+    let created_session = match state.db.create_session(&session).await {
+        Ok(created) => created,
+        Err(sqlx::Error::Database(e))
+            if e.constraint() == Some(ACTIVE_SESSION_PER_MICROSCOPE_INDEX) =>
+        {
+            return Err(AppError::Conflict(format!(
+                "Microscope {} already has an active session",
+                request.microscope_id
+            )));
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    // Hand the microscope to this session in the IA system, releasing the claim if that fails
+    if let Err(e) = state
+        .ia_client
+        .update_session_status(
+            &created_session.microscope_id,
+            Some(created_session.id),
+            true,
+        )
+        .await
+    {
+        tracing::error!(
+            "Failed to start session {} in IA system: {}. Releasing microscope claim.",
+            created_session.id,
+            e
+        );
+        state.db.delete_session(created_session.id).await?;
+        return Err(e.into());
+    }
+
+    session_timeline::record(
+        &state,
+        created_session.id,
+        SessionEventType::SessionStarted,
+        Some(claims.user_id),
+        serde_json::json!({
+            "microscope_id": created_session.microscope_id,
+            "booking_id": created_session.booking_id,
+        }),
+    )
+    .await;
 
     tracing::info!(
         "Started new session: {} for user: {} on microscope: {}",
@@ -203,8 +393,8 @@ pub async fn create_session(
         ("bearer_auth" = [])
     ),
     responses(
-        (status = 200, description = "Session details", body = ApiResponse<Session>),
-        (status = 403, description = "Access denied - can only access own sessions unless admin/teacher", body = ApiResponse<String>),
+        (status = 200, description = "Session details with its notebook entries", body = ApiResponse<SessionDetail>),
+        (status = 403, description = "Access denied - can only access own or shared sessions unless admin/teacher", body = ApiResponse<String>),
         (status = 404, description = "Session not found", body = ApiResponse<String>),
         (status = 401, description = "Unauthorized")
     )
@@ -213,6 +403,51 @@ pub async fn get_session(
     State(state): State<AppState>,
     Extension(claims): Extension<Claims>,
     Path(session_id): Path<Uuid>,
+) -> Result<Json<ApiResponse<SessionDetail>>, AppError> {
+    let session = state
+        .db
+        .get_session_by_id(session_id)
+        .await?
+        .ok_or(AppError::NotFound("Session not found".to_string()))?;
+
+    ensure_session_access(&state, &claims, &session).await?;
+
+    let notebook = state.db.list_session_notes(session.id).await?;
+    let session = session.with_presence(state.config.session.presence_timeout, chrono::Utc::now());
+    Ok(Json(ApiResponse::success(SessionDetail {
+        session,
+        notebook,
+    })))
+}
+
+/// End session (stop microscope usage)
+///
+/// Owners can end their own session. Teachers and admins can end any session, or abort it
+/// with `abort: true` and a reason.
+#[utoipa::path(
+    post,
+    path = "/api/sessions/{id}/end",
+    tag = "sessions",
+    params(
+        ("id" = Uuid, Path, description = "Session ID")
+    ),
+    request_body = EndSessionRequest,
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Session ended successfully", body = ApiResponse<Session>),
+        (status = 400, description = "Session is not active, or abort without a reason", body = ApiResponse<String>),
+        (status = 403, description = "Access denied - can only end own sessions unless admin/teacher", body = ApiResponse<String>),
+        (status = 404, description = "Session not found", body = ApiResponse<String>),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn end_session(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(session_id): Path<Uuid>,
+    Json(request): Json<EndSessionRequest>,
 ) -> Result<Json<ApiResponse<Session>>, AppError> {
     let session = state
         .db
@@ -220,124 +455,529 @@ pub async fn get_session(
         .await?
         .ok_or(AppError::NotFound("Session not found".to_string()))?;
 
-    // Permission checking based on user role
+    let abort = request.abort.unwrap_or(false);
+
+    // Check permissions - only session owner or staff can end session, only staff can abort
     match claims.role {
         UserRole::Student => {
-            // Students can only access their own sessions
             if session.user_id != claims.user_id {
+                return Err(AppError::Authorization("Access denied".to_string()));
+            }
+            if abort {
                 return Err(AppError::Authorization(
-                    "Access denied - can only view own sessions".to_string(),
+                    "Only teachers and admins can abort sessions".to_string(),
                 ));
             }
         }
         UserRole::Teacher | UserRole::Admin => {
-            // Teachers and admins can access all sessions
+            // Teachers and admins can end any session
         }
     }
 
-    Ok(Json(ApiResponse::success(session)))
+    let reason = request
+        .reason
+        .map(|r| r.trim().to_string())
+        .filter(|r| !r.is_empty());
+    if abort && reason.is_none() {
+        return Err(AppError::BadRequest(
+            "A reason is required to abort a session".to_string(),
+        ));
+    }
+
+    // Check if session is already ended
+    if session.status != SessionStatus::Active {
+        return Ok(Json(ApiResponse::error(
+            "Session is not active".to_string(),
+        )));
+    }
+
+    let end = EndSession {
+        status: if abort {
+            SessionStatus::Aborted
+        } else {
+            SessionStatus::Completed
+        },
+        notes: request.notes,
+        ended_by: Some(claims.user_id),
+        reason,
+    };
+
+    match session_lifecycle::end_session(&state, &session, end).await? {
+        Some(ended_session) => Ok(Json(ApiResponse::success(ended_session))),
+        None => Ok(Json(ApiResponse::error(
+            "Session is not active".to_string(),
+        ))),
+    }
 }
 
-/// End session (stop microscope usage)
+/// Get current user's active session
+///
+/// Falls back to an active session shared with the user by a handover if they own none.
+#[utoipa::path(
+    get,
+    path = "/api/sessions/current",
+    tag = "sessions",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Current active session or null if none", body = ApiResponse<Option<Session>>),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn get_current_session(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<ApiResponse<Option<Session>>>, AppError> {
+    let active_session = state
+        .db
+        .get_current_session_for_user(claims.user_id)
+        .await?
+        .map(|s| s.with_presence(state.config.session.presence_timeout, chrono::Utc::now()));
+    Ok(Json(ApiResponse::success(active_session)))
+}
+
+/// Record a heartbeat for an active session (called periodically by the UI)
 #[utoipa::path(
     post,
-    path = "/api/sessions/{id}/end",
+    path = "/api/sessions/{id}/heartbeat",
     tag = "sessions",
     params(
         ("id" = Uuid, Path, description = "Session ID")
     ),
-    request_body = EndSessionRequest,
     security(
         ("bearer_auth" = [])
     ),
     responses(
-        (status = 200, description = "Session ended successfully", body = ApiResponse<Session>),
+        (status = 200, description = "Heartbeat recorded", body = ApiResponse<Session>),
         (status = 400, description = "Session is not active", body = ApiResponse<String>),
-        (status = 403, description = "Access denied - can only end own sessions unless admin/teacher", body = ApiResponse<String>),
+        (status = 403, description = "Access denied - only the session user can send heartbeats", body = ApiResponse<String>),
         (status = 404, description = "Session not found", body = ApiResponse<String>),
         (status = 401, description = "Unauthorized")
     )
 )]
-pub async fn end_session(
+pub async fn session_heartbeat(
     State(state): State<AppState>,
     Extension(claims): Extension<Claims>,
     Path(session_id): Path<Uuid>,
-    Json(request): Json<EndSessionRequest>,
 ) -> Result<Json<ApiResponse<Session>>, AppError> {
-    // Get the active session for this user
-    let active_session = state
+    let session = state
         .db
-        .get_active_session_by_user(claims.user_id)
+        .get_session_by_id(session_id)
         .await?
-        .ok_or(AppError::NotFound("No active session found".to_string()))?;
+        .ok_or(AppError::NotFound("Session not found".to_string()))?;
 
-    // Check if the session ID matches the active session
-    if active_session.id != session_id {
+    // Presence is about the person at the microscope, so staff cannot heartbeat for others
+    if session.user_id != claims.user_id {
+        return Err(AppError::Authorization(
+            "Only the session user can send heartbeats".to_string(),
+        ));
+    }
+
+    if state.db.touch_session_heartbeat(session_id).await? == 0 {
         return Ok(Json(ApiResponse::error(
-            "Cannot end session - session ID does not match active session".to_string(),
+            "Session is not active".to_string(),
         )));
     }
 
-    // Check permissions - only session owner or admin can end session
+    let session = state
+        .db
+        .get_session_by_id(session_id)
+        .await?
+        .ok_or(AppError::NotFound("Session not found".to_string()))?
+        .with_presence(state.config.session.presence_timeout, chrono::Utc::now());
+
+    Ok(Json(ApiResponse::success(session)))
+}
+
+/// Hand an active session over to another user, or add them as a co-participant
+///
+/// A transfer makes the other user the session owner and keeps the previous owner as a
+/// participant, so the image history stays in one session. Only the owner or staff can hand
+/// over a session, and only to a member of its group: the requester of the session's booking,
+/// or a user who has booked under the group name of that booking or of the owner's bookings.
+#[utoipa::path(
+    post,
+    path = "/api/sessions/{id}/handover",
+    tag = "sessions",
+    params(
+        ("id" = Uuid, Path, description = "Session ID")
+    ),
+    request_body = HandoverRequest,
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Handover recorded", body = ApiResponse<SessionHandover>),
+        (status = 400, description = "Session is not active, or the user is the current owner or not in the session's group", body = ApiResponse<String>),
+        (status = 403, description = "Access denied - only the session owner or staff can hand over", body = ApiResponse<String>),
+        (status = 404, description = "Session or user not found", body = ApiResponse<String>),
+        (status = 409, description = "User already has an active session or already participates", body = ApiResponse<String>),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn handover_session(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(session_id): Path<Uuid>,
+    Json(request): Json<HandoverRequest>,
+) -> Result<Json<ApiResponse<SessionHandover>>, AppError> {
+    let session = state
+        .db
+        .get_session_by_id(session_id)
+        .await?
+        .ok_or(AppError::NotFound("Session not found".to_string()))?;
+
     match claims.role {
         UserRole::Student => {
-            if active_session.user_id != claims.user_id {
-                return Err(AppError::Authorization("Access denied".to_string()));
+            if session.user_id != claims.user_id {
+                return Err(AppError::Authorization(
+                    "Only the session owner can hand over a session".to_string(),
+                ));
             }
         }
         UserRole::Teacher | UserRole::Admin => {
-            // Teachers and admins can end any session
+            // Teachers and admins can hand over any session
         }
     }
 
-    // Check if session is already ended
-    if active_session.status != SessionStatus::Active {
+    if session.status != SessionStatus::Active {
         return Ok(Json(ApiResponse::error(
             "Session is not active".to_string(),
         )));
     }
 
-    // End the session in database
-    let ended_session = state.db.end_session(session_id, request.notes).await?;
+    let user = state
+        .db
+        .get_user_by_id(request.user_id)
+        .await?
+        .ok_or(AppError::NotFound("User not found".to_string()))?;
+
+    if user.id == session.user_id {
+        return Err(AppError::BadRequest(
+            "User already owns this session".to_string(),
+        ));
+    }
+
+    // Sessions are only handed over within the group that booked the microscope
+    if !state
+        .db
+        .is_session_group_member(session.id, user.id)
+        .await?
+    {
+        return Err(AppError::BadRequest(format!(
+            "{} is not a member of this session's group or booking",
+            user.name
+        )));
+    }
+
+    let note = request
+        .note
+        .map(|n| n.trim().to_string())
+        .filter(|n| !n.is_empty());
+    let kind = request.kind.unwrap_or(HandoverKind::Transfer);
+
+    let handover = match kind {
+        HandoverKind::Transfer => {
+            // Users can only run one session at a time
+            if state
+                .db
+                .get_active_session_by_user(user.id)
+                .await?
+                .is_some()
+            {
+                return Err(AppError::Conflict(format!(
+                    "{} already has an active session",
+                    user.name
+                )));
+            }
+
+            match state
+                .db
+                .transfer_session(
+                    session.id,
+                    session.user_id,
+                    user.id,
+                    claims.user_id,
+                    note.as_deref(),
+                )
+                .await?
+            {
+                Some(handover) => handover,
+                None => {
+                    return Ok(Json(ApiResponse::error(
+                        "Session is no longer active or has changed owner".to_string(),
+                    )))
+                }
+            }
+        }
+        HandoverKind::AddParticipant => state
+            .db
+            .add_session_participant(session.id, user.id, claims.user_id, note.as_deref())
+            .await?
+            .ok_or_else(|| {
+                AppError::Conflict(format!(
+                    "{} already participates in this session",
+                    user.name
+                ))
+            })?,
+    };
+
+    session_timeline::record(
+        &state,
+        session.id,
+        SessionEventType::Handover,
+        Some(claims.user_id),
+        serde_json::json!({
+            "kind": handover.kind,
+            "from_user_id": handover.from_user_id,
+            "to_user_id": handover.to_user_id,
+            "note": handover.note,
+        }),
+    )
+    .await;
+
+    let message = match kind {
+        HandoverKind::Transfer => format!(
+            "The session on {} has been handed over to you.",
+            session.microscope_id
+        ),
+        HandoverKind::AddParticipant => format!(
+            "You have been added to the session on {}.",
+            session.microscope_id
+        ),
+    };
 
-    // Update microscope status in IA system
     if let Err(e) = state
-        .ia_client
-        .update_session_status(&ended_session.microscope_id, None, false)
+        .db
+        .create_notification(
+            user.id,
+            NotificationKind::SessionHandover,
+            &message,
+            session.booking_id,
+            Some(session.id),
+        )
         .await
     {
         tracing::warn!(
-            "Failed to update microscope status in IA system: {}. Session ended in database.",
+            "Failed to notify user {} about handover of session {}: {}",
+            user.id,
+            session.id,
             e
         );
     }
 
     tracing::info!(
-        "Ended session: {} for user: {}",
-        ended_session.id,
+        "Session {} on microscope {}: {:?} to user {} by {}",
+        session.id,
+        session.microscope_id,
+        kind,
+        user.id,
         claims.user_id
     );
 
-    Ok(Json(ApiResponse::success(ended_session)))
+    Ok(Json(ApiResponse::success(handover)))
 }
 
-/// Get current user's active session
+/// List the owner, participants and handover history of a session
 #[utoipa::path(
     get,
-    path = "/api/sessions/current",
+    path = "/api/sessions/{id}/participants",
     tag = "sessions",
+    params(
+        ("id" = Uuid, Path, description = "Session ID")
+    ),
     security(
         ("bearer_auth" = [])
     ),
     responses(
-        (status = 200, description = "Current active session or null if none", body = ApiResponse<Option<Session>>),
+        (status = 200, description = "Session participants and handovers", body = ApiResponse<SessionParticipants>),
+        (status = 403, description = "Access denied - can only view own or shared sessions unless admin/teacher", body = ApiResponse<String>),
+        (status = 404, description = "Session not found", body = ApiResponse<String>),
         (status = 401, description = "Unauthorized")
     )
 )]
-pub async fn get_current_session(
+pub async fn get_session_participants(
     State(state): State<AppState>,
     Extension(claims): Extension<Claims>,
-) -> Result<Json<ApiResponse<Option<Session>>>, AppError> {
-    let active_session = state.db.get_active_session_by_user(claims.user_id).await?;
-    Ok(Json(ApiResponse::success(active_session)))
+    Path(session_id): Path<Uuid>,
+) -> Result<Json<ApiResponse<SessionParticipants>>, AppError> {
+    let session = state
+        .db
+        .get_session_by_id(session_id)
+        .await?
+        .ok_or(AppError::NotFound("Session not found".to_string()))?;
+
+    ensure_session_access(&state, &claims, &session).await?;
+
+    let participants = state.db.list_session_participants(session.id).await?;
+    let handovers = state.db.get_session_handovers(session.id).await?;
+
+    Ok(Json(ApiResponse::success(SessionParticipants {
+        session_id: session.id,
+        owner_id: session.user_id,
+        participants,
+        handovers,
+    })))
+}
+
+/// Chronological timeline of a session: start, microscope commands, focus and tracking,
+/// captures, notebook entries, handovers and the end
+#[utoipa::path(
+    get,
+    path = "/api/sessions/{id}/timeline",
+    tag = "sessions",
+    params(
+        ("id" = Uuid, Path, description = "Session ID"),
+        TimelineQuery
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Session events, oldest first", body = ApiResponse<Vec<SessionEvent>>),
+        (status = 400, description = "Unknown event type", body = ApiResponse<String>),
+        (status = 403, description = "Access denied - can only view own or shared sessions unless admin/teacher", body = ApiResponse<String>),
+        (status = 404, description = "Session not found", body = ApiResponse<String>),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn get_session_timeline(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(session_id): Path<Uuid>,
+    Query(query): Query<TimelineQuery>,
+) -> Result<Json<ApiResponse<Vec<SessionEvent>>>, AppError> {
+    let session = state
+        .db
+        .get_session_by_id(session_id)
+        .await?
+        .ok_or(AppError::NotFound("Session not found".to_string()))?;
+
+    ensure_session_access(&state, &claims, &session).await?;
+
+    let limit = query.limit.unwrap_or(50).min(200);
+    let page = query.page.unwrap_or(1).max(1);
+    let offset = (page - 1) * limit;
+
+    let event_types = query
+        .types
+        .as_deref()
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .map(|t| {
+            serde_json::from_value::<SessionEventType>(serde_json::Value::String(t.to_string()))
+                .map_err(|_| AppError::BadRequest(format!("Unknown event type: {}", t)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let events = state
+        .db
+        .get_session_events(session.id, &event_types, limit, offset)
+        .await?;
+
+    Ok(Json(ApiResponse::success(events)))
+}
+
+/// Export a session as a ZIP archive
+///
+/// The archive holds every image file, the image metadata as `metadata.json` and
+/// `metadata.csv`, the notebook as `notes.md` and `notes.json`, and an HTML summary in
+/// `report.html`. It is streamed while it is being built.
+#[utoipa::path(
+    get,
+    path = "/api/sessions/{id}/export",
+    tag = "sessions",
+    params(
+        ("id" = Uuid, Path, description = "Session ID")
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "ZIP archive of the session", content_type = "application/zip"),
+        (status = 403, description = "Access denied - can only export own or shared sessions unless admin/teacher", body = ApiResponse<String>),
+        (status = 404, description = "Session not found", body = ApiResponse<String>),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn export_session(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(session_id): Path<Uuid>,
+) -> Result<Response, AppError> {
+    let session = state
+        .db
+        .get_session_by_id(session_id)
+        .await?
+        .ok_or(AppError::NotFound("Session not found".to_string()))?;
+
+    ensure_session_access(&state, &claims, &session).await?;
+
+    let mut images = state.db.get_images_by_session(session.id).await?;
+    images.reverse(); // oldest first
+    let notes = state.db.list_session_notes(session.id).await?;
+    let owner_name = state
+        .db
+        .get_user_by_id(session.user_id)
+        .await?
+        .map(|user| user.name)
+        .unwrap_or_else(|| session.user_id.to_string());
+    let participant_names = state
+        .db
+        .list_session_participants(session.id)
+        .await?
+        .into_iter()
+        .map(|p| p.user_name)
+        .collect();
+
+    let filename = format!("session-{}.zip", session.id);
+    let export = SessionExport {
+        session,
+        owner_name,
+        participant_names,
+        images,
+        notes,
+    };
+
+    // Build the archive in the background and stream it as it is written
+    let (writer, reader) = tokio::io::duplex(64 * 1024);
+    let (failed_tx, failed_rx) = oneshot::channel::<String>();
+    let file_store = state.file_store.clone();
+    let db = state.db.clone();
+    tokio::spawn(async move {
+        if let Err(e) = session_export::write_zip(writer, &file_store, &export).await {
+            tracing::error!("Failed to export session {}: {}", export.session.id, e);
+            let _ = failed_tx.send(e.to_string());
+            return;
+        }
+        // Retention policies can keep the images of exported sessions
+        if let Err(e) = db.mark_session_exported(export.session.id).await {
+            tracing::error!(
+                "Failed to record export of session {}: {}",
+                export.session.id,
+                e
+            );
+        }
+    });
+
+    let headers = [
+        (header::CONTENT_TYPE, "application/zip".to_string()),
+        (
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}\"", filename),
+        ),
+    ];
+    // Headers are already sent by the time writing fails, so end the body with an error; the
+    // connection is aborted and the client sees a failed download instead of a truncated ZIP
+    let failure = stream::once(failed_rx).filter_map(|message| async move {
+        message
+            .ok()
+            .map(|message| Err::<Bytes, _>(std::io::Error::other(message)))
+    });
+    let body = Body::from_stream(ReaderStream::new(reader).chain(failure));
+
+    Ok((headers, body).into_response())
 }
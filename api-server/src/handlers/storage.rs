@@ -0,0 +1,261 @@
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+    Extension,
+};
+use serde::Deserialize;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::{
+    config::StorageBackendKind,
+    middleware::auth::{ensure_admin, Claims},
+    models::{
+        ApiResponse, IntegrityReport, ReconciliationReport, StorageUsageReport, UserRole,
+        UserStorageUsage,
+    },
+    services::{integrity, reconciliation, storage_quota},
+    AppError, AppState,
+};
+
+/// Re-hash every stored image file now and report missing or corrupted ones
+///
+/// The same check runs as a background job every `STORAGE_JOBS_INTERVAL` seconds. Images
+/// stored before checksums were recorded get their current checksum saved.
+#[utoipa::path(
+    post,
+    path = "/api/storage/verify",
+    tag = "storage",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Integrity report", body = ApiResponse<IntegrityReport>),
+        (status = 403, description = "Admin only", body = ApiResponse<String>),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn verify_storage(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<ApiResponse<IntegrityReport>>, AppError> {
+    ensure_admin(&claims, "manage file storage")?;
+
+    let report = integrity::verify_images(&state).await?;
+
+    Ok(Json(ApiResponse::success(report)))
+}
+
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct ReconcileQuery {
+    /// Only report what would be changed
+    pub dry_run: Option<bool>,
+}
+
+/// Find stored files no image references and images whose file is missing
+///
+/// Unreferenced files older than a day are deleted and the file reference counts recounted,
+/// unless `dry_run` is set. Images with a missing file are only reported. The same job runs
+/// every `STORAGE_JOBS_INTERVAL` seconds.
+#[utoipa::path(
+    post,
+    path = "/api/storage/reconcile",
+    tag = "storage",
+    params(ReconcileQuery),
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Reconciliation report", body = ApiResponse<ReconciliationReport>),
+        (status = 403, description = "Admin only", body = ApiResponse<String>),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn reconcile_storage(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Query(query): Query<ReconcileQuery>,
+) -> Result<Json<ApiResponse<ReconciliationReport>>, AppError> {
+    ensure_admin(&claims, "manage file storage")?;
+
+    let report = reconciliation::reconcile(&state, query.dry_run.unwrap_or(false)).await?;
+
+    Ok(Json(ApiResponse::success(report)))
+}
+
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct StorageStatsQuery {
+    /// Users and sessions listed in the breakdowns (default 20, at most 100)
+    #[schema(example = 20)]
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct StorageQuotaRequest {
+    /// Bytes the user's session images may take up; null falls back to `USER_STORAGE_QUOTA`
+    #[schema(example = 10737418240i64)]
+    pub quota_bytes: Option<i64>,
+}
+
+/// Disk space and how it is used
+///
+/// Usage is broken down by session owner, session and microscope. Breakdowns count the size of
+/// each image, including images in the trash, so files shared by content-addressed storage are
+/// counted once per image.
+#[utoipa::path(
+    get,
+    path = "/api/storage/stats",
+    tag = "storage",
+    params(StorageStatsQuery),
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Storage usage", body = ApiResponse<StorageUsageReport>),
+        (status = 403, description = "Admin only", body = ApiResponse<String>),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn get_storage_stats(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Query(query): Query<StorageStatsQuery>,
+) -> Result<Json<ApiResponse<StorageUsageReport>>, AppError> {
+    ensure_admin(&claims, "manage file storage")?;
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+
+    let stats = state.file_store.get_storage_stats().await?;
+    let by_user = state
+        .db
+        .get_storage_usage_by_user(limit)
+        .await?
+        .into_iter()
+        .map(|usage| storage_quota::with_default_quota(&state, usage))
+        .collect();
+    let by_session = state.db.get_storage_usage_by_session(limit).await?;
+    let by_microscope = state.db.get_storage_usage_by_microscope().await?;
+
+    let backend = match state.config.file_storage.backend {
+        StorageBackendKind::Local => "local",
+        StorageBackendKind::S3 => "s3",
+        StorageBackendKind::Memory => "memory",
+    };
+
+    Ok(Json(ApiResponse::success(StorageUsageReport {
+        backend: backend.to_string(),
+        total_files: stats.total_files,
+        stored_bytes: stats.total_size_bytes,
+        disk_total_bytes: stats.total_space_bytes,
+        disk_free_bytes: stats.available_space_bytes,
+        default_user_quota_bytes: state.config.file_storage.user_quota_bytes,
+        by_user,
+        by_session,
+        by_microscope,
+    })))
+}
+
+/// Storage used by a user's sessions and the quota that applies (admin, or the user themselves)
+#[utoipa::path(
+    get,
+    path = "/api/storage/users/{user_id}",
+    tag = "storage",
+    params(
+        ("user_id" = Uuid, Path, description = "User ID")
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "User storage usage", body = ApiResponse<UserStorageUsage>),
+        (status = 403, description = "Access denied", body = ApiResponse<String>),
+        (status = 404, description = "User not found", body = ApiResponse<String>),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn get_user_storage(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(user_id): Path<Uuid>,
+) -> Result<Json<ApiResponse<UserStorageUsage>>, AppError> {
+    match claims.role {
+        UserRole::Admin => {}
+        UserRole::Teacher | UserRole::Student => {
+            if claims.user_id != user_id {
+                return Err(AppError::Authorization(
+                    "Only admins can view other users' storage".to_string(),
+                ));
+            }
+        }
+    }
+
+    let usage = state
+        .db
+        .get_user_storage_usage(user_id)
+        .await?
+        .ok_or(AppError::NotFound("User not found".to_string()))?;
+
+    Ok(Json(ApiResponse::success(
+        storage_quota::with_default_quota(&state, usage),
+    )))
+}
+
+/// Set or clear a user's storage quota (admin only)
+///
+/// Once the images of a user's sessions reach the quota, new captures and uploads to those
+/// sessions are rejected with 403. Images in the trash count until they are purged.
+#[utoipa::path(
+    put,
+    path = "/api/storage/users/{user_id}/quota",
+    tag = "storage",
+    params(
+        ("user_id" = Uuid, Path, description = "User ID")
+    ),
+    request_body = StorageQuotaRequest,
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "User storage usage with the new quota", body = ApiResponse<UserStorageUsage>),
+        (status = 400, description = "Negative quota", body = ApiResponse<String>),
+        (status = 403, description = "Admin only", body = ApiResponse<String>),
+        (status = 404, description = "User not found", body = ApiResponse<String>),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn set_user_storage_quota(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(user_id): Path<Uuid>,
+    Json(request): Json<StorageQuotaRequest>,
+) -> Result<Json<ApiResponse<UserStorageUsage>>, AppError> {
+    ensure_admin(&claims, "manage file storage")?;
+
+    if request.quota_bytes.is_some_and(|quota| quota < 0) {
+        return Err(AppError::BadRequest(
+            "quota_bytes cannot be negative".to_string(),
+        ));
+    }
+
+    if !state
+        .db
+        .set_user_storage_quota(user_id, request.quota_bytes)
+        .await?
+    {
+        return Err(AppError::NotFound("User not found".to_string()));
+    }
+    let usage = state
+        .db
+        .get_user_storage_usage(user_id)
+        .await?
+        .ok_or(AppError::NotFound("User not found".to_string()))?;
+
+    tracing::info!(
+        "User {} set the storage quota of {} to {:?} bytes",
+        claims.user_id,
+        user_id,
+        request.quota_bytes
+    );
+    Ok(Json(ApiResponse::success(
+        storage_quota::with_default_quota(&state, usage),
+    )))
+}
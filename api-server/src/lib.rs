@@ -9,13 +9,13 @@ pub use config::Config;
 pub use error::{AppError, AppResult};
 
 use axum::{
+    extract::DefaultBodyLimit,
     routing::{delete, get, post, put},
     Router,
 };
 use std::sync::Arc;
 use tower_http::{
     cors::CorsLayer,
-    services::ServeDir,
     trace::{DefaultMakeSpan, DefaultOnResponse, TraceLayer},
 };
 use tracing::Level;
@@ -42,20 +42,44 @@ use utoipa_swagger_ui::SwaggerUi;
         handlers::auth::refresh_token,
         handlers::bookings::list_bookings,
         handlers::bookings::create_booking,
+        handlers::bookings::search_availability,
         handlers::bookings::get_booking,
+        handlers::bookings::get_booking_approval,
         handlers::bookings::update_booking,
         handlers::bookings::delete_booking,
         handlers::bookings::approve_booking,
         handlers::bookings::reject_booking,
+        handlers::bookings::get_booking_history,
+        handlers::bookings::bulk_decide_bookings,
         handlers::sessions::list_sessions,
         handlers::sessions::create_session,
         handlers::sessions::get_current_session,
         handlers::sessions::get_session,
         handlers::sessions::end_session,
+        handlers::sessions::session_heartbeat,
+        handlers::sessions::handover_session,
+        handlers::sessions::get_session_participants,
+        handlers::sessions::get_session_timeline,
+        handlers::sessions::export_session,
+        handlers::session_notes::list_session_notes,
+        handlers::session_notes::create_session_note,
+        handlers::session_notes::update_session_note,
         handlers::images::get_image,
         handlers::images::serve_image_file,
+        handlers::images::get_image_download_url,
+        handlers::images::serve_signed_image_file,
         handlers::images::search_images,
         handlers::images::get_all_images_for_session,
+        handlers::images::upload_image,
+        handlers::images::delete_image,
+        handlers::images::restore_image,
+        handlers::images::list_trash,
+        handlers::images::set_image_starred,
+        handlers::storage::verify_storage,
+        handlers::storage::reconcile_storage,
+        handlers::storage::get_storage_stats,
+        handlers::storage::get_user_storage,
+        handlers::storage::set_user_storage_quota,
         handlers::images::get_latest_image_for_session,
         handlers::images::get_all_images_for_user,
         handlers::microscope::send_command,
@@ -64,6 +88,28 @@ use utoipa_swagger_ui::SwaggerUi;
         handlers::microscope::auto_focus,
         handlers::microscope::start_tracking,
         handlers::microscope::stop_tracking,
+        handlers::maintenance::create_maintenance_window,
+        handlers::maintenance::list_maintenance_windows,
+        handlers::maintenance::cancel_maintenance_window,
+        handlers::notifications::list_notifications,
+        handlers::notifications::mark_notification_read,
+        handlers::approval_rules::list_approval_rules,
+        handlers::approval_rules::create_approval_rule,
+        handlers::approval_rules::update_approval_rule,
+        handlers::approval_rules::delete_approval_rule,
+        handlers::retention::list_retention_policies,
+        handlers::retention::create_retention_policy,
+        handlers::retention::update_retention_policy,
+        handlers::retention::delete_retention_policy,
+        handlers::retention::list_academic_terms,
+        handlers::retention::create_academic_term,
+        handlers::retention::delete_academic_term,
+        handlers::retention::run_retention,
+        handlers::analytics::get_usage_summary,
+        handlers::analytics::get_daily_usage,
+        handlers::analytics::get_microscope_usage,
+        handlers::analytics::get_hourly_demand,
+        handlers::analytics::get_top_requesters,
         // All new endpoints must be added here with #[utoipa::path] annotations
     ),
     components(
@@ -72,19 +118,81 @@ use utoipa_swagger_ui::SwaggerUi;
             models::UserRole,
             models::Session,
             models::SessionStatus,
+            models::SessionParticipant,
+            models::SessionHandover,
+            models::HandoverKind,
+            models::SessionNote,
+            models::SessionNoteKind,
+            models::Measurement,
+            models::SessionEvent,
+            models::SessionEventType,
+            models::SessionSortField,
+            models::SortOrder,
             models::Image,
             models::ImageMetadata,
+            models::ImageRendition,
+            models::TrashedImage,
+            handlers::images::SignedImageUrl,
+            handlers::images::UploadImageForm,
+            handlers::images::StarImageRequest,
+            models::IntegrityReport,
+            models::IntegrityIssue,
+            models::ReconciliationReport,
+            models::OrphanFile,
+            models::RetentionPolicy,
+            models::AcademicTerm,
+            models::RetentionReport,
+            models::ExpiredImage,
+            models::StorageUsageReport,
+            models::UserStorageUsage,
+            models::SessionStorageUsage,
+            models::MicroscopeStorageUsage,
             models::DetectedObject,
             models::BoundingBox,
             models::Booking,
             models::BookingStatus,
+            models::Microscope,
+            models::MicroscopeState,
+            models::MicroscopeSpecs,
+            models::MicroscopeType,
+            models::RequiredCapabilities,
+            models::MaintenanceWindow,
+            models::MaintenanceKind,
+            models::Notification,
+            models::NotificationKind,
+            models::ApprovalRule,
+            models::ApprovalAction,
+            models::ApprovalDecision,
+            models::BookingEvent,
+            models::BookingEventType,
             models::MicroscopeCommand,
             models::CommandType,
+            models::UsageTotals,
+            models::UsageSummary,
+            models::DailyUsage,
+            models::MicroscopeUsage,
+            models::HourlyDemand,
+            models::RequesterUsage,
             models::ApiResponse<String>,
             handlers::bookings::CreateBookingRequest,
             handlers::bookings::UpdateBookingRequest,
+            handlers::bookings::BookingDecision,
+            handlers::bookings::BulkBookingDecisionRequest,
+            handlers::bookings::BookingDecisionResult,
+            handlers::bookings::BulkBookingDecisionResponse,
             handlers::sessions::EndSessionRequest,
             handlers::sessions::CreateSessionRequest,
+            handlers::sessions::HandoverRequest,
+            handlers::sessions::SessionParticipants,
+            handlers::sessions::SessionDetail,
+            handlers::session_notes::CreateSessionNoteRequest,
+            handlers::session_notes::UpdateSessionNoteRequest,
+            handlers::maintenance::CreateMaintenanceWindowRequest,
+            handlers::maintenance::MaintenanceWindowResponse,
+            handlers::approval_rules::ApprovalRuleRequest,
+            handlers::retention::RetentionPolicyRequest,
+            handlers::retention::AcademicTermRequest,
+            handlers::storage::StorageQuotaRequest,
         )
     ),
     tags(
@@ -93,7 +201,13 @@ use utoipa_swagger_ui::SwaggerUi;
         (name = "bookings", description = "Booking management"),
         (name = "sessions", description = "Session tracking"),
         (name = "images", description = "Image management and serving"),
-        (name = "microscope", description = "Microscope control and commands")
+        (name = "microscope", description = "Microscope control and commands"),
+        (name = "maintenance", description = "Microscope maintenance windows"),
+        (name = "notifications", description = "User notifications"),
+        (name = "approval-rules", description = "Booking approval rules"),
+        (name = "analytics", description = "Usage analytics (teacher/admin)"),
+        (name = "storage", description = "Image file storage usage, quotas and maintenance"),
+        (name = "retention", description = "Image retention policies (admin)")
     )
 )]
 struct ApiDoc;
@@ -122,6 +236,10 @@ pub fn create_router(state: AppState) -> Router {
         // Booking routes (from existing UI)
         .route("/api/bookings", get(handlers::bookings::list_bookings))
         .route("/api/bookings", post(handlers::bookings::create_booking))
+        .route(
+            "/api/bookings/availability",
+            get(handlers::bookings::search_availability),
+        )
         .route("/api/bookings/{id}", get(handlers::bookings::get_booking))
         .route(
             "/api/bookings/{id}",
@@ -139,6 +257,18 @@ pub fn create_router(state: AppState) -> Router {
             "/api/bookings/{id}/reject",
             post(handlers::bookings::reject_booking),
         )
+        .route(
+            "/api/bookings/{id}/approval",
+            get(handlers::bookings::get_booking_approval),
+        )
+        .route(
+            "/api/bookings/decisions",
+            post(handlers::bookings::bulk_decide_bookings),
+        )
+        .route(
+            "/api/bookings/{id}/history",
+            get(handlers::bookings::get_booking_history),
+        )
         // Session management
         .route("/api/sessions", get(handlers::sessions::list_sessions))
         .route("/api/sessions", post(handlers::sessions::create_session))
@@ -151,16 +281,69 @@ pub fn create_router(state: AppState) -> Router {
             "/api/sessions/{id}/end",
             post(handlers::sessions::end_session),
         )
+        .route(
+            "/api/sessions/{id}/heartbeat",
+            post(handlers::sessions::session_heartbeat),
+        )
+        .route(
+            "/api/sessions/{id}/handover",
+            post(handlers::sessions::handover_session),
+        )
+        .route(
+            "/api/sessions/{id}/participants",
+            get(handlers::sessions::get_session_participants),
+        )
+        .route(
+            "/api/sessions/{id}/export",
+            get(handlers::sessions::export_session),
+        )
+        .route(
+            "/api/sessions/{id}/timeline",
+            get(handlers::sessions::get_session_timeline),
+        )
+        .route(
+            "/api/sessions/{id}/notes",
+            get(handlers::session_notes::list_session_notes),
+        )
+        .route(
+            "/api/sessions/{id}/notes",
+            post(handlers::session_notes::create_session_note),
+        )
+        .route(
+            "/api/sessions/{id}/notes/{note_id}",
+            put(handlers::session_notes::update_session_note),
+        )
         // Image routes
-        .route("/api/images/{id}", get(handlers::images::get_image))
+        .route(
+            "/api/images/{id}",
+            get(handlers::images::get_image).delete(handlers::images::delete_image),
+        )
+        .route(
+            "/api/images/{id}/restore",
+            post(handlers::images::restore_image),
+        )
+        .route("/api/images/trash", get(handlers::images::list_trash))
+        .route(
+            "/api/images/{id}/starred",
+            put(handlers::images::set_image_starred),
+        )
         .route(
             "/api/images/{id}/file",
             get(handlers::images::serve_image_file),
         )
+        .route(
+            "/api/images/{id}/url",
+            get(handlers::images::get_image_download_url),
+        )
         .route("/api/images/search", get(handlers::images::search_images))
         .route(
             "/api/sessions/{session_id}/images",
-            get(handlers::images::get_all_images_for_session),
+            get(handlers::images::get_all_images_for_session).merge(
+                post(handlers::images::upload_image).layer(DefaultBodyLimit::max(
+                    state.config.file_storage.max_file_size as usize
+                        + handlers::images::UPLOAD_FORM_OVERHEAD,
+                )),
+            ),
         )
         .route(
             "/api/sessions/{session_id}/images/latest",
@@ -195,8 +378,116 @@ pub fn create_router(state: AppState) -> Router {
             "/api/microscope/{microscope_id}/tracking/stop",
             post(handlers::microscope::stop_tracking),
         )
-        // File serving for static content
-        .nest_service("/files", ServeDir::new("uploads"))
+        // Maintenance windows
+        .route(
+            "/api/microscope/{microscope_id}/maintenance",
+            get(handlers::maintenance::list_maintenance_windows),
+        )
+        .route(
+            "/api/microscope/{microscope_id}/maintenance",
+            post(handlers::maintenance::create_maintenance_window),
+        )
+        .route(
+            "/api/maintenance/{id}",
+            delete(handlers::maintenance::cancel_maintenance_window),
+        )
+        // Notifications
+        .route(
+            "/api/notifications",
+            get(handlers::notifications::list_notifications),
+        )
+        .route(
+            "/api/notifications/{id}/read",
+            post(handlers::notifications::mark_notification_read),
+        )
+        // Booking approval rules
+        .route(
+            "/api/approval-rules",
+            get(handlers::approval_rules::list_approval_rules),
+        )
+        .route(
+            "/api/approval-rules",
+            post(handlers::approval_rules::create_approval_rule),
+        )
+        .route(
+            "/api/approval-rules/{id}",
+            put(handlers::approval_rules::update_approval_rule),
+        )
+        .route(
+            "/api/approval-rules/{id}",
+            delete(handlers::approval_rules::delete_approval_rule),
+        )
+        // Usage analytics
+        .route(
+            "/api/analytics/summary",
+            get(handlers::analytics::get_usage_summary),
+        )
+        .route(
+            "/api/analytics/daily",
+            get(handlers::analytics::get_daily_usage),
+        )
+        .route(
+            "/api/analytics/microscopes",
+            get(handlers::analytics::get_microscope_usage),
+        )
+        .route(
+            "/api/analytics/hourly",
+            get(handlers::analytics::get_hourly_demand),
+        )
+        .route(
+            "/api/analytics/requesters",
+            get(handlers::analytics::get_top_requesters),
+        )
+        // Storage maintenance routes
+        .route(
+            "/api/storage/verify",
+            post(handlers::storage::verify_storage),
+        )
+        .route(
+            "/api/storage/reconcile",
+            post(handlers::storage::reconcile_storage),
+        )
+        .route(
+            "/api/storage/stats",
+            get(handlers::storage::get_storage_stats),
+        )
+        .route(
+            "/api/storage/users/{user_id}",
+            get(handlers::storage::get_user_storage),
+        )
+        .route(
+            "/api/storage/users/{user_id}/quota",
+            put(handlers::storage::set_user_storage_quota),
+        )
+        // Retention policies
+        .route(
+            "/api/retention/policies",
+            get(handlers::retention::list_retention_policies)
+                .post(handlers::retention::create_retention_policy),
+        )
+        .route(
+            "/api/retention/policies/{id}",
+            put(handlers::retention::update_retention_policy)
+                .delete(handlers::retention::delete_retention_policy),
+        )
+        .route(
+            "/api/retention/terms",
+            get(handlers::retention::list_academic_terms)
+                .post(handlers::retention::create_academic_term),
+        )
+        .route(
+            "/api/retention/terms/{id}",
+            delete(handlers::retention::delete_academic_term),
+        )
+        .route(
+            "/api/retention/run",
+            post(handlers::retention::run_retention),
+        )
+        // Signed image downloads (checked by signature instead of a bearer token)
+        .route(
+            "/files/{id}",
+            get(handlers::images::serve_signed_image_file),
+        )
         // Add middleware
         .layer(axum::middleware::from_fn_with_state(
             state.clone(),
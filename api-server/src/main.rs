@@ -4,8 +4,9 @@ use tokio::net::TcpListener;
 use tracing_subscriber;
 
 use bam::{
+    config::StorageBackendKind,
     create_router,
-    services::{DatabaseService, FileStorageService, IAClient},
+    services::{scheduler, DatabaseService, FileStorageService, IAClient},
     AppState, Config,
 };
 
@@ -46,6 +47,10 @@ async fn main() {
         ia_client,
     };
 
+    // Start background jobs (maintenance status sync, storage maintenance)
+    scheduler::spawn(state.clone());
+    scheduler::spawn_storage_jobs(state.clone());
+
     // Build the application router
     let app = create_router(state);
 
@@ -56,7 +61,18 @@ async fn main() {
 
     tracing::info!("Server starting on {}", config.server.bind_address);
     tracing::info!("Database connected to: {}", config.database.url);
-    tracing::info!("File storage path: {}", config.file_storage.base_path);
+    match config.file_storage.backend {
+        StorageBackendKind::Local => {
+            tracing::info!("File storage path: {}", config.file_storage.base_path)
+        }
+        StorageBackendKind::S3 => {
+            tracing::info!(
+                "File storage bucket: s3://{}",
+                config.file_storage.s3.bucket
+            )
+        }
+        StorageBackendKind::Memory => tracing::warn!("File storage is in memory only"),
+    }
     tracing::info!("IA system URL: {}", config.ia.base_url);
 
     axum::serve(listener, app)
@@ -8,7 +8,7 @@ use jsonwebtoken::{decode, DecodingKey, Validation};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::{models::UserRole, AppState};
+use crate::{models::UserRole, AppError, AppState};
 
 /// JWT Claims structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -33,8 +33,13 @@ pub async fn auth_middleware(
         return Ok(next.run(request).await);
     }
 
-    // Skip authentication for health check and auth endpoints
-    if path == "/health" || path.starts_with("/api/auth") || path.starts_with("/swagger") {
+    // Skip authentication for health check and auth endpoints. Signed file URLs carry
+    // their own HMAC and are verified by the handler.
+    if path == "/health"
+        || path.starts_with("/api/auth")
+        || path.starts_with("/swagger")
+        || path.starts_with("/files/")
+    {
         return Ok(next.run(request).await);
     }
 
@@ -113,6 +118,18 @@ pub async fn require_admin(request: Request, next: Next) -> Result<Response, Sta
     Ok(next.run(request).await)
 }
 
+/// Check in a handler that the caller is an admin; `action` completes "Only admins can ..."
+/// in the error message
+pub fn ensure_admin(claims: &Claims, action: &str) -> Result<(), AppError> {
+    match claims.role {
+        UserRole::Admin => Ok(()),
+        UserRole::Teacher | UserRole::Student => Err(AppError::Authorization(format!(
+            "Only admins can {}",
+            action
+        ))),
+    }
+}
+
 /// Middleware to require teacher or admin role
 pub async fn require_teacher_or_admin(
     request: Request,
@@ -127,4 +144,4 @@ pub async fn require_teacher_or_admin(
         UserRole::Teacher | UserRole::Admin => Ok(next.run(request).await),
         _ => Err(StatusCode::FORBIDDEN),
     }
-}
\ No newline at end of file
+}
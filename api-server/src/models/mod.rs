@@ -1,4 +1,4 @@
-use chrono::{DateTime, FixedOffset, NaiveDate, Utc};
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, Utc};
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 use uuid::Uuid;
@@ -33,6 +33,26 @@ pub struct Session {
     pub started_at: DateTime<Utc>,
     pub ended_at: Option<DateTime<Utc>>,
     pub notes: Option<String>,
+    /// User who ended the session, if not ended by the system
+    pub ended_by: Option<Uuid>,
+    /// Why the session was ended early or aborted
+    pub end_reason: Option<String>,
+    /// Last heartbeat from the session user's UI
+    pub last_seen_at: Option<DateTime<Utc>>,
+    /// Whether the user is at the microscope (active session with a recent heartbeat)
+    #[serde(default)]
+    pub present: bool,
+}
+
+impl Session {
+    /// Work out `present` from the last heartbeat
+    pub fn with_presence(mut self, timeout_secs: i64, now: DateTime<Utc>) -> Self {
+        self.present = self.status == SessionStatus::Active
+            && self
+                .last_seen_at
+                .is_some_and(|seen| (now - seen).num_seconds() <= timeout_secs);
+        self
+    }
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, sqlx::Type, ToSchema)]
@@ -43,6 +63,107 @@ pub enum SessionStatus {
     Aborted,
 }
 
+/// User sharing a session besides its owner (a co-participant or a previous owner)
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SessionParticipant {
+    pub session_id: Uuid,
+    pub user_id: Uuid,
+    pub user_name: String,
+    pub added_by: Option<Uuid>,
+    pub added_at: DateTime<Utc>,
+}
+
+/// Recorded transfer of a session, or a co-participant being added
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SessionHandover {
+    pub id: Uuid,
+    pub session_id: Uuid,
+    pub kind: HandoverKind,
+    /// Previous owner for a transfer
+    pub from_user_id: Option<Uuid>,
+    /// New owner, or the added participant
+    pub to_user_id: Uuid,
+    pub performed_by: Option<Uuid>,
+    pub note: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "VARCHAR")]
+pub enum HandoverKind {
+    /// Make another user the session owner; the previous owner stays a participant
+    Transfer,
+    /// Let another user share the session
+    AddParticipant,
+}
+
+/// Timestamped lab notebook entry written during a session
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SessionNote {
+    pub id: Uuid,
+    pub session_id: Uuid,
+    pub author_id: Option<Uuid>,
+    pub kind: SessionNoteKind,
+    /// Entry text in Markdown
+    pub body: String,
+    /// `body` rendered to HTML (raw HTML in the Markdown is escaped)
+    pub body_html: String,
+    /// Image the entry refers to (`Image` entries)
+    pub image_id: Option<Uuid>,
+    /// Recorded value (`Measurement` entries)
+    pub measurement: Option<Measurement>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "VARCHAR")]
+pub enum SessionNoteKind {
+    Text,
+    Image,
+    Measurement,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Measurement {
+    #[schema(example = "Cell diameter")]
+    pub name: String,
+    #[schema(example = 12.5)]
+    pub value: f64,
+    #[schema(example = "µm")]
+    pub unit: Option<String>,
+}
+
+/// Entry in a session's timeline
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SessionEvent {
+    pub id: Uuid,
+    pub session_id: Uuid,
+    pub event_type: SessionEventType,
+    /// None for system actions such as the scheduler ending a session
+    pub actor_id: Option<Uuid>,
+    /// Event specific data, e.g. the command sent or the captured image
+    pub details: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "VARCHAR")]
+pub enum SessionEventType {
+    SessionStarted,
+    Command,
+    Focus,
+    TrackingStarted,
+    TrackingStopped,
+    Capture,
+    NoteAdded,
+    NoteUpdated,
+    Handover,
+    SessionEnded,
+    ImageDeleted,
+    ImageRestored,
+}
+
 /// Image model for storing microscope captures
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct Image {
@@ -56,6 +177,11 @@ pub struct Image {
     pub height: Option<i32>,
     pub metadata: ImageMetadata,
     pub captured_at: DateTime<Utc>,
+    /// Hex SHA-256 of the file; missing for older images until the integrity check hashes them
+    pub sha256: Option<String>,
+    /// Starred images can be exempted from retention policies
+    #[serde(default)]
+    pub starred: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
@@ -66,6 +192,51 @@ pub struct ImageMetadata {
     pub focus_quality: Option<f32>,
     pub magnification: Option<String>,
     pub lighting_conditions: Option<String>,
+    /// Acquisition time from the image's EXIF/TIFF tags (instrument local time)
+    #[serde(default)]
+    pub acquired_at: Option<NaiveDateTime>,
+    /// Physical pixel size in micrometres, when the file records it
+    #[serde(default)]
+    #[schema(example = 0.65)]
+    pub pixel_size_x_um: Option<f64>,
+    #[serde(default)]
+    #[schema(example = 0.65)]
+    pub pixel_size_y_um: Option<f64>,
+    #[serde(default)]
+    pub camera: Option<String>,
+    #[serde(default)]
+    pub exposure_time_s: Option<f64>,
+}
+
+/// An image in the trash, waiting to be restored or purged
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TrashedImage {
+    #[serde(flatten)]
+    pub image: Image,
+    pub deleted_at: DateTime<Utc>,
+    pub deleted_by: Option<Uuid>,
+    /// When the purge job removes the image and its file for good
+    pub purge_after: DateTime<Utc>,
+}
+
+/// Downscaled WebP version of an image, generated on first request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageRendition {
+    /// Fits within 256x256, for gallery grids
+    Thumb,
+    /// Fits within 1280x1280, for on-screen viewing
+    Preview,
+}
+
+impl ImageRendition {
+    /// Name used in query strings and rendition file names
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ImageRendition::Thumb => "thumb",
+            ImageRendition::Preview => "preview",
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
@@ -97,6 +268,7 @@ pub struct Booking {
     pub requester_name: String,
     pub status: BookingStatus,
     pub approved_by: Option<Uuid>,
+    pub required_capabilities: Option<RequiredCapabilities>,
     pub created_at: DateTime<FixedOffset>,
 }
 
@@ -106,6 +278,237 @@ pub enum BookingStatus {
     Pending,
     Approved,
     Rejected,
+    Cancelled,
+}
+
+/// Configurable rule deciding whether a new booking is auto-approved
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ApprovalRule {
+    pub id: Uuid,
+    pub name: String,
+    /// Rules are evaluated in ascending priority; the first match decides
+    pub priority: i32,
+    pub action: ApprovalAction,
+    /// Requester roles the rule applies to (empty matches any role)
+    pub requester_roles: Vec<UserRole>,
+    /// Matches bookings strictly shorter than this many minutes
+    pub duration_under_minutes: Option<i32>,
+    /// Matches bookings strictly longer than this many minutes
+    pub duration_over_minutes: Option<i32>,
+    /// Only matches bookings on Monday to Friday
+    pub weekdays_only: bool,
+    /// Only matches bookings on this microscope
+    pub microscope_id: Option<String>,
+    pub enabled: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "VARCHAR")]
+pub enum ApprovalAction {
+    AutoApprove,
+    RequireApproval,
+}
+
+/// Record of which approval rule decided a booking's initial status
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ApprovalDecision {
+    pub id: Uuid,
+    pub booking_id: Uuid,
+    /// None when no rule matched and the default applied, or the rule was deleted
+    pub rule_id: Option<Uuid>,
+    pub rule_name: String,
+    pub action: ApprovalAction,
+    pub decided_at: DateTime<Utc>,
+}
+
+/// Rule deciding how long images are kept, evaluated by the retention job
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RetentionPolicy {
+    pub id: Uuid,
+    pub name: String,
+    /// Policies are evaluated in ascending priority; the first match decides
+    pub priority: i32,
+    /// Roles of the session owner the policy applies to (empty matches any role)
+    pub owner_roles: Vec<UserRole>,
+    /// Only matches images captured on this microscope
+    pub microscope_id: Option<String>,
+    /// Days matching images are kept; None keeps them forever
+    #[schema(example = 180)]
+    pub retain_days: Option<i32>,
+    /// Count `retain_days` from the end of the term the image was captured in, rather than
+    /// from the capture. Images captured outside every term are kept.
+    pub after_term_end: bool,
+    /// Never expire starred images
+    pub keep_starred: bool,
+    /// Never expire images from sessions that have been exported
+    pub keep_exported: bool,
+    pub enabled: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Teaching term, for retention policies counted from the end of term
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AcademicTerm {
+    pub id: Uuid,
+    #[schema(example = "Semester 1 2026")]
+    pub name: String,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+}
+
+/// Append-only audit entry for a change to a booking
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BookingEvent {
+    pub id: Uuid,
+    pub booking_id: Uuid,
+    pub event_type: BookingEventType,
+    /// None for system actions such as auto-approval
+    pub actor_id: Option<Uuid>,
+    pub note: Option<String>,
+    /// Booking snapshot before the change (absent on create)
+    pub before: Option<serde_json::Value>,
+    /// Booking snapshot after the change (absent on delete)
+    pub after: Option<serde_json::Value>,
+    /// Changed fields as `{"field": {"from": ..., "to": ...}}`
+    pub changes: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "VARCHAR")]
+pub enum BookingEventType {
+    Created,
+    Updated,
+    Approved,
+    Rejected,
+    Cancelled,
+    Deleted,
+}
+
+/// Microscope registered in the system
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Microscope {
+    pub id: String,
+    pub name: String,
+    pub location: Option<String>,
+    pub status: MicroscopeState,
+    pub specs: MicroscopeSpecs,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "VARCHAR")]
+pub enum MicroscopeState {
+    Available,
+    InUse,
+    Maintenance,
+    Offline,
+}
+
+/// Hardware specifications stored in `microscopes.specs`
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct MicroscopeSpecs {
+    #[schema(example = "1000x")]
+    pub max_magnification: Option<String>,
+    #[serde(rename = "type")]
+    pub microscope_type: Option<MicroscopeType>,
+}
+
+impl MicroscopeSpecs {
+    /// Maximum magnification as a number, e.g. `"1000x"` -> `1000`
+    pub fn max_magnification_value(&self) -> Option<u32> {
+        self.max_magnification
+            .as_deref()
+            .map(|m| m.trim().trim_end_matches(['x', 'X']).trim())
+            .and_then(|m| m.parse().ok())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum MicroscopeType {
+    Compound,
+    Stereo,
+}
+
+/// Equipment a booking needs from its microscope
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct RequiredCapabilities {
+    #[schema(example = 1000)]
+    pub min_magnification: Option<u32>,
+    pub microscope_type: Option<MicroscopeType>,
+}
+
+impl RequiredCapabilities {
+    /// Describe each requirement the given specs fail to meet (empty if all are met)
+    pub fn unmet_by(&self, specs: &MicroscopeSpecs) -> Vec<String> {
+        let mut unmet = Vec::new();
+
+        if let Some(min) = self.min_magnification {
+            match specs.max_magnification_value() {
+                Some(max) if max >= min => {}
+                Some(max) => unmet.push(format!("magnification {}x < {}x", max, min)),
+                None => unmet.push(format!("magnification unknown (need {}x)", min)),
+            }
+        }
+
+        if let Some(required) = self.microscope_type {
+            if specs.microscope_type != Some(required) {
+                unmet.push(format!("type is not {:?}", required).to_lowercase());
+            }
+        }
+
+        unmet
+    }
+
+    pub fn is_met_by(&self, specs: &MicroscopeSpecs) -> bool {
+        self.unmet_by(specs).is_empty()
+    }
+}
+
+/// Period during which a microscope is out of service
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct MaintenanceWindow {
+    pub id: Uuid,
+    pub microscope_id: String,
+    pub kind: MaintenanceKind,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    pub reason: Option<String>,
+    pub created_by: Uuid,
+    pub cancelled_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Microscope status applied while a maintenance window is in effect
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "VARCHAR")]
+pub enum MaintenanceKind {
+    Maintenance,
+    Offline,
+}
+
+/// Message delivered to a user, e.g. when their booking is affected by maintenance
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Notification {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub kind: NotificationKind,
+    pub message: String,
+    pub booking_id: Option<Uuid>,
+    pub session_id: Option<Uuid>,
+    pub read_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "VARCHAR")]
+pub enum NotificationKind {
+    MaintenanceConflict,
+    BookingCancelled,
+    SessionEnded,
+    SessionEndingSoon,
+    SessionHandover,
 }
 
 /// Microscope control commands
@@ -126,6 +529,234 @@ pub enum CommandType {
     StopTracking,
 }
 
+/// Booked vs used microscope time and related counts over a period
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct UsageTotals {
+    /// Approved bookings
+    #[schema(example = 12)]
+    pub bookings: i64,
+    #[schema(example = 720)]
+    pub booked_minutes: i64,
+    /// Minutes microscopes were actually in a session
+    #[schema(example = 540)]
+    pub used_minutes: i64,
+    /// Bookable lab hours across the microscopes and days covered
+    #[schema(example = 4860)]
+    pub capacity_minutes: i64,
+    /// Used minutes as a percentage of capacity
+    #[schema(example = 11.1)]
+    pub utilization: f64,
+    /// Booked minutes as a percentage of capacity
+    #[schema(example = 14.8)]
+    pub booked_utilization: f64,
+    /// Approved bookings that have ended without a session being started
+    #[schema(example = 2)]
+    pub no_shows: i64,
+    /// No-shows as a percentage of approved bookings that have already ended
+    #[schema(example = 20.0)]
+    pub no_show_rate: f64,
+    #[schema(example = 10)]
+    pub sessions: i64,
+    /// Images captured during those sessions
+    #[schema(example = 85)]
+    pub captures: i64,
+}
+
+/// Usage totals for a whole reporting period
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UsageSummary {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    #[serde(flatten)]
+    pub totals: UsageTotals,
+}
+
+/// Usage totals for one lab-local day
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DailyUsage {
+    pub date: NaiveDate,
+    #[serde(flatten)]
+    pub totals: UsageTotals,
+}
+
+/// Usage totals for one microscope
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct MicroscopeUsage {
+    #[schema(example = "bio-1")]
+    pub microscope_id: String,
+    pub name: String,
+    #[serde(flatten)]
+    pub totals: UsageTotals,
+}
+
+/// Number of bookings covering an hour of the day
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct HourlyDemand {
+    /// Lab-local hour, 0-23
+    #[schema(example = 10)]
+    pub hour: i32,
+    #[schema(example = 7)]
+    pub bookings: i64,
+}
+
+/// Booking activity of a single requester
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RequesterUsage {
+    pub user_id: Uuid,
+    pub name: String,
+    /// Bookings requested, whatever their outcome (cancelled ones excluded)
+    #[schema(example = 6)]
+    pub bookings: i64,
+    pub approved_bookings: i64,
+    pub booked_minutes: i64,
+    pub no_shows: i64,
+}
+
+/// Result of re-hashing every stored image file
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct IntegrityReport {
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    /// Images whose file was looked for
+    #[schema(example = 1250)]
+    pub checked: u64,
+    /// Files matching their recorded checksum
+    pub ok: u64,
+    /// Images that had no checksum yet and now have one recorded
+    pub hashed: u64,
+    pub missing: Vec<IntegrityIssue>,
+    pub corrupted: Vec<IntegrityIssue>,
+}
+
+/// An image whose file is missing or no longer matches its checksum
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct IntegrityIssue {
+    pub image_id: Uuid,
+    pub session_id: Uuid,
+    pub file_path: String,
+    pub expected_sha256: Option<String>,
+    /// Checksum of the file as found (corrupted files only)
+    pub actual_sha256: Option<String>,
+}
+
+/// Result of comparing stored files with the images that reference them
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ReconciliationReport {
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    /// Nothing was changed; the report lists what would have been
+    pub dry_run: bool,
+    /// Files found in storage
+    #[schema(example = 2480)]
+    pub files_scanned: u64,
+    /// Files no image references, older than the grace period for uploads in progress
+    pub orphan_files: Vec<OrphanFile>,
+    pub orphan_bytes: u64,
+    /// Orphan files deleted (0 on a dry run)
+    pub deleted_files: u64,
+    /// Image rows whose file is not in storage
+    pub dangling_images: Vec<IntegrityIssue>,
+    /// `stored_files` reference counts corrected to match the images table (0 on a dry run)
+    pub references_fixed: u64,
+}
+
+/// Result of applying the retention policies
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RetentionReport {
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    /// Nothing was changed; the report lists what would have been
+    pub dry_run: bool,
+    /// Images evaluated (images already in the trash are skipped)
+    #[schema(example = 1250)]
+    pub checked: u64,
+    /// Images kept only because they are starred or their session was exported
+    pub exempted: u64,
+    /// Images past their retention period, moved to the trash unless this is a dry run
+    pub expired: Vec<ExpiredImage>,
+    pub expired_bytes: u64,
+}
+
+/// An image past the retention period of the policy that applies to it
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ExpiredImage {
+    pub image_id: Uuid,
+    pub session_id: Uuid,
+    pub filename: String,
+    pub file_size: i64,
+    pub captured_at: DateTime<Utc>,
+    pub policy_id: Uuid,
+    pub policy_name: String,
+    /// End of the retention period
+    pub expired_at: DateTime<Utc>,
+}
+
+/// Disk space and the images using it
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct StorageUsageReport {
+    #[schema(example = "local")]
+    pub backend: String,
+    /// Files in storage, including renditions and files no image references
+    pub total_files: u64,
+    pub stored_bytes: u64,
+    /// Size of the filesystem files are stored on (absent for object stores)
+    pub disk_total_bytes: Option<u64>,
+    /// Space left on that filesystem
+    pub disk_free_bytes: Option<u64>,
+    /// Quota of users without one of their own (absent when unlimited)
+    pub default_user_quota_bytes: Option<u64>,
+    /// Session owners using the most space
+    pub by_user: Vec<UserStorageUsage>,
+    /// Sessions using the most space
+    pub by_session: Vec<SessionStorageUsage>,
+    pub by_microscope: Vec<MicroscopeStorageUsage>,
+}
+
+/// Space used by the images of one user's sessions
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UserStorageUsage {
+    pub user_id: Uuid,
+    pub name: String,
+    /// Images outside the trash
+    #[schema(example = 120)]
+    pub images: i64,
+    /// Bytes of all the user's images, including those in the trash
+    pub used_bytes: i64,
+    pub trashed_bytes: i64,
+    /// Quota that applies to the user (absent when unlimited)
+    pub quota_bytes: Option<i64>,
+    /// The quota was set for this user rather than taken from the default
+    pub custom_quota: bool,
+}
+
+/// Space used by the images of one session
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SessionStorageUsage {
+    pub session_id: Uuid,
+    pub user_id: Uuid,
+    #[schema(example = "bio-1")]
+    pub microscope_id: String,
+    pub images: i64,
+    pub used_bytes: i64,
+}
+
+/// Space used by the images captured on one microscope
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct MicroscopeStorageUsage {
+    #[schema(example = "bio-1")]
+    pub microscope_id: String,
+    pub images: i64,
+    pub used_bytes: i64,
+}
+
+/// A stored file that no image references
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct OrphanFile {
+    pub location: String,
+    pub size: u64,
+    pub modified: Option<DateTime<Utc>>,
+}
+
 /// API Response types
 #[derive(Debug, Serialize, ToSchema)]
 pub struct ApiResponse<T> {
@@ -162,3 +793,159 @@ impl<T> ApiResponse<T> {
         }
     }
 }
+
+/// One page of a list endpoint's results.
+///
+/// Lists can be paged by `page`, counted by offset, or by `cursor`. Pages counted by offset
+/// shift when rows are added or removed while a client pages through a list; cursor pages
+/// follow creation order and continue after the last item seen.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Paginated<T> {
+    pub items: Vec<T>,
+    /// Number of items matching the filters across all pages
+    #[schema(example = 42)]
+    pub total: u64,
+    #[schema(example = 1)]
+    pub page: u64,
+    #[schema(example = 20)]
+    pub limit: u64,
+    /// Opaque cursor for the next page, passed back as `cursor`; only set when paging by
+    /// cursor, and absent on the last page
+    pub next_cursor: Option<String>,
+}
+
+/// Position of the last item on a cursor page: its creation time and ID
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cursor {
+    /// `created_at` in microseconds since the Unix epoch
+    pub created_at_us: i64,
+    pub id: Uuid,
+}
+
+impl Cursor {
+    pub fn encode(&self) -> String {
+        hex::encode(format!("{}:{}", self.created_at_us, self.id))
+    }
+
+    pub fn decode(cursor: &str) -> Option<Self> {
+        let raw = String::from_utf8(hex::decode(cursor).ok()?).ok()?;
+        let (created_at_us, id) = raw.split_once(':')?;
+
+        Some(Self {
+            created_at_us: created_at_us.parse().ok()?,
+            id: id.parse().ok()?,
+        })
+    }
+}
+
+/// Resolved paging parameters for a list query
+#[derive(Debug, Clone, Copy)]
+pub struct PageRequest {
+    pub page: u64,
+    pub limit: u64,
+    pub offset: u64,
+    /// Page by cursor: items in creation order, starting after `after`
+    pub by_cursor: bool,
+    /// Last item of the previous cursor page; None for the first page
+    pub after: Option<Cursor>,
+}
+
+impl PageRequest {
+    /// Resolve `page`/`limit`/`cursor` query parameters. An empty `cursor` starts paging by
+    /// cursor from the first item.
+    pub fn new(
+        page: Option<u64>,
+        limit: Option<u64>,
+        cursor: Option<&str>,
+        default_limit: u64,
+        max_limit: u64,
+    ) -> Result<Self, String> {
+        let limit = limit.unwrap_or(default_limit).clamp(1, max_limit);
+
+        if let Some(cursor) = cursor {
+            if page.is_some() {
+                return Err("Use either page or cursor, not both".to_string());
+            }
+            let after = match cursor {
+                "" => None,
+                cursor => Some(
+                    Cursor::decode(cursor).ok_or_else(|| format!("Invalid cursor '{}'", cursor))?,
+                ),
+            };
+
+            return Ok(Self {
+                page: 1,
+                limit,
+                offset: 0,
+                by_cursor: true,
+                after,
+            });
+        }
+
+        let page = page.unwrap_or(1).max(1);
+
+        // Offsets are bound as BIGINT
+        let offset = (page - 1)
+            .checked_mul(limit)
+            .filter(|offset| *offset <= i64::MAX as u64)
+            .ok_or_else(|| format!("Invalid page '{}'", page))?;
+
+        Ok(Self {
+            page,
+            limit,
+            offset,
+            by_cursor: false,
+            after: None,
+        })
+    }
+}
+
+impl<T> Paginated<T> {
+    pub fn new(items: Vec<T>, total: u64, request: PageRequest) -> Self {
+        Self {
+            items,
+            total,
+            page: request.page,
+            limit: request.limit,
+            next_cursor: None,
+        }
+    }
+
+    /// Set the cursor of the page after this one
+    pub fn with_next_cursor(self, cursor: Option<Cursor>) -> Self {
+        Self {
+            next_cursor: cursor.map(|cursor| cursor.encode()),
+            ..self
+        }
+    }
+
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Paginated<U> {
+        Paginated {
+            items: self.items.into_iter().map(f).collect(),
+            total: self.total,
+            page: self.page,
+            limit: self.limit,
+            next_cursor: self.next_cursor,
+        }
+    }
+}
+
+/// Sort direction for list endpoints
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+/// Column to sort session listings by
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionSortField {
+    #[default]
+    StartedAt,
+    EndedAt,
+    MicroscopeId,
+    Status,
+}
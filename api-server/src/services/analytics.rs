@@ -0,0 +1,102 @@
+use std::collections::HashMap;
+
+use chrono::NaiveDate;
+
+use crate::models::{DailyUsage, Microscope, MicroscopeUsage, UsageTotals};
+use crate::services::database::MicroscopeDayUsage;
+
+/// Bookable minutes per microscope and day: slots must fall within 08:00-17:00
+pub const LAB_MINUTES_PER_DAY: i64 = 9 * 60;
+
+/// Running totals for a group of (microscope, day) rows
+#[derive(Default)]
+struct Tally {
+    totals: UsageTotals,
+    ended_bookings: i64,
+}
+
+impl Tally {
+    fn add(&mut self, row: &MicroscopeDayUsage) {
+        self.totals.bookings += row.bookings;
+        self.totals.booked_minutes += row.booked_minutes;
+        self.totals.used_minutes += row.used_minutes;
+        self.totals.no_shows += row.no_shows;
+        self.totals.sessions += row.sessions;
+        self.totals.captures += row.captures;
+        self.ended_bookings += row.ended_bookings;
+    }
+
+    fn finish(mut self, capacity_minutes: i64) -> UsageTotals {
+        self.totals.capacity_minutes = capacity_minutes;
+        self.totals.utilization = percentage(self.totals.used_minutes, capacity_minutes);
+        self.totals.booked_utilization = percentage(self.totals.booked_minutes, capacity_minutes);
+        self.totals.no_show_rate = percentage(self.totals.no_shows, self.ended_bookings);
+        self.totals
+    }
+}
+
+/// Totals over every row, against the capacity of `microscopes` microscopes for `days` days
+pub fn summarize(rows: &[MicroscopeDayUsage], microscopes: usize, days: i64) -> UsageTotals {
+    let mut tally = Tally::default();
+    for row in rows {
+        tally.add(row);
+    }
+    tally.finish(microscopes as i64 * days * LAB_MINUTES_PER_DAY)
+}
+
+/// Totals per day from `from` to `to` inclusive; days without activity are included as zeros
+pub fn daily(
+    rows: &[MicroscopeDayUsage],
+    from: NaiveDate,
+    to: NaiveDate,
+    microscopes: usize,
+) -> Vec<DailyUsage> {
+    let mut by_day: HashMap<NaiveDate, Tally> = HashMap::new();
+    for row in rows {
+        by_day.entry(row.date).or_default().add(row);
+    }
+
+    let capacity = microscopes as i64 * LAB_MINUTES_PER_DAY;
+    from.iter_days()
+        .take_while(|date| *date <= to)
+        .map(|date| DailyUsage {
+            date,
+            totals: by_day.remove(&date).unwrap_or_default().finish(capacity),
+        })
+        .collect()
+}
+
+/// Totals per microscope over `days` days, in the order `microscopes` are given
+pub fn by_microscope(
+    rows: &[MicroscopeDayUsage],
+    microscopes: &[Microscope],
+    days: i64,
+) -> Vec<MicroscopeUsage> {
+    let mut by_microscope: HashMap<&str, Tally> = HashMap::new();
+    for row in rows {
+        by_microscope
+            .entry(row.microscope_id.as_str())
+            .or_default()
+            .add(row);
+    }
+
+    microscopes
+        .iter()
+        .map(|microscope| MicroscopeUsage {
+            microscope_id: microscope.id.clone(),
+            name: microscope.name.clone(),
+            totals: by_microscope
+                .remove(microscope.id.as_str())
+                .unwrap_or_default()
+                .finish(days * LAB_MINUTES_PER_DAY),
+        })
+        .collect()
+}
+
+/// `part` as a percentage of `whole`, rounded to one decimal (0 when `whole` is 0)
+fn percentage(part: i64, whole: i64) -> f64 {
+    if whole <= 0 {
+        return 0.0;
+    }
+    (part as f64 * 1000.0 / whole as f64).round() / 10.0
+}
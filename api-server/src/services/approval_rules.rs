@@ -0,0 +1,73 @@
+use chrono::{Datelike, Weekday};
+use uuid::Uuid;
+
+use crate::models::{ApprovalAction, ApprovalRule, Booking, UserRole};
+
+/// Rule name recorded when no configured rule matches a booking
+pub const DEFAULT_RULE_NAME: &str = "Default: require approval";
+
+/// Result of evaluating the approval rules for a booking
+#[derive(Debug, Clone)]
+pub struct RuleOutcome {
+    pub rule_id: Option<Uuid>,
+    pub rule_name: String,
+    pub action: ApprovalAction,
+}
+
+/// Evaluate rules in ascending priority and return the first enabled match.
+/// Bookings no rule matches fall back to requiring approval.
+pub fn evaluate(
+    rules: &[ApprovalRule],
+    booking: &Booking,
+    requester_role: UserRole,
+) -> RuleOutcome {
+    let mut ordered: Vec<&ApprovalRule> = rules.iter().filter(|r| r.enabled).collect();
+    ordered.sort_by_key(|r| r.priority);
+
+    ordered
+        .into_iter()
+        .find(|rule| rule_matches(rule, booking, requester_role))
+        .map(|rule| RuleOutcome {
+            rule_id: Some(rule.id),
+            rule_name: rule.name.clone(),
+            action: rule.action,
+        })
+        .unwrap_or_else(|| RuleOutcome {
+            rule_id: None,
+            rule_name: DEFAULT_RULE_NAME.to_string(),
+            action: ApprovalAction::RequireApproval,
+        })
+}
+
+/// Check every condition set on the rule against the booking
+pub fn rule_matches(rule: &ApprovalRule, booking: &Booking, requester_role: UserRole) -> bool {
+    let duration = booking.slot_end - booking.slot_start;
+
+    if !rule.requester_roles.is_empty() && !rule.requester_roles.contains(&requester_role) {
+        return false;
+    }
+
+    if let Some(under) = rule.duration_under_minutes {
+        if duration >= under {
+            return false;
+        }
+    }
+
+    if let Some(over) = rule.duration_over_minutes {
+        if duration <= over {
+            return false;
+        }
+    }
+
+    if rule.weekdays_only && matches!(booking.date.weekday(), Weekday::Sat | Weekday::Sun) {
+        return false;
+    }
+
+    if let Some(microscope_id) = &rule.microscope_id {
+        if *microscope_id != booking.microscope_id {
+            return false;
+        }
+    }
+
+    true
+}
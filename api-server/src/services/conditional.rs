@@ -0,0 +1,95 @@
+//! Conditional (`If-None-Match`, `If-Modified-Since`, `If-Range`) and `Range` request
+//! handling for file downloads
+
+use std::time::SystemTime;
+
+use axum::http::{header, HeaderMap};
+
+/// `If-None-Match` takes precedence; `If-Modified-Since` is only used without it
+pub fn is_not_modified(
+    request_headers: &HeaderMap,
+    etag: &str,
+    last_modified: Option<SystemTime>,
+) -> bool {
+    if let Some(if_none_match) = request_headers.get(header::IF_NONE_MATCH) {
+        let if_none_match = if_none_match.to_str().unwrap_or_default();
+        // Weak comparison: W/"x" matches "x"
+        return if_none_match
+            .split(',')
+            .map(str::trim)
+            .any(|tag| tag == "*" || tag.strip_prefix("W/").unwrap_or(tag) == etag);
+    }
+
+    match (
+        request_headers
+            .get(header::IF_MODIFIED_SINCE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| httpdate::parse_http_date(value).ok()),
+        last_modified,
+    ) {
+        (Some(since), Some(modified)) => modified <= since,
+        _ => false,
+    }
+}
+
+/// A range is only honoured if `If-Range` is absent or still describes the current file
+pub fn if_range_matches(
+    request_headers: &HeaderMap,
+    etag: &str,
+    last_modified: Option<SystemTime>,
+) -> bool {
+    let Some(if_range) = request_headers
+        .get(header::IF_RANGE)
+        .and_then(|value| value.to_str().ok())
+    else {
+        return true;
+    };
+
+    if if_range.starts_with('"') {
+        // Strong comparison
+        return if_range == etag;
+    }
+
+    match (httpdate::parse_http_date(if_range).ok(), last_modified) {
+        (Some(date), Some(modified)) => date == modified,
+        _ => false,
+    }
+}
+
+/// Parse a single `bytes=` range into inclusive offsets.
+///
+/// `None` means the header should be ignored (malformed, another unit, or several ranges,
+/// which are served as the full file); `Some(Err(()))` means it cannot be satisfied.
+pub fn parse_byte_range(value: &str, size: u64) -> Option<Result<(u64, u64), ()>> {
+    let spec = value.trim().strip_prefix("bytes=")?;
+    if spec.contains(',') {
+        return None;
+    }
+    let (start, end) = spec.split_once('-')?;
+    let (start, end) = (start.trim(), end.trim());
+
+    let range = if start.is_empty() {
+        // Suffix range: the last N bytes
+        let suffix: u64 = end.parse().ok()?;
+        if suffix == 0 || size == 0 {
+            return Some(Err(()));
+        }
+        (size.saturating_sub(suffix), size - 1)
+    } else {
+        let start: u64 = start.parse().ok()?;
+        let end = if end.is_empty() {
+            u64::MAX
+        } else {
+            end.parse().ok()?
+        };
+        if end < start {
+            return None;
+        }
+        if start >= size {
+            return Some(Err(()));
+        }
+        (start, end.min(size - 1))
+    };
+
+    Some(Ok(range))
+}
@@ -1,12 +1,23 @@
 use chrono::{DateTime, Datelike, FixedOffset, NaiveDate, Utc};
 use serde_json;
+use sqlx::postgres::PgRow;
 use sqlx::types::time;
-use sqlx::{Error as SqlxError, PgPool, Row};
+use sqlx::{Error as SqlxError, PgExecutor, PgPool, Postgres, Row, Transaction};
 use uuid::Uuid;
 
 use crate::models::{
-    Booking, BookingStatus, Image, ImageMetadata, Session, SessionStatus, User, UserRole,
+    AcademicTerm, ApprovalAction, ApprovalDecision, ApprovalRule, Booking, BookingEvent,
+    BookingEventType, BookingStatus, Cursor, HandoverKind, HourlyDemand, Image, ImageMetadata,
+    MaintenanceKind, MaintenanceWindow, Measurement, Microscope, MicroscopeState,
+    MicroscopeStorageUsage, Notification, NotificationKind, PageRequest, RequesterUsage,
+    RetentionPolicy, Session, SessionEvent, SessionEventType, SessionHandover, SessionNote,
+    SessionNoteKind, SessionParticipant, SessionSortField, SessionStatus, SessionStorageUsage,
+    SortOrder, TrashedImage, User, UserRole, UserStorageUsage,
 };
+use crate::services::markdown;
+
+/// Partial unique index allowing one active session per microscope
+pub const ACTIVE_SESSION_PER_MICROSCOPE_INDEX: &str = "idx_sessions_one_active_per_microscope";
 
 /// Database service for handling all database operations
 #[derive(Clone)]
@@ -130,7 +141,16 @@ impl DatabaseService {
         }))
     }
 
-    pub async fn create_booking(&self, booking: &Booking) -> Result<Booking, SqlxError> {
+    pub async fn create_booking(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        booking: &Booking,
+    ) -> Result<Booking, SqlxError> {
+        let required_capabilities = booking
+            .required_capabilities
+            .as_ref()
+            .map(|caps| serde_json::to_value(caps).unwrap());
+
         // Convert chrono NaiveDate to time Date
         let time_date =
             time::Date::from_ordinal_date(booking.date.year(), booking.date.ordinal() as u16)
@@ -140,12 +160,13 @@ impl DatabaseService {
             r#"
             INSERT INTO bookings (
                 microscope_id, date, slot_start, slot_end, title, 
-                group_name, attendees, requester_id, requester_name, status, approved_by
+                group_name, attendees, requester_id, requester_name, status, approved_by,
+                required_capabilities
             )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
             RETURNING id, microscope_id, date, slot_start, slot_end, title,
                      group_name, attendees, requester_id, requester_name, 
-                     status, approved_by, created_at
+                     status, approved_by, required_capabilities, created_at
             "#,
             booking.microscope_id,
             time_date,
@@ -160,16 +181,19 @@ impl DatabaseService {
                 BookingStatus::Pending => "Pending",
                 BookingStatus::Approved => "Approved",
                 BookingStatus::Rejected => "Rejected",
+                BookingStatus::Cancelled => "Cancelled",
             },
-            booking.approved_by
+            booking.approved_by,
+            required_capabilities
         )
-        .fetch_one(&self.pool)
+        .fetch_one(&mut **tx)
         .await?;
 
         let status = match row.status.as_str() {
             "Pending" => BookingStatus::Pending,
             "Approved" => BookingStatus::Approved,
             "Rejected" => BookingStatus::Rejected,
+            "Cancelled" => BookingStatus::Cancelled,
             _ => BookingStatus::Pending, // default fallback
         };
 
@@ -193,73 +217,118 @@ impl DatabaseService {
             requester_name: row.requester_name,
             status,
             approved_by: row.approved_by,
+            required_capabilities: row
+                .required_capabilities
+                .and_then(|v| serde_json::from_value(v).ok()),
             created_at: DateTime::from_timestamp(row.created_at.unix_timestamp(), 0)
                 .unwrap()
                 .fixed_offset(),
         })
     }
 
-    pub async fn get_bookings_by_date_and_microscope(
+    /// List bookings matching `filter`, returning one page and the total match count.
+    /// Bookings on one microscope and day are ordered by start time, others newest first.
+    pub async fn list_bookings(
         &self,
-        microscope_id: &str,
-        date: NaiveDate,
-    ) -> Result<Vec<Booking>, SqlxError> {
-        // Convert chrono NaiveDate to time Date
-        let time_date = time::Date::from_ordinal_date(date.year(), date.ordinal() as u16).unwrap();
+        filter: &BookingFilter<'_>,
+        page: &PageRequest,
+    ) -> Result<(Vec<Booking>, u64, Option<Cursor>), SqlxError> {
+        let mut conditions = String::from(" WHERE 1=1");
+        let mut param_count = 0;
 
-        let rows = sqlx::query!(
+        if filter.requester_id.is_some() {
+            param_count += 1;
+            conditions.push_str(&format!(" AND requester_id = ${}", param_count));
+        }
+        if filter.microscope_id.is_some() {
+            param_count += 1;
+            conditions.push_str(&format!(" AND microscope_id = ${}", param_count));
+        }
+        if filter.date.is_some() {
+            param_count += 1;
+            conditions.push_str(&format!(" AND date = ${}", param_count));
+        }
+
+        let count_query = format!("SELECT COUNT(*) FROM bookings{}", conditions);
+        let total: i64 = bind_booking_filter(sqlx::query(&count_query), filter)
+            .fetch_one(&self.pool)
+            .await?
+            .get(0);
+
+        let order = match filter.date {
+            _ if page.by_cursor => "created_at, id",
+            Some(_) => "slot_start, id",
+            None => "date DESC, slot_start DESC, id",
+        };
+        conditions.push_str(&cursor_condition("", page, &mut param_count));
+
+        let query = format!(
             r#"
             SELECT id, microscope_id, date, slot_start, slot_end, title,
                    group_name, attendees, requester_id, requester_name,
-                   status, approved_by, created_at
-            FROM bookings 
-            WHERE microscope_id = $1 AND date = $2
-            ORDER BY slot_start
+                   status, approved_by, required_capabilities, created_at, {}
+            FROM bookings{}
+            ORDER BY {}
+            LIMIT ${} OFFSET ${}
             "#,
-            microscope_id,
-            time_date
-        )
-        .fetch_all(&self.pool)
-        .await?;
+            cursor_column(""),
+            conditions,
+            order,
+            param_count + 1,
+            param_count + 2
+        );
+
+        let mut rows = bind_cursor(bind_booking_filter(sqlx::query(&query), filter), page)
+            .bind(page_fetch_limit(page))
+            .bind(page.offset as i64)
+            .fetch_all(&self.pool)
+            .await?;
+        let next_cursor = split_cursor_page(&mut rows, page);
 
         let bookings = rows
             .into_iter()
             .map(|row| {
-                let status = match row.status.as_str() {
+                let status = match row.get::<&str, _>("status") {
                     "Pending" => BookingStatus::Pending,
                     "Approved" => BookingStatus::Approved,
                     "Rejected" => BookingStatus::Rejected,
+                    "Cancelled" => BookingStatus::Cancelled,
                     _ => BookingStatus::Pending,
                 };
 
-                let naive_date = NaiveDate::from_ymd_opt(
-                    row.date.year(),
-                    row.date.month() as u32,
-                    row.date.day() as u32,
-                )
-                .unwrap();
+                let date = row.get::<time::Date, _>("date");
+                let naive_date =
+                    NaiveDate::from_ymd_opt(date.year(), date.month() as u32, date.day() as u32)
+                        .unwrap();
 
                 Booking {
-                    id: row.id,
-                    microscope_id: row.microscope_id,
+                    id: row.get("id"),
+                    microscope_id: row.get("microscope_id"),
                     date: naive_date,
-                    slot_start: row.slot_start,
-                    slot_end: row.slot_end,
-                    title: row.title,
-                    group_name: row.group_name,
-                    attendees: row.attendees,
-                    requester_id: row.requester_id,
-                    requester_name: row.requester_name,
+                    slot_start: row.get("slot_start"),
+                    slot_end: row.get("slot_end"),
+                    title: row.get("title"),
+                    group_name: row.get("group_name"),
+                    attendees: row.get("attendees"),
+                    requester_id: row.get("requester_id"),
+                    requester_name: row.get("requester_name"),
                     status,
-                    approved_by: row.approved_by,
-                    created_at: DateTime::from_timestamp(row.created_at.unix_timestamp(), 0)
-                        .unwrap()
-                        .fixed_offset(),
+                    approved_by: row.get("approved_by"),
+                    required_capabilities: row
+                        .get::<Option<serde_json::Value>, _>("required_capabilities")
+                        .and_then(|v| serde_json::from_value(v).ok()),
+                    created_at: DateTime::from_timestamp(
+                        row.get::<time::OffsetDateTime, _>("created_at")
+                            .unix_timestamp(),
+                        0,
+                    )
+                    .unwrap()
+                    .fixed_offset(),
                 }
             })
             .collect();
 
-        Ok(bookings)
+        Ok((bookings, total.max(0) as u64, next_cursor))
     }
 
     pub async fn get_bookings_by_user(&self, user_id: Uuid) -> Result<Vec<Booking>, SqlxError> {
@@ -267,7 +336,7 @@ impl DatabaseService {
             r#"
             SELECT id, microscope_id, date, slot_start, slot_end, title,
                    group_name, attendees, requester_id, requester_name,
-                   status, approved_by, created_at
+                   status, approved_by, required_capabilities, created_at
             FROM bookings 
             WHERE requester_id = $1
             ORDER BY date DESC, slot_start DESC
@@ -284,6 +353,7 @@ impl DatabaseService {
                     "Pending" => BookingStatus::Pending,
                     "Approved" => BookingStatus::Approved,
                     "Rejected" => BookingStatus::Rejected,
+                    "Cancelled" => BookingStatus::Cancelled,
                     _ => BookingStatus::Pending,
                 };
 
@@ -307,6 +377,9 @@ impl DatabaseService {
                     requester_name: row.requester_name,
                     status,
                     approved_by: row.approved_by,
+                    required_capabilities: row
+                        .required_capabilities
+                        .and_then(|v| serde_json::from_value(v).ok()),
                     created_at: DateTime::from_timestamp(row.created_at.unix_timestamp(), 0)
                         .unwrap()
                         .fixed_offset(),
@@ -319,6 +392,7 @@ impl DatabaseService {
 
     pub async fn update_booking_status(
         &self,
+        tx: &mut Transaction<'_, Postgres>,
         booking_id: Uuid,
         status: BookingStatus,
         approved_by: Option<Uuid>,
@@ -330,23 +404,25 @@ impl DatabaseService {
             WHERE id = $1
             RETURNING id, microscope_id, date, slot_start, slot_end, title,
                      group_name, attendees, requester_id, requester_name,
-                     status, approved_by, created_at
+                     status, approved_by, required_capabilities, created_at
             "#,
             booking_id,
             match status {
                 BookingStatus::Pending => "Pending",
                 BookingStatus::Approved => "Approved",
                 BookingStatus::Rejected => "Rejected",
+                BookingStatus::Cancelled => "Cancelled",
             },
             approved_by
         )
-        .fetch_one(&self.pool)
+        .fetch_one(&mut **tx)
         .await?;
 
         let booking_status = match row.status.as_str() {
             "Pending" => BookingStatus::Pending,
             "Approved" => BookingStatus::Approved,
             "Rejected" => BookingStatus::Rejected,
+            "Cancelled" => BookingStatus::Cancelled,
             _ => BookingStatus::Pending,
         };
 
@@ -370,6 +446,9 @@ impl DatabaseService {
             requester_name: row.requester_name,
             status: booking_status,
             approved_by: row.approved_by,
+            required_capabilities: row
+                .required_capabilities
+                .and_then(|v| serde_json::from_value(v).ok()),
             created_at: DateTime::from_timestamp(row.created_at.unix_timestamp(), 0)
                 .unwrap()
                 .fixed_offset(),
@@ -379,10 +458,10 @@ impl DatabaseService {
     pub async fn create_session(&self, session: &Session) -> Result<Session, SqlxError> {
         let row = sqlx::query!(
             r#"
-            INSERT INTO sessions (user_id, booking_id, microscope_id, status, notes)
-            VALUES ($1, $2, $3, $4, $5)
+            INSERT INTO sessions (user_id, booking_id, microscope_id, status, notes, last_seen_at)
+            VALUES ($1, $2, $3, $4, $5, NOW())
             RETURNING id, user_id, booking_id, microscope_id, 
-                     status, started_at, ended_at, notes
+                     status, started_at, ended_at, notes, ended_by, end_reason, last_seen_at
             "#,
             session.user_id,
             session.booking_id,
@@ -419,23 +498,52 @@ impl DatabaseService {
                     .with_timezone(&Utc)
             }),
             notes: row.notes,
+            ended_by: row.ended_by,
+            end_reason: row.end_reason,
+            last_seen_at: row.last_seen_at.map(|dt| {
+                DateTime::from_timestamp(dt.unix_timestamp(), 0)
+                    .unwrap()
+                    .with_timezone(&Utc)
+            }),
+            present: false,
         })
     }
 
-    pub async fn get_active_session_by_user(
+    /// Remove a session that never properly started (e.g. the IA system rejected it)
+    pub async fn delete_session(&self, session_id: Uuid) -> Result<u64, SqlxError> {
+        let result = sqlx::query!("DELETE FROM sessions WHERE id = $1", session_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Record a heartbeat from the session user's UI. Returns 0 if the session is not active.
+    pub async fn touch_session_heartbeat(&self, session_id: Uuid) -> Result<u64, SqlxError> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE sessions
+            SET last_seen_at = NOW()
+            WHERE id = $1 AND status = 'Active'
+            "#,
+            session_id
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    pub async fn get_active_session_by_microscope(
         &self,
-        user_id: Uuid,
+        microscope_id: &str,
     ) -> Result<Option<Session>, SqlxError> {
         let row = sqlx::query!(
             r#"
             SELECT id, user_id, booking_id, microscope_id,
-                   status, started_at, ended_at, notes
-            FROM sessions 
-            WHERE user_id = $1 AND status = 'Active'
-            ORDER BY started_at DESC
-            LIMIT 1
+                   status, started_at, ended_at, notes, ended_by, end_reason, last_seen_at
+            FROM sessions
+            WHERE microscope_id = $1 AND status = 'Active'
             "#,
-            user_id
+            microscope_id
         )
         .fetch_optional(&self.pool)
         .await?;
@@ -463,171 +571,117 @@ impl DatabaseService {
                         .with_timezone(&Utc)
                 }),
                 notes: row.notes,
+                ended_by: row.ended_by,
+                end_reason: row.end_reason,
+                last_seen_at: row.last_seen_at.map(|dt| {
+                    DateTime::from_timestamp(dt.unix_timestamp(), 0)
+                        .unwrap()
+                        .with_timezone(&Utc)
+                }),
+                present: false,
             }
         }))
     }
 
-    pub async fn end_session(
-        &self,
-        session_id: Uuid,
-        notes: Option<String>,
-    ) -> Result<Session, SqlxError> {
-        let row = sqlx::query!(
+    /// Record microscope activity on the microscope's active session
+    pub async fn touch_session_activity(&self, microscope_id: &str) -> Result<u64, SqlxError> {
+        let result = sqlx::query!(
             r#"
-            UPDATE sessions 
-            SET status = 'Completed', ended_at = NOW(), notes = COALESCE($2, notes)
-            WHERE id = $1
-            RETURNING id, user_id, booking_id, microscope_id,
-                     status, started_at, ended_at, notes
+            UPDATE sessions
+            SET last_activity_at = NOW()
+            WHERE microscope_id = $1 AND status = 'Active'
             "#,
-            session_id,
-            notes
+            microscope_id
         )
-        .fetch_one(&self.pool)
+        .execute(&self.pool)
         .await?;
-
-        let session_status = match row.status.as_str() {
-            "Active" => SessionStatus::Active,
-            "Completed" => SessionStatus::Completed,
-            "Aborted" => SessionStatus::Aborted,
-            _ => SessionStatus::Completed,
-        };
-
-        Ok(Session {
-            id: row.id,
-            user_id: row.user_id,
-            booking_id: row.booking_id,
-            microscope_id: row.microscope_id,
-            status: session_status,
-            started_at: DateTime::from_timestamp(row.started_at.unix_timestamp(), 0)
-                .unwrap()
-                .with_timezone(&Utc),
-            ended_at: row.ended_at.map(|dt| {
-                DateTime::from_timestamp(dt.unix_timestamp(), 0)
-                    .unwrap()
-                    .with_timezone(&Utc)
-            }),
-            notes: row.notes,
-        })
+        Ok(result.rows_affected())
     }
 
-    pub async fn list_sessions(
+    /// Active sessions whose booking ends before `cutoff` (booking slots are local to `timezone`)
+    pub async fn get_sessions_with_booking_ending_before(
         &self,
-        microscope_id: Option<&str>,
-        user_id: Option<Uuid>,
-        status: Option<SessionStatus>,
-        active_only: bool,
-        limit: u64,
-        offset: u64,
-    ) -> Result<Vec<Session>, SqlxError> {
-        let mut query = r#"
-            SELECT id, user_id, booking_id, microscope_id,
-                   status, started_at, ended_at, notes
-            FROM sessions 
-            WHERE 1=1
-        "#
-        .to_string();
-
-        let mut conditions = Vec::new();
-        let mut param_count = 0;
-
-        if let Some(mid) = microscope_id {
-            param_count += 1;
-            conditions.push(format!(" AND microscope_id = ${}", param_count));
-        }
-
-        if let Some(uid) = user_id {
-            param_count += 1;
-            conditions.push(format!(" AND user_id = ${}", param_count));
-        }
-
-        if let Some(s) = &status {
-            param_count += 1;
-            conditions.push(format!(" AND status = ${}", param_count));
-        }
-
-        if active_only {
-            conditions.push(" AND status = 'Active'".to_string());
-        }
-
-        for condition in conditions {
-            query.push_str(&condition);
-        }
-
-        query.push_str(" ORDER BY started_at DESC");
-        param_count += 1;
-        query.push_str(&format!(" LIMIT ${}", param_count));
-        param_count += 1;
-        query.push_str(&format!(" OFFSET ${}", param_count));
-
-        let mut sql_query = sqlx::query(&query);
-
-        if let Some(mid) = microscope_id {
-            sql_query = sql_query.bind(mid);
-        }
-        if let Some(uid) = user_id {
-            sql_query = sql_query.bind(uid);
-        }
-        if let Some(s) = &status {
-            let status_str = match s {
-                SessionStatus::Active => "Active",
-                SessionStatus::Completed => "Completed",
-                SessionStatus::Aborted => "Aborted",
-            };
-            sql_query = sql_query.bind(status_str);
-        }
-
-        sql_query = sql_query.bind(limit as i64).bind(offset as i64);
-
-        let rows = sql_query.fetch_all(&self.pool).await?;
+        cutoff: DateTime<Utc>,
+        unwarned_only: bool,
+        timezone: &str,
+    ) -> Result<Vec<SessionDeadline>, SqlxError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT s.id, s.user_id, s.microscope_id, b.id as booking_id, b.slot_end,
+                   ((b.date + make_interval(mins => b.slot_end)) AT TIME ZONE $3) as "booking_ends_at!"
+            FROM sessions s
+            JOIN bookings b ON b.id = s.booking_id
+            WHERE s.status = 'Active'
+              AND ((b.date + make_interval(mins => b.slot_end)) AT TIME ZONE $3) < $1
+              AND (NOT $2 OR s.end_warning_sent_at IS NULL)
+            ORDER BY 6
+            "#,
+            time::OffsetDateTime::from_unix_timestamp(cutoff.timestamp()).unwrap(),
+            unwarned_only,
+            timezone
+        )
+        .fetch_all(&self.pool)
+        .await?;
 
-        let sessions = rows
+        Ok(rows
             .into_iter()
-            .map(|row| {
-                let session_status = match row.get::<&str, _>("status") {
-                    "Active" => SessionStatus::Active,
-                    "Completed" => SessionStatus::Completed,
-                    "Aborted" => SessionStatus::Aborted,
-                    _ => SessionStatus::Active,
-                };
-
-                Session {
-                    id: row.get("id"),
-                    user_id: row.get("user_id"),
-                    booking_id: row.get("booking_id"),
-                    microscope_id: row.get("microscope_id"),
-                    status: session_status,
-                    started_at: DateTime::from_timestamp(
-                        row.get::<time::OffsetDateTime, _>("started_at")
-                            .unix_timestamp(),
-                        0,
-                    )
+            .map(|row| SessionDeadline {
+                session_id: row.id,
+                user_id: row.user_id,
+                microscope_id: row.microscope_id,
+                booking_id: row.booking_id,
+                booking_ends_at: DateTime::from_timestamp(row.booking_ends_at.unix_timestamp(), 0)
                     .unwrap()
                     .with_timezone(&Utc),
-                    ended_at: row
-                        .get::<Option<time::OffsetDateTime>, _>("ended_at")
-                        .map(|dt| {
-                            DateTime::from_timestamp(dt.unix_timestamp(), 0)
-                                .unwrap()
-                                .with_timezone(&Utc)
-                        }),
-                    notes: row.get("notes"),
-                }
+                slot_end: row.slot_end,
             })
-            .collect();
+            .collect())
+    }
 
-        Ok(sessions)
+    pub async fn mark_session_end_warning_sent(&self, session_id: Uuid) -> Result<u64, SqlxError> {
+        let result = sqlx::query!(
+            "UPDATE sessions SET end_warning_sent_at = NOW() WHERE id = $1",
+            session_id
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected())
     }
 
-    pub async fn get_session_by_id(&self, session_id: Uuid) -> Result<Option<Session>, SqlxError> {
+    /// Active sessions with no microscope activity (or start) since `idle_since`
+    pub async fn get_idle_session_ids(
+        &self,
+        idle_since: DateTime<Utc>,
+    ) -> Result<Vec<Uuid>, SqlxError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT id
+            FROM sessions
+            WHERE status = 'Active'
+              AND COALESCE(last_activity_at, started_at) < $1
+            "#,
+            time::OffsetDateTime::from_unix_timestamp(idle_since.timestamp()).unwrap()
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| row.id).collect())
+    }
+
+    pub async fn get_active_session_by_user(
+        &self,
+        user_id: Uuid,
+    ) -> Result<Option<Session>, SqlxError> {
         let row = sqlx::query!(
             r#"
             SELECT id, user_id, booking_id, microscope_id,
-                   status, started_at, ended_at, notes
+                   status, started_at, ended_at, notes, ended_by, end_reason, last_seen_at
             FROM sessions 
-            WHERE id = $1
+            WHERE user_id = $1 AND status = 'Active'
+            ORDER BY started_at DESC
+            LIMIT 1
             "#,
-            session_id
+            user_id
         )
         .fetch_optional(&self.pool)
         .await?;
@@ -655,542 +709,3417 @@ impl DatabaseService {
                         .with_timezone(&Utc)
                 }),
                 notes: row.notes,
+                ended_by: row.ended_by,
+                end_reason: row.end_reason,
+                last_seen_at: row.last_seen_at.map(|dt| {
+                    DateTime::from_timestamp(dt.unix_timestamp(), 0)
+                        .unwrap()
+                        .with_timezone(&Utc)
+                }),
+                present: false,
             }
         }))
     }
 
-    pub async fn get_booking_by_id(&self, booking_id: Uuid) -> Result<Option<Booking>, SqlxError> {
+    /// Active session the user owns or, failing that, the latest one shared with them
+    pub async fn get_current_session_for_user(
+        &self,
+        user_id: Uuid,
+    ) -> Result<Option<Session>, SqlxError> {
         let row = sqlx::query!(
             r#"
-            SELECT id, microscope_id, date, slot_start, slot_end, title,
-                   group_name, attendees, requester_id, requester_name,
-                   status, approved_by, created_at
-            FROM bookings 
-            WHERE id = $1
+            SELECT s.id, s.user_id, s.booking_id, s.microscope_id,
+                   s.status, s.started_at, s.ended_at, s.notes, s.ended_by, s.end_reason,
+                   s.last_seen_at
+            FROM sessions s
+            WHERE s.status = 'Active'
+              AND (
+                  s.user_id = $1
+                  OR EXISTS (
+                      SELECT 1 FROM session_participants p
+                      WHERE p.session_id = s.id AND p.user_id = $1
+                  )
+              )
+            ORDER BY s.user_id = $1 DESC, s.started_at DESC
+            LIMIT 1
             "#,
-            booking_id
+            user_id
         )
         .fetch_optional(&self.pool)
         .await?;
 
         Ok(row.map(|row| {
-            let status = match row.status.as_str() {
-                "Pending" => BookingStatus::Pending,
-                "Approved" => BookingStatus::Approved,
-                "Rejected" => BookingStatus::Rejected,
-                _ => BookingStatus::Pending,
+            let session_status = match row.status.as_str() {
+                "Active" => SessionStatus::Active,
+                "Completed" => SessionStatus::Completed,
+                "Aborted" => SessionStatus::Aborted,
+                _ => SessionStatus::Active,
             };
 
-            let naive_date = NaiveDate::from_ymd_opt(
-                row.date.year(),
-                row.date.month() as u32,
-                row.date.day() as u32,
-            )
-            .unwrap();
-
-            Booking {
+            Session {
                 id: row.id,
+                user_id: row.user_id,
+                booking_id: row.booking_id,
                 microscope_id: row.microscope_id,
-                date: naive_date,
-                slot_start: row.slot_start,
-                slot_end: row.slot_end,
-                title: row.title,
-                group_name: row.group_name,
-                attendees: row.attendees,
-                requester_id: row.requester_id,
-                requester_name: row.requester_name,
-                status,
-                approved_by: row.approved_by,
-                created_at: DateTime::from_timestamp(row.created_at.unix_timestamp(), 0)
+                status: session_status,
+                started_at: DateTime::from_timestamp(row.started_at.unix_timestamp(), 0)
                     .unwrap()
-                    .fixed_offset(),
+                    .with_timezone(&Utc),
+                ended_at: row.ended_at.map(|dt| {
+                    DateTime::from_timestamp(dt.unix_timestamp(), 0)
+                        .unwrap()
+                        .with_timezone(&Utc)
+                }),
+                notes: row.notes,
+                ended_by: row.ended_by,
+                end_reason: row.end_reason,
+                last_seen_at: row.last_seen_at.map(|dt| {
+                    DateTime::from_timestamp(dt.unix_timestamp(), 0)
+                        .unwrap()
+                        .with_timezone(&Utc)
+                }),
+                present: false,
             }
         }))
     }
 
-    pub async fn create_image(&self, image: &Image) -> Result<Image, SqlxError> {
-        let metadata_json = serde_json::to_value(&image.metadata).unwrap();
-
-        let created_image = sqlx::query!(
-            r#"
-            INSERT INTO images (
-                id, session_id, filename, file_path, content_type, file_size,
-                width, height, metadata, captured_at
-            )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
-            RETURNING id, session_id, filename, file_path, content_type, file_size,
-                     width, height, metadata, captured_at
-            "#,
-            image.id,
-            image.session_id,
-            image.filename,
-            image.file_path,
-            image.content_type,
-            image.file_size,
-            image.width,
-            image.height,
-            metadata_json,
-            time::OffsetDateTime::from_unix_timestamp(image.captured_at.timestamp()).unwrap()
-        )
-        .fetch_one(&self.pool)
-        .await?;
-
-        let metadata: ImageMetadata = serde_json::from_value(created_image.metadata).unwrap();
-
-        Ok(Image {
-            id: created_image.id,
-            session_id: created_image.session_id,
-            filename: created_image.filename,
-            file_path: created_image.file_path,
-            content_type: created_image.content_type,
-            file_size: created_image.file_size,
-            width: created_image.width,
-            height: created_image.height,
-            metadata,
-            captured_at: DateTime::from_timestamp(created_image.captured_at.unix_timestamp(), 0)
-                .unwrap()
-                .with_timezone(&Utc),
-        })
-    }
-
-    pub async fn get_images_by_session(&self, session_id: Uuid) -> Result<Vec<Image>, SqlxError> {
-        let rows = sqlx::query!(
-            r#"
-            SELECT id, session_id, filename, file_path, content_type, file_size,
-                   width, height, metadata, captured_at
-            FROM images 
-            WHERE session_id = $1
-            ORDER BY captured_at DESC
-            "#,
-            session_id
-        )
-        .fetch_all(&self.pool)
-        .await?;
-
-        let images = rows
-            .into_iter()
-            .map(|row| {
-                let metadata: ImageMetadata =
-                    serde_json::from_value(row.metadata).unwrap_or_default();
-                Image {
-                    id: row.id,
-                    session_id: row.session_id,
-                    filename: row.filename,
-                    file_path: row.file_path,
-                    content_type: row.content_type,
-                    file_size: row.file_size,
-                    width: row.width,
-                    height: row.height,
-                    metadata,
-                    captured_at: DateTime::from_timestamp(row.captured_at.unix_timestamp(), 0)
-                        .unwrap()
-                        .with_timezone(&Utc),
-                }
-            })
-            .collect();
-
-        Ok(images)
-    }
-
-    pub async fn get_latest_image_by_session(
+    /// End an active session with the given final status. Returns None if the session was
+    /// not active (e.g. it was ended concurrently).
+    pub async fn end_session(
         &self,
         session_id: Uuid,
-    ) -> Result<Option<Image>, SqlxError> {
+        status: SessionStatus,
+        notes: Option<String>,
+        ended_by: Option<Uuid>,
+        end_reason: Option<&str>,
+    ) -> Result<Option<Session>, SqlxError> {
         let row = sqlx::query!(
             r#"
-            SELECT id, session_id, filename, file_path, content_type, file_size,
-                   width, height, metadata, captured_at
-            FROM images
-            WHERE session_id = $1
-            ORDER BY captured_at DESC
-            LIMIT 1
+            UPDATE sessions
+            SET status = $2, ended_at = NOW(), notes = COALESCE($3, notes),
+                ended_by = $4, end_reason = $5
+            WHERE id = $1 AND status = 'Active'
+            RETURNING id, user_id, booking_id, microscope_id,
+                     status, started_at, ended_at, notes, ended_by, end_reason, last_seen_at
             "#,
-            session_id
+            session_id,
+            match status {
+                SessionStatus::Active => "Active",
+                SessionStatus::Completed => "Completed",
+                SessionStatus::Aborted => "Aborted",
+            },
+            notes,
+            ended_by,
+            end_reason
         )
         .fetch_optional(&self.pool)
         .await?;
 
         Ok(row.map(|row| {
-            let metadata: ImageMetadata = serde_json::from_value(row.metadata).unwrap_or_default();
-            Image {
-                id: row.id,
-                session_id: row.session_id,
-                filename: row.filename,
-                file_path: row.file_path,
-                content_type: row.content_type,
-                file_size: row.file_size,
-                width: row.width,
-                height: row.height,
-                metadata,
-                captured_at: DateTime::from_timestamp(row.captured_at.unix_timestamp(), 0)
-                    .unwrap()
-                    .with_timezone(&Utc),
-            }
-        }))
-    }
-
-    pub async fn get_image_by_id(&self, image_id: Uuid) -> Result<Option<Image>, SqlxError> {
-        let row = sqlx::query!(
-            r#"
-            SELECT id, session_id, filename, file_path, content_type, file_size,
-                   width, height, metadata, captured_at
-            FROM images
-            WHERE id = $1
-            "#,
-            image_id
-        )
-        .fetch_optional(&self.pool)
-        .await?;
+            let session_status = match row.status.as_str() {
+                "Active" => SessionStatus::Active,
+                "Completed" => SessionStatus::Completed,
+                "Aborted" => SessionStatus::Aborted,
+                _ => SessionStatus::Completed,
+            };
 
-        Ok(row.map(|row| {
-            let metadata: ImageMetadata = serde_json::from_value(row.metadata).unwrap_or_default();
-            Image {
+            Session {
                 id: row.id,
-                session_id: row.session_id,
-                filename: row.filename,
-                file_path: row.file_path,
-                content_type: row.content_type,
-                file_size: row.file_size,
-                width: row.width,
-                height: row.height,
-                metadata,
-                captured_at: DateTime::from_timestamp(row.captured_at.unix_timestamp(), 0)
+                user_id: row.user_id,
+                booking_id: row.booking_id,
+                microscope_id: row.microscope_id,
+                status: session_status,
+                started_at: DateTime::from_timestamp(row.started_at.unix_timestamp(), 0)
                     .unwrap()
                     .with_timezone(&Utc),
+                ended_at: row.ended_at.map(|dt| {
+                    DateTime::from_timestamp(dt.unix_timestamp(), 0)
+                        .unwrap()
+                        .with_timezone(&Utc)
+                }),
+                notes: row.notes,
+                ended_by: row.ended_by,
+                end_reason: row.end_reason,
+                last_seen_at: row.last_seen_at.map(|dt| {
+                    DateTime::from_timestamp(dt.unix_timestamp(), 0)
+                        .unwrap()
+                        .with_timezone(&Utc)
+                }),
+                present: false,
             }
         }))
     }
 
-    pub async fn get_images_by_user(
+    /// List sessions matching `filter`, returning one page and the total match count
+    pub async fn list_sessions(
         &self,
-        user_id: Uuid,
-        limit: u64,
-        offset: u64,
-        tags: Option<String>,
-        date_from: Option<NaiveDate>,
-        date_to: Option<NaiveDate>,
-    ) -> Result<Vec<Image>, SqlxError> {
-        let mut query = r#"
-            SELECT i.id, i.session_id, i.filename, i.file_path, i.content_type, i.file_size,
-                   i.width, i.height, i.metadata, i.captured_at
-            FROM images i
-            INNER JOIN sessions s ON i.session_id = s.id
-            WHERE s.user_id = $1
-        "#
-        .to_string();
-
-        let mut param_count = 1;
-        let mut conditions = Vec::new();
+        filter: &SessionFilter<'_>,
+        sort: SessionSortField,
+        order: SortOrder,
+        page: &PageRequest,
+    ) -> Result<(Vec<Session>, u64, Option<Cursor>), SqlxError> {
+        let mut conditions = String::from(" WHERE 1=1");
+        let mut param_count = 0;
 
-        if tags.is_some() {
+        if filter.microscope_id.is_some() {
             param_count += 1;
-            conditions.push(format!(" AND i.metadata::text ILIKE ${}", param_count));
+            conditions.push_str(&format!(" AND s.microscope_id = ${}", param_count));
         }
-
-        if date_from.is_some() {
+        if filter.user_id.is_some() {
             param_count += 1;
-            conditions.push(format!(" AND i.captured_at >= ${}", param_count));
+            conditions.push_str(&format!(
+                " AND (s.user_id = ${0} OR EXISTS (SELECT 1 FROM session_participants p WHERE p.session_id = s.id AND p.user_id = ${0}))",
+                param_count
+            ));
         }
-
-        if date_to.is_some() {
+        if filter.status.is_some() {
             param_count += 1;
-            conditions.push(format!(" AND i.captured_at <= ${}", param_count));
+            conditions.push_str(&format!(" AND s.status = ${}", param_count));
         }
-
-        for condition in conditions {
-            query.push_str(&condition);
+        if filter.active_only {
+            conditions.push_str(" AND s.status = 'Active'");
         }
-
-        query.push_str(" ORDER BY i.captured_at DESC");
-        param_count += 1;
-        query.push_str(&format!(" LIMIT ${}", param_count));
-        param_count += 1;
-        query.push_str(&format!(" OFFSET ${}", param_count));
-
-        let mut sql_query = sqlx::query(&query);
-        sql_query = sql_query.bind(user_id);
-
-        if let Some(tag_filter) = tags {
-            sql_query = sql_query.bind(format!("%{}%", tag_filter));
+        if filter.booking_id.is_some() {
+            param_count += 1;
+            conditions.push_str(&format!(" AND s.booking_id = ${}", param_count));
         }
-
-        if let Some(from_date) = date_from {
-            let from_datetime = from_date
-                .and_hms_opt(0, 0, 0)
-                .unwrap()
-                .and_local_timezone(Utc)
-                .unwrap();
-            sql_query = sql_query.bind(
-                time::OffsetDateTime::from_unix_timestamp(from_datetime.timestamp()).unwrap(),
-            );
+        if filter.group_name.is_some() {
+            param_count += 1;
+            conditions.push_str(&format!(
+                " AND EXISTS (SELECT 1 FROM bookings b WHERE b.id = s.booking_id AND b.group_name = ${})",
+                param_count
+            ));
         }
-
-        if let Some(to_date) = date_to {
-            let to_datetime = to_date
-                .and_hms_opt(23, 59, 59)
-                .unwrap()
-                .and_local_timezone(Utc)
-                .unwrap();
-            sql_query = sql_query
-                .bind(time::OffsetDateTime::from_unix_timestamp(to_datetime.timestamp()).unwrap());
+        if filter.started_from.is_some() {
+            param_count += 1;
+            conditions.push_str(&format!(" AND s.started_at >= ${}", param_count));
+        }
+        if filter.started_to.is_some() {
+            param_count += 1;
+            conditions.push_str(&format!(" AND s.started_at < ${}", param_count));
         }
 
-        sql_query = sql_query.bind(limit as i64).bind(offset as i64);
+        let count_query = format!("SELECT COUNT(*) FROM sessions s{}", conditions);
+        let total: i64 = bind_session_filter(sqlx::query(&count_query), filter)
+            .fetch_one(&self.pool)
+            .await?
+            .get(0);
+
+        let sort_column = match sort {
+            SessionSortField::StartedAt => "s.started_at",
+            SessionSortField::EndedAt => "s.ended_at",
+            SessionSortField::MicroscopeId => "s.microscope_id",
+            SessionSortField::Status => "s.status",
+        };
+        let direction = match order {
+            SortOrder::Asc => "ASC NULLS LAST",
+            SortOrder::Desc => "DESC NULLS LAST",
+        };
 
-        let rows = sql_query.fetch_all(&self.pool).await?;
+        let order = if page.by_cursor {
+            "s.created_at, s.id".to_string()
+        } else {
+            format!("{} {}, s.id", sort_column, direction)
+        };
+        conditions.push_str(&cursor_condition("s.", page, &mut param_count));
 
-        let images = rows
+        let query = format!(
+            r#"
+            SELECT s.id, s.user_id, s.booking_id, s.microscope_id,
+                   s.status, s.started_at, s.ended_at, s.notes, s.ended_by, s.end_reason, s.last_seen_at,
+                   {}
+            FROM sessions s{}
+            ORDER BY {}
+            LIMIT ${} OFFSET ${}
+            "#,
+            cursor_column("s."),
+            conditions,
+            order,
+            param_count + 1,
+            param_count + 2
+        );
+
+        let mut rows = bind_cursor(bind_session_filter(sqlx::query(&query), filter), page)
+            .bind(page_fetch_limit(page))
+            .bind(page.offset as i64)
+            .fetch_all(&self.pool)
+            .await?;
+        let next_cursor = split_cursor_page(&mut rows, page);
+
+        let sessions = rows
             .into_iter()
             .map(|row| {
-                let metadata: ImageMetadata =
-                    serde_json::from_value(row.get("metadata")).unwrap_or_default();
-                Image {
+                let session_status = match row.get::<&str, _>("status") {
+                    "Active" => SessionStatus::Active,
+                    "Completed" => SessionStatus::Completed,
+                    "Aborted" => SessionStatus::Aborted,
+                    _ => SessionStatus::Active,
+                };
+
+                Session {
                     id: row.get("id"),
-                    session_id: row.get("session_id"),
-                    filename: row.get("filename"),
-                    file_path: row.get("file_path"),
-                    content_type: row.get("content_type"),
-                    file_size: row.get("file_size"),
-                    width: row.get("width"),
-                    height: row.get("height"),
-                    metadata,
-                    captured_at: DateTime::from_timestamp(
-                        row.get::<time::OffsetDateTime, _>("captured_at")
+                    user_id: row.get("user_id"),
+                    booking_id: row.get("booking_id"),
+                    microscope_id: row.get("microscope_id"),
+                    status: session_status,
+                    started_at: DateTime::from_timestamp(
+                        row.get::<time::OffsetDateTime, _>("started_at")
                             .unix_timestamp(),
                         0,
                     )
                     .unwrap()
                     .with_timezone(&Utc),
-                }
+                    ended_at: row
+                        .get::<Option<time::OffsetDateTime>, _>("ended_at")
+                        .map(|dt| {
+                            DateTime::from_timestamp(dt.unix_timestamp(), 0)
+                                .unwrap()
+                                .with_timezone(&Utc)
+                        }),
+                    notes: row.get("notes"),
+                    ended_by: row.get("ended_by"),
+                    end_reason: row.get("end_reason"),
+                    last_seen_at: row
+                        .get::<Option<time::OffsetDateTime>, _>("last_seen_at")
+                        .map(|dt| {
+                            DateTime::from_timestamp(dt.unix_timestamp(), 0)
+                                .unwrap()
+                                .with_timezone(&Utc)
+                        }),
+                    present: false,
+                }
+            })
+            .collect();
+
+        Ok((sessions, total as u64, next_cursor))
+    }
+
+    pub async fn get_session_by_id(&self, session_id: Uuid) -> Result<Option<Session>, SqlxError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT id, user_id, booking_id, microscope_id,
+                   status, started_at, ended_at, notes, ended_by, end_reason, last_seen_at
+            FROM sessions 
+            WHERE id = $1
+            "#,
+            session_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| {
+            let session_status = match row.status.as_str() {
+                "Active" => SessionStatus::Active,
+                "Completed" => SessionStatus::Completed,
+                "Aborted" => SessionStatus::Aborted,
+                _ => SessionStatus::Active,
+            };
+
+            Session {
+                id: row.id,
+                user_id: row.user_id,
+                booking_id: row.booking_id,
+                microscope_id: row.microscope_id,
+                status: session_status,
+                started_at: DateTime::from_timestamp(row.started_at.unix_timestamp(), 0)
+                    .unwrap()
+                    .with_timezone(&Utc),
+                ended_at: row.ended_at.map(|dt| {
+                    DateTime::from_timestamp(dt.unix_timestamp(), 0)
+                        .unwrap()
+                        .with_timezone(&Utc)
+                }),
+                notes: row.notes,
+                ended_by: row.ended_by,
+                end_reason: row.end_reason,
+                last_seen_at: row.last_seen_at.map(|dt| {
+                    DateTime::from_timestamp(dt.unix_timestamp(), 0)
+                        .unwrap()
+                        .with_timezone(&Utc)
+                }),
+                present: false,
+            }
+        }))
+    }
+
+    /// Whether the user owns the session or shares it as a participant (incl. previous owners)
+    pub async fn is_session_participant(
+        &self,
+        session_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<bool, SqlxError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT (
+                EXISTS (SELECT 1 FROM sessions WHERE id = $1 AND user_id = $2)
+                OR EXISTS (SELECT 1 FROM session_participants WHERE session_id = $1 AND user_id = $2)
+            ) as "participant!"
+            "#,
+            session_id,
+            user_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(row.participant)
+    }
+
+    /// Whether the user may take over or join the session: they requested the session's
+    /// booking, or they have booked under the group name of that booking or of one of the
+    /// session owner's bookings
+    pub async fn is_session_group_member(
+        &self,
+        session_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<bool, SqlxError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT EXISTS (
+                SELECT 1
+                FROM sessions s
+                LEFT JOIN bookings sb ON sb.id = s.booking_id
+                WHERE s.id = $1
+                  AND (
+                      sb.requester_id = $2
+                      OR EXISTS (
+                          SELECT 1 FROM bookings tb
+                          WHERE tb.requester_id = $2
+                            AND tb.group_name IS NOT NULL
+                            AND (
+                                LOWER(tb.group_name) = LOWER(sb.group_name)
+                                OR LOWER(tb.group_name) IN (
+                                    SELECT LOWER(ob.group_name) FROM bookings ob
+                                    WHERE ob.requester_id = s.user_id AND ob.group_name IS NOT NULL
+                                )
+                            )
+                      )
+                  )
+            ) as "member!"
+            "#,
+            session_id,
+            user_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(row.member)
+    }
+
+    pub async fn list_session_participants(
+        &self,
+        session_id: Uuid,
+    ) -> Result<Vec<SessionParticipant>, SqlxError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT sp.session_id, sp.user_id, u.name as user_name, sp.added_by, sp.added_at
+            FROM session_participants sp
+            INNER JOIN users u ON u.id = sp.user_id
+            WHERE sp.session_id = $1
+            ORDER BY sp.added_at
+            "#,
+            session_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let participants = rows
+            .into_iter()
+            .map(|row| SessionParticipant {
+                session_id: row.session_id,
+                user_id: row.user_id,
+                user_name: row.user_name,
+                added_by: row.added_by,
+                added_at: DateTime::from_timestamp(row.added_at.unix_timestamp(), 0)
+                    .unwrap()
+                    .with_timezone(&Utc),
+            })
+            .collect();
+
+        Ok(participants)
+    }
+
+    /// Make `to_user_id` the owner of an active session owned by `from_user_id`.
+    ///
+    /// The previous owner is kept as a participant so they retain access to the images.
+    /// Returns `Ok(None)` if the session is no longer active or changed owner meanwhile.
+    pub async fn transfer_session(
+        &self,
+        session_id: Uuid,
+        from_user_id: Uuid,
+        to_user_id: Uuid,
+        performed_by: Uuid,
+        note: Option<&str>,
+    ) -> Result<Option<SessionHandover>, SqlxError> {
+        let mut tx = self.pool.begin().await?;
+
+        let transferred = sqlx::query!(
+            r#"
+            UPDATE sessions
+            SET user_id = $3, last_seen_at = NOW()
+            WHERE id = $1 AND user_id = $2 AND status = 'Active'
+            "#,
+            session_id,
+            from_user_id,
+            to_user_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        if transferred.rows_affected() == 0 {
+            return Ok(None);
+        }
+
+        sqlx::query!(
+            r#"
+            INSERT INTO session_participants (session_id, user_id, added_by)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (session_id, user_id) DO NOTHING
+            "#,
+            session_id,
+            from_user_id,
+            performed_by
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        // The new owner is tracked on the session itself
+        sqlx::query!(
+            "DELETE FROM session_participants WHERE session_id = $1 AND user_id = $2",
+            session_id,
+            to_user_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let handover = insert_session_handover(
+            &mut *tx,
+            session_id,
+            HandoverKind::Transfer,
+            Some(from_user_id),
+            to_user_id,
+            performed_by,
+            note,
+        )
+        .await?;
+
+        tx.commit().await?;
+        Ok(Some(handover))
+    }
+
+    /// Add a co-participant to a session. Returns `Ok(None)` if they already are one.
+    pub async fn add_session_participant(
+        &self,
+        session_id: Uuid,
+        user_id: Uuid,
+        performed_by: Uuid,
+        note: Option<&str>,
+    ) -> Result<Option<SessionHandover>, SqlxError> {
+        let mut tx = self.pool.begin().await?;
+
+        let added = sqlx::query!(
+            r#"
+            INSERT INTO session_participants (session_id, user_id, added_by)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (session_id, user_id) DO NOTHING
+            "#,
+            session_id,
+            user_id,
+            performed_by
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        if added.rows_affected() == 0 {
+            return Ok(None);
+        }
+
+        let handover = insert_session_handover(
+            &mut *tx,
+            session_id,
+            HandoverKind::AddParticipant,
+            None,
+            user_id,
+            performed_by,
+            note,
+        )
+        .await?;
+
+        tx.commit().await?;
+        Ok(Some(handover))
+    }
+
+    pub async fn get_session_handovers(
+        &self,
+        session_id: Uuid,
+    ) -> Result<Vec<SessionHandover>, SqlxError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT id, session_id, kind, from_user_id, to_user_id, performed_by, note, created_at
+            FROM session_handovers
+            WHERE session_id = $1
+            ORDER BY created_at
+            "#,
+            session_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let handovers = rows
+            .into_iter()
+            .filter_map(|row| {
+                Some(SessionHandover {
+                    id: row.id,
+                    session_id: row.session_id,
+                    kind: parse_handover_kind(&row.kind)?,
+                    from_user_id: row.from_user_id,
+                    to_user_id: row.to_user_id,
+                    performed_by: row.performed_by,
+                    note: row.note,
+                    created_at: DateTime::from_timestamp(row.created_at.unix_timestamp(), 0)
+                        .unwrap()
+                        .with_timezone(&Utc),
+                })
+            })
+            .collect();
+
+        Ok(handovers)
+    }
+
+    pub async fn create_session_note(&self, note: &SessionNote) -> Result<SessionNote, SqlxError> {
+        let measurement = note
+            .measurement
+            .as_ref()
+            .map(|m| serde_json::to_value(m).unwrap_or_default());
+
+        sqlx::query!(
+            r#"
+            INSERT INTO session_notes (id, session_id, author_id, kind, body, image_id, measurement)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            "#,
+            note.id,
+            note.session_id,
+            note.author_id,
+            session_note_kind_str(note.kind),
+            note.body,
+            note.image_id,
+            measurement
+        )
+        .execute(&self.pool)
+        .await?;
+
+        self.get_session_note(note.id)
+            .await?
+            .ok_or(SqlxError::RowNotFound)
+    }
+
+    /// Replace the editable fields of a notebook entry
+    pub async fn update_session_note(
+        &self,
+        note_id: Uuid,
+        body: Option<&str>,
+        image_id: Option<Uuid>,
+        measurement: Option<&Measurement>,
+    ) -> Result<Option<SessionNote>, SqlxError> {
+        let measurement = measurement.map(|m| serde_json::to_value(m).unwrap_or_default());
+
+        let result = sqlx::query!(
+            r#"
+            UPDATE session_notes
+            SET body = COALESCE($2, body),
+                image_id = COALESCE($3, image_id),
+                measurement = COALESCE($4, measurement)
+            WHERE id = $1
+            "#,
+            note_id,
+            body,
+            image_id,
+            measurement
+        )
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(None);
+        }
+        self.get_session_note(note_id).await
+    }
+
+    pub async fn get_session_note(&self, note_id: Uuid) -> Result<Option<SessionNote>, SqlxError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT id, session_id, author_id, kind, body, image_id, measurement,
+                   created_at, updated_at
+            FROM session_notes
+            WHERE id = $1
+            "#,
+            note_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.and_then(|row| {
+            Some(SessionNote {
+                id: row.id,
+                session_id: row.session_id,
+                author_id: row.author_id,
+                kind: parse_session_note_kind(&row.kind)?,
+                body_html: markdown::render(&row.body),
+                body: row.body,
+                image_id: row.image_id,
+                measurement: row.measurement.and_then(|v| serde_json::from_value(v).ok()),
+                created_at: DateTime::from_timestamp(row.created_at.unix_timestamp(), 0)
+                    .unwrap()
+                    .with_timezone(&Utc),
+                updated_at: DateTime::from_timestamp(row.updated_at.unix_timestamp(), 0)
+                    .unwrap()
+                    .with_timezone(&Utc),
+            })
+        }))
+    }
+
+    /// Notebook entries of a session, oldest first
+    pub async fn list_session_notes(
+        &self,
+        session_id: Uuid,
+    ) -> Result<Vec<SessionNote>, SqlxError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT id, session_id, author_id, kind, body, image_id, measurement,
+                   created_at, updated_at
+            FROM session_notes
+            WHERE session_id = $1
+            ORDER BY created_at, id
+            "#,
+            session_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let notes = rows
+            .into_iter()
+            .filter_map(|row| {
+                Some(SessionNote {
+                    id: row.id,
+                    session_id: row.session_id,
+                    author_id: row.author_id,
+                    kind: parse_session_note_kind(&row.kind)?,
+                    body_html: markdown::render(&row.body),
+                    body: row.body,
+                    image_id: row.image_id,
+                    measurement: row.measurement.and_then(|v| serde_json::from_value(v).ok()),
+                    created_at: DateTime::from_timestamp(row.created_at.unix_timestamp(), 0)
+                        .unwrap()
+                        .with_timezone(&Utc),
+                    updated_at: DateTime::from_timestamp(row.updated_at.unix_timestamp(), 0)
+                        .unwrap()
+                        .with_timezone(&Utc),
+                })
+            })
+            .collect();
+
+        Ok(notes)
+    }
+
+    pub async fn record_session_event(
+        &self,
+        session_id: Uuid,
+        event_type: SessionEventType,
+        actor_id: Option<Uuid>,
+        details: serde_json::Value,
+    ) -> Result<(), SqlxError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO session_events (session_id, event_type, actor_id, details)
+            VALUES ($1, $2, $3, $4)
+            "#,
+            session_id,
+            session_event_type_str(event_type),
+            actor_id,
+            details
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Record an event on the microscope's active session. Returns false if there is none.
+    pub async fn record_microscope_session_event(
+        &self,
+        microscope_id: &str,
+        event_type: SessionEventType,
+        actor_id: Option<Uuid>,
+        details: serde_json::Value,
+    ) -> Result<bool, SqlxError> {
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO session_events (session_id, event_type, actor_id, details)
+            SELECT id, $2, $3, $4
+            FROM sessions
+            WHERE microscope_id = $1 AND status = 'Active'
+            "#,
+            microscope_id,
+            session_event_type_str(event_type),
+            actor_id,
+            details
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Timeline of a session in chronological order, optionally limited to some event types
+    pub async fn get_session_events(
+        &self,
+        session_id: Uuid,
+        event_types: &[SessionEventType],
+        limit: u64,
+        offset: u64,
+    ) -> Result<Vec<SessionEvent>, SqlxError> {
+        let event_types: Vec<String> = event_types
+            .iter()
+            .map(|t| session_event_type_str(*t).to_string())
+            .collect();
+
+        let rows = sqlx::query!(
+            r#"
+            SELECT id, session_id, event_type, actor_id, details, created_at
+            FROM session_events
+            WHERE session_id = $1
+              AND (cardinality($2::VARCHAR[]) = 0 OR event_type = ANY($2))
+            ORDER BY created_at, id
+            LIMIT $3 OFFSET $4
+            "#,
+            session_id,
+            &event_types,
+            limit as i64,
+            offset as i64
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let events = rows
+            .into_iter()
+            .filter_map(|row| {
+                Some(SessionEvent {
+                    id: row.id,
+                    session_id: row.session_id,
+                    event_type: parse_session_event_type(&row.event_type)?,
+                    actor_id: row.actor_id,
+                    details: row.details,
+                    created_at: DateTime::from_timestamp(row.created_at.unix_timestamp(), 0)
+                        .unwrap()
+                        .with_timezone(&Utc),
+                })
+            })
+            .collect();
+
+        Ok(events)
+    }
+
+    pub async fn get_booking_by_id(&self, booking_id: Uuid) -> Result<Option<Booking>, SqlxError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT id, microscope_id, date, slot_start, slot_end, title,
+                   group_name, attendees, requester_id, requester_name,
+                   status, approved_by, required_capabilities, created_at
+            FROM bookings 
+            WHERE id = $1
+            "#,
+            booking_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| {
+            let status = match row.status.as_str() {
+                "Pending" => BookingStatus::Pending,
+                "Approved" => BookingStatus::Approved,
+                "Rejected" => BookingStatus::Rejected,
+                "Cancelled" => BookingStatus::Cancelled,
+                _ => BookingStatus::Pending,
+            };
+
+            let naive_date = NaiveDate::from_ymd_opt(
+                row.date.year(),
+                row.date.month() as u32,
+                row.date.day() as u32,
+            )
+            .unwrap();
+
+            Booking {
+                id: row.id,
+                microscope_id: row.microscope_id,
+                date: naive_date,
+                slot_start: row.slot_start,
+                slot_end: row.slot_end,
+                title: row.title,
+                group_name: row.group_name,
+                attendees: row.attendees,
+                requester_id: row.requester_id,
+                requester_name: row.requester_name,
+                status,
+                approved_by: row.approved_by,
+                required_capabilities: row
+                    .required_capabilities
+                    .and_then(|v| serde_json::from_value(v).ok()),
+                created_at: DateTime::from_timestamp(row.created_at.unix_timestamp(), 0)
+                    .unwrap()
+                    .fixed_offset(),
+            }
+        }))
+    }
+
+    /// Insert an image and count it as a reference to its stored file
+    pub async fn create_image(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        image: &Image,
+    ) -> Result<Image, SqlxError> {
+        let metadata_json = serde_json::to_value(&image.metadata).unwrap();
+
+        let created_image = sqlx::query!(
+            r#"
+            INSERT INTO images (
+                id, session_id, filename, file_path, content_type, file_size,
+                width, height, metadata, captured_at, sha256, starred
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+            RETURNING id, session_id, filename, file_path, content_type, file_size,
+                     width, height, metadata, captured_at, sha256, starred
+            "#,
+            image.id,
+            image.session_id,
+            image.filename,
+            image.file_path,
+            image.content_type,
+            image.file_size,
+            image.width,
+            image.height,
+            metadata_json,
+            time::OffsetDateTime::from_unix_timestamp(image.captured_at.timestamp()).unwrap(),
+            image.sha256,
+            image.starred
+        )
+        .fetch_one(&mut **tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO stored_files (file_path, ref_count)
+            VALUES ($1, 1)
+            ON CONFLICT (file_path) DO UPDATE SET ref_count = stored_files.ref_count + 1
+            "#,
+            image.file_path
+        )
+        .execute(&mut **tx)
+        .await?;
+
+        let metadata: ImageMetadata = serde_json::from_value(created_image.metadata).unwrap();
+
+        Ok(Image {
+            id: created_image.id,
+            session_id: created_image.session_id,
+            filename: created_image.filename,
+            file_path: created_image.file_path,
+            content_type: created_image.content_type,
+            file_size: created_image.file_size,
+            width: created_image.width,
+            height: created_image.height,
+            metadata,
+            captured_at: DateTime::from_timestamp(created_image.captured_at.unix_timestamp(), 0)
+                .unwrap()
+                .with_timezone(&Utc),
+            sha256: created_image.sha256,
+            starred: created_image.starred,
+        })
+    }
+
+    /// Whether any image uses the stored file at `file_path`
+    pub async fn is_file_referenced(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        file_path: &str,
+    ) -> Result<bool, SqlxError> {
+        let referenced = sqlx::query_scalar!(
+            r#"SELECT EXISTS(SELECT 1 FROM stored_files WHERE file_path = $1) AS "referenced!""#,
+            file_path
+        )
+        .fetch_one(&mut **tx)
+        .await?;
+
+        Ok(referenced)
+    }
+
+    /// Lock the stored file at `file_path` until the transaction ends. Held while checking
+    /// whether a file is still referenced and deleting or rewriting it, so content-addressed
+    /// uploads cannot start using a file that is being deleted. Works whether or not the
+    /// file has a `stored_files` row yet.
+    pub async fn lock_stored_file(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        file_path: &str,
+    ) -> Result<(), SqlxError> {
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtextextended($1, 0))")
+            .bind(file_path)
+            .execute(&mut **tx)
+            .await?;
+        Ok(())
+    }
+
+    /// One page of image files to verify, ordered by image ID and starting after `after`
+    pub async fn list_image_files(
+        &self,
+        after: Option<Uuid>,
+        limit: i64,
+    ) -> Result<Vec<ImageFileRecord>, SqlxError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT id, session_id, file_path, sha256
+            FROM images
+            WHERE $1::uuid IS NULL OR id > $1
+            ORDER BY id
+            LIMIT $2
+            "#,
+            after,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| ImageFileRecord {
+                image_id: row.id,
+                session_id: row.session_id,
+                file_path: row.file_path,
+                sha256: row.sha256,
+            })
+            .collect())
+    }
+
+    /// Images that retention policies apply to, ordered by ID. `term_end` is the end of the
+    /// latest academic term containing the capture date in the lab's timezone.
+    pub async fn list_retention_candidates(
+        &self,
+        after: Option<Uuid>,
+        limit: i64,
+        timezone: &str,
+    ) -> Result<Vec<RetentionCandidate>, SqlxError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT i.id, i.session_id, i.filename, i.file_size, i.captured_at, i.starred,
+                   s.exported_at IS NOT NULL AS "exported!", u.role AS owner_role,
+                   s.microscope_id,
+                   (
+                       SELECT t.end_date FROM academic_terms t
+                       WHERE (i.captured_at AT TIME ZONE $3)::date
+                             BETWEEN t.start_date AND t.end_date
+                       ORDER BY t.end_date DESC
+                       LIMIT 1
+                   ) AS term_end
+            FROM images i
+            JOIN sessions s ON s.id = i.session_id
+            JOIN users u ON u.id = s.user_id
+            WHERE i.deleted_at IS NULL AND ($1::uuid IS NULL OR i.id > $1)
+            ORDER BY i.id
+            LIMIT $2
+            "#,
+            after,
+            limit,
+            timezone
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| RetentionCandidate {
+                image_id: row.id,
+                session_id: row.session_id,
+                filename: row.filename,
+                file_size: row.file_size,
+                captured_at: DateTime::from_timestamp(row.captured_at.unix_timestamp(), 0)
+                    .unwrap()
+                    .with_timezone(&Utc),
+                starred: row.starred,
+                exported: row.exported,
+                owner_role: match row.owner_role.as_str() {
+                    "Admin" => UserRole::Admin,
+                    "Teacher" => UserRole::Teacher,
+                    _ => UserRole::Student,
+                },
+                microscope_id: row.microscope_id,
+                term_end: row.term_end.map(|date| {
+                    NaiveDate::from_ymd_opt(date.year(), date.month() as u32, date.day() as u32)
+                        .unwrap()
+                }),
+            })
+            .collect())
+    }
+
+    /// Star or unstar an image. Returns false if it does not exist or is trashed.
+    pub async fn set_image_starred(
+        &self,
+        image_id: Uuid,
+        starred: bool,
+    ) -> Result<bool, SqlxError> {
+        let result = sqlx::query!(
+            "UPDATE images SET starred = $2 WHERE id = $1 AND deleted_at IS NULL",
+            image_id,
+            starred
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Record that a session's images were exported
+    pub async fn mark_session_exported(&self, session_id: Uuid) -> Result<(), SqlxError> {
+        sqlx::query!(
+            "UPDATE sessions SET exported_at = NOW() WHERE id = $1",
+            session_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Record the checksum of an image stored before checksums were kept
+    pub async fn set_image_sha256(&self, image_id: Uuid, sha256: &str) -> Result<(), SqlxError> {
+        sqlx::query!(
+            "UPDATE images SET sha256 = $2 WHERE id = $1 AND sha256 IS NULL",
+            image_id,
+            sha256
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Move an image to the trash. `deleted_by` is `None` when a retention policy deleted it.
+    /// Returns false if it does not exist or is already trashed.
+    pub async fn trash_image(
+        &self,
+        image_id: Uuid,
+        deleted_by: Option<Uuid>,
+    ) -> Result<bool, SqlxError> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE images SET deleted_at = NOW(), deleted_by = $2
+            WHERE id = $1 AND deleted_at IS NULL
+            "#,
+            image_id,
+            deleted_by
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Take an image out of the trash. Returns false if it is not in the trash.
+    pub async fn restore_image(&self, image_id: Uuid) -> Result<bool, SqlxError> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE images SET deleted_at = NULL, deleted_by = NULL
+            WHERE id = $1 AND deleted_at IS NOT NULL
+            "#,
+            image_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// An image in the trash; `retention` is how long trashed images are kept
+    pub async fn get_trashed_image(
+        &self,
+        image_id: Uuid,
+        retention: chrono::Duration,
+    ) -> Result<Option<TrashedImage>, SqlxError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT id, session_id, filename, file_path, content_type, file_size,
+                   width, height, metadata, captured_at, sha256, starred,
+                   deleted_at AS "deleted_at!", deleted_by
+            FROM images
+            WHERE id = $1 AND deleted_at IS NOT NULL
+            "#,
+            image_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| {
+            let metadata: ImageMetadata = serde_json::from_value(row.metadata).unwrap_or_default();
+            let deleted_at = DateTime::from_timestamp(row.deleted_at.unix_timestamp(), 0)
+                .unwrap()
+                .with_timezone(&Utc);
+            TrashedImage {
+                image: Image {
+                    id: row.id,
+                    session_id: row.session_id,
+                    filename: row.filename,
+                    file_path: row.file_path,
+                    content_type: row.content_type,
+                    file_size: row.file_size,
+                    width: row.width,
+                    height: row.height,
+                    metadata,
+                    captured_at: DateTime::from_timestamp(row.captured_at.unix_timestamp(), 0)
+                        .unwrap()
+                        .with_timezone(&Utc),
+                    sha256: row.sha256,
+                    starred: row.starred,
+                },
+                deleted_at,
+                deleted_by: row.deleted_by,
+                purge_after: deleted_at + retention,
+            }
+        }))
+    }
+
+    /// One page of the trash, most recently deleted first. With `owner_id`, only images from
+    /// sessions that user owns.
+    pub async fn list_trashed_images(
+        &self,
+        owner_id: Option<Uuid>,
+        retention: chrono::Duration,
+        limit: u64,
+        offset: u64,
+    ) -> Result<(Vec<TrashedImage>, u64), SqlxError> {
+        let total = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) AS "count!"
+            FROM images i
+            INNER JOIN sessions s ON i.session_id = s.id
+            WHERE i.deleted_at IS NOT NULL AND ($1::uuid IS NULL OR s.user_id = $1)
+            "#,
+            owner_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        let rows = sqlx::query!(
+            r#"
+            SELECT i.id, i.session_id, i.filename, i.file_path, i.content_type, i.file_size,
+                   i.width, i.height, i.metadata, i.captured_at, i.sha256, i.starred,
+                   i.deleted_at AS "deleted_at!", i.deleted_by
+            FROM images i
+            INNER JOIN sessions s ON i.session_id = s.id
+            WHERE i.deleted_at IS NOT NULL AND ($1::uuid IS NULL OR s.user_id = $1)
+            ORDER BY i.deleted_at DESC, i.id
+            LIMIT $2 OFFSET $3
+            "#,
+            owner_id,
+            limit as i64,
+            offset as i64
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let images = rows
+            .into_iter()
+            .map(|row| {
+                let metadata: ImageMetadata =
+                    serde_json::from_value(row.metadata).unwrap_or_default();
+                let deleted_at = DateTime::from_timestamp(row.deleted_at.unix_timestamp(), 0)
+                    .unwrap()
+                    .with_timezone(&Utc);
+                TrashedImage {
+                    image: Image {
+                        id: row.id,
+                        session_id: row.session_id,
+                        filename: row.filename,
+                        file_path: row.file_path,
+                        content_type: row.content_type,
+                        file_size: row.file_size,
+                        width: row.width,
+                        height: row.height,
+                        metadata,
+                        captured_at: DateTime::from_timestamp(row.captured_at.unix_timestamp(), 0)
+                            .unwrap()
+                            .with_timezone(&Utc),
+                        sha256: row.sha256,
+                        starred: row.starred,
+                    },
+                    deleted_at,
+                    deleted_by: row.deleted_by,
+                    purge_after: deleted_at + retention,
+                }
+            })
+            .collect();
+
+        Ok((images, total as u64))
+    }
+
+    /// IDs of trashed images deleted before `deleted_before`, oldest first
+    pub async fn list_expired_trash(
+        &self,
+        deleted_before: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<Uuid>, SqlxError> {
+        sqlx::query_scalar!(
+            r#"
+            SELECT id FROM images
+            WHERE deleted_at < $1
+            ORDER BY deleted_at
+            LIMIT $2
+            "#,
+            time::OffsetDateTime::from_unix_timestamp(deleted_before.timestamp()).unwrap(),
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Permanently delete a trashed image and release its reference to the stored file, with
+    /// the file locked as by [`Self::lock_stored_file`]. Returns the file path, or `None` if
+    /// the image is not in the trash.
+    pub async fn purge_image(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        image_id: Uuid,
+    ) -> Result<Option<String>, SqlxError> {
+        let Some(file_path) = sqlx::query_scalar!(
+            "DELETE FROM images WHERE id = $1 AND deleted_at IS NOT NULL RETURNING file_path",
+            image_id
+        )
+        .fetch_optional(&mut **tx)
+        .await?
+        else {
+            return Ok(None);
+        };
+
+        self.lock_stored_file(tx, &file_path).await?;
+
+        // Drop the last reference outright, since ref_count must stay positive
+        let released = sqlx::query!(
+            "DELETE FROM stored_files WHERE file_path = $1 AND ref_count <= 1",
+            file_path
+        )
+        .execute(&mut **tx)
+        .await?;
+        if released.rows_affected() == 0 {
+            sqlx::query!(
+                "UPDATE stored_files SET ref_count = ref_count - 1 WHERE file_path = $1",
+                file_path
+            )
+            .execute(&mut **tx)
+            .await?;
+        }
+
+        Ok(Some(file_path))
+    }
+
+    /// Recount `stored_files` from the images table, dropping entries no image uses.
+    /// Returns the number of entries corrected.
+    pub async fn reconcile_file_references(&self) -> Result<u64, SqlxError> {
+        let mut tx = self.pool.begin().await?;
+
+        // Hold off concurrent uploads and purges, which would otherwise race with the recount
+        sqlx::query!("LOCK TABLE stored_files IN EXCLUSIVE MODE")
+            .execute(&mut *tx)
+            .await?;
+
+        let removed = sqlx::query!(
+            r#"
+            DELETE FROM stored_files sf
+            WHERE NOT EXISTS (SELECT 1 FROM images i WHERE i.file_path = sf.file_path)
+            "#
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let recounted = sqlx::query!(
+            r#"
+            INSERT INTO stored_files (file_path, ref_count)
+            SELECT file_path, COUNT(*) FROM images GROUP BY file_path
+            ON CONFLICT (file_path) DO UPDATE SET ref_count = EXCLUDED.ref_count
+            WHERE stored_files.ref_count <> EXCLUDED.ref_count
+            "#
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(removed.rows_affected() + recounted.rows_affected())
+    }
+
+    /// Space used by one user's session images, or None if the user does not exist.
+    /// `quota_bytes` is only the user's own quota.
+    pub async fn get_user_storage_usage(
+        &self,
+        user_id: Uuid,
+    ) -> Result<Option<UserStorageUsage>, SqlxError> {
+        user_storage_usage(&self.pool, user_id).await
+    }
+
+    /// Lock the row of the session's owner until the transaction ends and return the space
+    /// their images use, or None if the session does not exist. Taken before adding an image
+    /// so concurrent uploads for the same user are checked against their quota one at a time.
+    pub async fn lock_session_owner_storage_usage(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        session_id: Uuid,
+    ) -> Result<Option<UserStorageUsage>, SqlxError> {
+        let owner = sqlx::query_scalar!(
+            r#"
+            SELECT u.id
+            FROM users u
+            JOIN sessions s ON s.user_id = u.id
+            WHERE s.id = $1
+            FOR UPDATE OF u
+            "#,
+            session_id
+        )
+        .fetch_optional(&mut **tx)
+        .await?;
+
+        match owner {
+            Some(user_id) => user_storage_usage(&mut **tx, user_id).await,
+            None => Ok(None),
+        }
+    }
+
+    /// Session owners using the most space. `quota_bytes` is only the user's own quota.
+    pub async fn get_storage_usage_by_user(
+        &self,
+        limit: i64,
+    ) -> Result<Vec<UserStorageUsage>, SqlxError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT u.id, u.name, u.storage_quota_bytes,
+                   COUNT(i.id) FILTER (WHERE i.deleted_at IS NULL) AS "images!",
+                   SUM(i.file_size)::BIGINT AS "used_bytes!",
+                   COALESCE(SUM(i.file_size) FILTER (WHERE i.deleted_at IS NOT NULL), 0)::BIGINT
+                       AS "trashed_bytes!"
+            FROM users u
+            JOIN sessions s ON s.user_id = u.id
+            JOIN images i ON i.session_id = s.id
+            GROUP BY u.id
+            ORDER BY 5 DESC, u.name
+            LIMIT $1
+            "#,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| UserStorageUsage {
+                user_id: row.id,
+                name: row.name,
+                images: row.images,
+                used_bytes: row.used_bytes,
+                trashed_bytes: row.trashed_bytes,
+                quota_bytes: row.storage_quota_bytes,
+                custom_quota: row.storage_quota_bytes.is_some(),
+            })
+            .collect())
+    }
+
+    /// Sessions using the most space, counting trashed images
+    pub async fn get_storage_usage_by_session(
+        &self,
+        limit: i64,
+    ) -> Result<Vec<SessionStorageUsage>, SqlxError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT s.id, s.user_id, s.microscope_id,
+                   COUNT(i.id) FILTER (WHERE i.deleted_at IS NULL) AS "images!",
+                   SUM(i.file_size)::BIGINT AS "used_bytes!"
+            FROM sessions s
+            JOIN images i ON i.session_id = s.id
+            GROUP BY s.id
+            ORDER BY 5 DESC, s.id
+            LIMIT $1
+            "#,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| SessionStorageUsage {
+                session_id: row.id,
+                user_id: row.user_id,
+                microscope_id: row.microscope_id,
+                images: row.images,
+                used_bytes: row.used_bytes,
+            })
+            .collect())
+    }
+
+    /// Space used by the images captured on each microscope, counting trashed images
+    pub async fn get_storage_usage_by_microscope(
+        &self,
+    ) -> Result<Vec<MicroscopeStorageUsage>, SqlxError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT s.microscope_id,
+                   COUNT(i.id) FILTER (WHERE i.deleted_at IS NULL) AS "images!",
+                   SUM(i.file_size)::BIGINT AS "used_bytes!"
+            FROM sessions s
+            JOIN images i ON i.session_id = s.id
+            GROUP BY s.microscope_id
+            ORDER BY 3 DESC, s.microscope_id
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| MicroscopeStorageUsage {
+                microscope_id: row.microscope_id,
+                images: row.images,
+                used_bytes: row.used_bytes,
+            })
+            .collect())
+    }
+
+    /// Set or clear (`None`, using the default) a user's storage quota.
+    /// Returns false if the user does not exist.
+    pub async fn set_user_storage_quota(
+        &self,
+        user_id: Uuid,
+        quota_bytes: Option<i64>,
+    ) -> Result<bool, SqlxError> {
+        let result = sqlx::query!(
+            "UPDATE users SET storage_quota_bytes = $2 WHERE id = $1",
+            user_id,
+            quota_bytes
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn get_images_by_session(&self, session_id: Uuid) -> Result<Vec<Image>, SqlxError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT id, session_id, filename, file_path, content_type, file_size,
+                   width, height, metadata, captured_at, sha256, starred
+            FROM images
+            WHERE session_id = $1 AND deleted_at IS NULL
+            ORDER BY captured_at DESC
+            "#,
+            session_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let images = rows
+            .into_iter()
+            .map(|row| {
+                let metadata: ImageMetadata =
+                    serde_json::from_value(row.metadata).unwrap_or_default();
+                Image {
+                    id: row.id,
+                    session_id: row.session_id,
+                    filename: row.filename,
+                    file_path: row.file_path,
+                    content_type: row.content_type,
+                    file_size: row.file_size,
+                    width: row.width,
+                    height: row.height,
+                    metadata,
+                    captured_at: DateTime::from_timestamp(row.captured_at.unix_timestamp(), 0)
+                        .unwrap()
+                        .with_timezone(&Utc),
+                    sha256: row.sha256,
+                    starred: row.starred,
+                }
+            })
+            .collect();
+
+        Ok(images)
+    }
+
+    pub async fn get_latest_image_by_session(
+        &self,
+        session_id: Uuid,
+    ) -> Result<Option<Image>, SqlxError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT id, session_id, filename, file_path, content_type, file_size,
+                   width, height, metadata, captured_at, sha256, starred
+            FROM images
+            WHERE session_id = $1 AND deleted_at IS NULL
+            ORDER BY captured_at DESC
+            LIMIT 1
+            "#,
+            session_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| {
+            let metadata: ImageMetadata = serde_json::from_value(row.metadata).unwrap_or_default();
+            Image {
+                id: row.id,
+                session_id: row.session_id,
+                filename: row.filename,
+                file_path: row.file_path,
+                content_type: row.content_type,
+                file_size: row.file_size,
+                width: row.width,
+                height: row.height,
+                metadata,
+                captured_at: DateTime::from_timestamp(row.captured_at.unix_timestamp(), 0)
+                    .unwrap()
+                    .with_timezone(&Utc),
+                sha256: row.sha256,
+                starred: row.starred,
+            }
+        }))
+    }
+
+    pub async fn get_image_by_id(&self, image_id: Uuid) -> Result<Option<Image>, SqlxError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT id, session_id, filename, file_path, content_type, file_size,
+                   width, height, metadata, captured_at, sha256, starred
+            FROM images
+            WHERE id = $1 AND deleted_at IS NULL
+            "#,
+            image_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| {
+            let metadata: ImageMetadata = serde_json::from_value(row.metadata).unwrap_or_default();
+            Image {
+                id: row.id,
+                session_id: row.session_id,
+                filename: row.filename,
+                file_path: row.file_path,
+                content_type: row.content_type,
+                file_size: row.file_size,
+                width: row.width,
+                height: row.height,
+                metadata,
+                captured_at: DateTime::from_timestamp(row.captured_at.unix_timestamp(), 0)
+                    .unwrap()
+                    .with_timezone(&Utc),
+                sha256: row.sha256,
+                starred: row.starred,
+            }
+        }))
+    }
+
+    /// Search images matching `filter`, returning one page and the total match count
+    pub async fn search_images(
+        &self,
+        filter: &ImageFilter,
+        page: &PageRequest,
+    ) -> Result<(Vec<Image>, u64, Option<Cursor>), SqlxError> {
+        let mut conditions = String::from(" WHERE i.deleted_at IS NULL");
+        let mut param_count = 0;
+
+        if filter.user_id.is_some() {
+            param_count += 1;
+            conditions.push_str(&format!(
+                " AND (s.user_id = ${0} OR EXISTS (SELECT 1 FROM session_participants sp WHERE sp.session_id = s.id AND sp.user_id = ${0}))",
+                param_count
+            ));
+        }
+
+        if filter.session_id.is_some() {
+            param_count += 1;
+            conditions.push_str(&format!(" AND i.session_id = ${}", param_count));
+        }
+
+        if filter.tags.is_some() {
+            param_count += 1;
+            conditions.push_str(&format!(" AND i.metadata::text ILIKE ${}", param_count));
+        }
+
+        if filter.date_from.is_some() {
+            param_count += 1;
+            conditions.push_str(&format!(" AND i.captured_at >= ${}", param_count));
+        }
+
+        if filter.date_to.is_some() {
+            param_count += 1;
+            conditions.push_str(&format!(" AND i.captured_at <= ${}", param_count));
+        }
+
+        let count_query = format!(
+            "SELECT COUNT(*) FROM images i INNER JOIN sessions s ON i.session_id = s.id{}",
+            conditions
+        );
+        let total: i64 = bind_image_filter(sqlx::query(&count_query), filter)
+            .fetch_one(&self.pool)
+            .await?
+            .get(0);
+
+        let order = if page.by_cursor {
+            "i.created_at, i.id"
+        } else {
+            "i.captured_at DESC, i.id"
+        };
+        conditions.push_str(&cursor_condition("i.", page, &mut param_count));
+
+        let query = format!(
+            r#"
+            SELECT i.id, i.session_id, i.filename, i.file_path, i.content_type, i.file_size,
+                   i.width, i.height, i.metadata, i.captured_at, i.sha256, i.starred, {}
+            FROM images i
+            INNER JOIN sessions s ON i.session_id = s.id{}
+            ORDER BY {}
+            LIMIT ${} OFFSET ${}
+            "#,
+            cursor_column("i."),
+            conditions,
+            order,
+            param_count + 1,
+            param_count + 2
+        );
+
+        let mut rows = bind_cursor(bind_image_filter(sqlx::query(&query), filter), page)
+            .bind(page_fetch_limit(page))
+            .bind(page.offset as i64)
+            .fetch_all(&self.pool)
+            .await?;
+        let next_cursor = split_cursor_page(&mut rows, page);
+
+        let images = rows
+            .into_iter()
+            .map(|row| {
+                let metadata: ImageMetadata =
+                    serde_json::from_value(row.get("metadata")).unwrap_or_default();
+                Image {
+                    id: row.get("id"),
+                    session_id: row.get("session_id"),
+                    filename: row.get("filename"),
+                    file_path: row.get("file_path"),
+                    content_type: row.get("content_type"),
+                    file_size: row.get("file_size"),
+                    width: row.get("width"),
+                    height: row.get("height"),
+                    metadata,
+                    captured_at: DateTime::from_timestamp(
+                        row.get::<time::OffsetDateTime, _>("captured_at")
+                            .unix_timestamp(),
+                        0,
+                    )
+                    .unwrap()
+                    .with_timezone(&Utc),
+                    sha256: row.get("sha256"),
+                    starred: row.get("starred"),
+                }
+            })
+            .collect();
+
+        Ok((images, total as u64, next_cursor))
+    }
+
+    pub async fn check_booking_conflicts(
+        &self,
+        microscope_id: &str,
+        date: NaiveDate,
+        slot_start: i32,
+        slot_end: i32,
+        exclude_booking_id: Option<Uuid>,
+    ) -> Result<bool, SqlxError> {
+        // Convert chrono NaiveDate to time Date
+        let time_date = time::Date::from_ordinal_date(date.year(), date.ordinal() as u16).unwrap();
+
+        let count = if let Some(exclude_id) = exclude_booking_id {
+            let result = sqlx::query!(
+                r#"
+                SELECT COUNT(*) as count
+                FROM bookings 
+                WHERE microscope_id = $1 
+                  AND date = $2 
+                  AND status IN ('Pending', 'Approved')
+                  AND id != $5
+                  AND NOT (slot_end <= $3 OR slot_start >= $4)
+                "#,
+                microscope_id,
+                time_date,
+                slot_start,
+                slot_end,
+                exclude_id
+            )
+            .fetch_one(&self.pool)
+            .await?;
+            result.count.unwrap_or(0)
+        } else {
+            let result = sqlx::query!(
+                r#"
+                SELECT COUNT(*) as count
+                FROM bookings 
+                WHERE microscope_id = $1 
+                  AND date = $2 
+                  AND status IN ('Pending', 'Approved')
+                  AND NOT (slot_end <= $3 OR slot_start >= $4)
+                "#,
+                microscope_id,
+                time_date,
+                slot_start,
+                slot_end
+            )
+            .fetch_one(&self.pool)
+            .await?;
+            result.count.unwrap_or(0)
+        };
+
+        Ok(count > 0)
+    }
+
+    pub async fn delete_booking(&self, booking_id: Uuid) -> Result<u64, SqlxError> {
+        let result = sqlx::query!(
+            r#"
+            DELETE
+            FROM bookings
+            WHERE id = $1
+        "#,
+            booking_id
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    pub async fn get_booking_owner(&self, booking_id: Uuid) -> Result<Option<Uuid>, SqlxError> {
+        let result = sqlx::query!(
+            "SELECT requester_id FROM bookings WHERE id = $1",
+            booking_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(result.map(|row| row.requester_id))
+    }
+
+    pub async fn delete_booking_by_owner(
+        &self,
+        booking_id: Uuid,
+        user_id: Option<Uuid>,
+    ) -> Result<u64, SqlxError> {
+        let result = sqlx::query!(
+            r#"
+            DELETE
+            FROM bookings
+            WHERE id = $1
+            AND requester_id = $2
+        "#,
+            booking_id,
+            user_id
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    pub async fn cancel_booking(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        booking_id: Uuid,
+        reason: Option<&str>,
+    ) -> Result<Booking, SqlxError> {
+        let row = sqlx::query!(
+            r#"
+            UPDATE bookings
+            SET status = 'Cancelled', rejection_reason = COALESCE($2, rejection_reason)
+            WHERE id = $1
+            RETURNING id, microscope_id, date, slot_start, slot_end, title,
+                     group_name, attendees, requester_id, requester_name,
+                     status, approved_by, required_capabilities, created_at
+            "#,
+            booking_id,
+            reason
+        )
+        .fetch_one(&mut **tx)
+        .await?;
+
+        let booking_status = match row.status.as_str() {
+            "Pending" => BookingStatus::Pending,
+            "Approved" => BookingStatus::Approved,
+            "Rejected" => BookingStatus::Rejected,
+            "Cancelled" => BookingStatus::Cancelled,
+            _ => BookingStatus::Pending,
+        };
+
+        let naive_date = NaiveDate::from_ymd_opt(
+            row.date.year(),
+            row.date.month() as u32,
+            row.date.day() as u32,
+        )
+        .unwrap();
+
+        Ok(Booking {
+            id: row.id,
+            microscope_id: row.microscope_id,
+            date: naive_date,
+            slot_start: row.slot_start,
+            slot_end: row.slot_end,
+            title: row.title,
+            group_name: row.group_name,
+            attendees: row.attendees,
+            requester_id: row.requester_id,
+            requester_name: row.requester_name,
+            status: booking_status,
+            approved_by: row.approved_by,
+            required_capabilities: row
+                .required_capabilities
+                .and_then(|v| serde_json::from_value(v).ok()),
+            created_at: DateTime::from_timestamp(row.created_at.unix_timestamp(), 0)
+                .unwrap()
+                .fixed_offset(),
+        })
+    }
+
+    pub async fn update_booking_details(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        booking_id: Uuid,
+        title: Option<&str>,
+        group_name: Option<&str>,
+        attendees: Option<i32>,
+    ) -> Result<Booking, SqlxError> {
+        let row = sqlx::query!(
+            r#"
+            UPDATE bookings
+            SET title = COALESCE($2, title),
+                group_name = COALESCE($3, group_name),
+                attendees = COALESCE($4, attendees)
+            WHERE id = $1
+            RETURNING id, microscope_id, date, slot_start, slot_end, title,
+                     group_name, attendees, requester_id, requester_name,
+                     status, approved_by, required_capabilities, created_at
+            "#,
+            booking_id,
+            title,
+            group_name,
+            attendees
+        )
+        .fetch_one(&mut **tx)
+        .await?;
+
+        let booking_status = match row.status.as_str() {
+            "Pending" => BookingStatus::Pending,
+            "Approved" => BookingStatus::Approved,
+            "Rejected" => BookingStatus::Rejected,
+            "Cancelled" => BookingStatus::Cancelled,
+            _ => BookingStatus::Pending,
+        };
+
+        let naive_date = NaiveDate::from_ymd_opt(
+            row.date.year(),
+            row.date.month() as u32,
+            row.date.day() as u32,
+        )
+        .unwrap();
+
+        Ok(Booking {
+            id: row.id,
+            microscope_id: row.microscope_id,
+            date: naive_date,
+            slot_start: row.slot_start,
+            slot_end: row.slot_end,
+            title: row.title,
+            group_name: row.group_name,
+            attendees: row.attendees,
+            requester_id: row.requester_id,
+            requester_name: row.requester_name,
+            status: booking_status,
+            approved_by: row.approved_by,
+            required_capabilities: row
+                .required_capabilities
+                .and_then(|v| serde_json::from_value(v).ok()),
+            created_at: DateTime::from_timestamp(row.created_at.unix_timestamp(), 0)
+                .unwrap()
+                .fixed_offset(),
+        })
+    }
+
+    /// Append an audit entry for a booking change, storing both snapshots and the field diff
+    pub async fn record_booking_event(
+        &self,
+        booking_id: Uuid,
+        event_type: BookingEventType,
+        actor_id: Option<Uuid>,
+        note: Option<&str>,
+        before: Option<&Booking>,
+        after: Option<&Booking>,
+    ) -> Result<BookingEvent, SqlxError> {
+        insert_booking_event(
+            &self.pool, booking_id, event_type, actor_id, note, before, after,
+        )
+        .await
+    }
+
+    /// Same as `record_booking_event`, but as part of an open transaction
+    #[allow(clippy::too_many_arguments)]
+    pub async fn record_booking_event_tx(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        booking_id: Uuid,
+        event_type: BookingEventType,
+        actor_id: Option<Uuid>,
+        note: Option<&str>,
+        before: Option<&Booking>,
+        after: Option<&Booking>,
+    ) -> Result<BookingEvent, SqlxError> {
+        insert_booking_event(
+            &mut **tx, booking_id, event_type, actor_id, note, before, after,
+        )
+        .await
+    }
+
+    pub async fn get_booking_events(
+        &self,
+        booking_id: Uuid,
+    ) -> Result<Vec<BookingEvent>, SqlxError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT id, booking_id, event_type, actor_id, note, before, after, changes, created_at
+            FROM booking_events
+            WHERE booking_id = $1
+            ORDER BY created_at, id
+            "#,
+            booking_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let events = rows
+            .into_iter()
+            .map(|row| BookingEvent {
+                id: row.id,
+                booking_id: row.booking_id,
+                event_type: parse_booking_event_type(&row.event_type)
+                    .unwrap_or(BookingEventType::Updated),
+                actor_id: row.actor_id,
+                note: row.note,
+                before: row.before,
+                after: row.after,
+                changes: row.changes,
+                created_at: DateTime::from_timestamp(row.created_at.unix_timestamp(), 0)
+                    .unwrap()
+                    .with_timezone(&Utc),
+            })
+            .collect();
+
+        Ok(events)
+    }
+
+    pub async fn begin_transaction(&self) -> Result<Transaction<'static, Postgres>, SqlxError> {
+        self.pool.begin().await
+    }
+
+    /// Load bookings and lock their rows until the transaction ends
+    pub async fn lock_bookings(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        booking_ids: &[Uuid],
+    ) -> Result<Vec<Booking>, SqlxError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT id, microscope_id, date, slot_start, slot_end, title,
+                   group_name, attendees, requester_id, requester_name,
+                   status, approved_by, required_capabilities, created_at
+            FROM bookings
+            WHERE id = ANY($1)
+            ORDER BY id
+            FOR UPDATE
+            "#,
+            booking_ids
+        )
+        .fetch_all(&mut **tx)
+        .await?;
+
+        let bookings = rows
+            .into_iter()
+            .map(|row| {
+                let status = match row.status.as_str() {
+                    "Pending" => BookingStatus::Pending,
+                    "Approved" => BookingStatus::Approved,
+                    "Rejected" => BookingStatus::Rejected,
+                    "Cancelled" => BookingStatus::Cancelled,
+                    _ => BookingStatus::Pending,
+                };
+
+                let naive_date = NaiveDate::from_ymd_opt(
+                    row.date.year(),
+                    row.date.month() as u32,
+                    row.date.day() as u32,
+                )
+                .unwrap();
+
+                Booking {
+                    id: row.id,
+                    microscope_id: row.microscope_id,
+                    date: naive_date,
+                    slot_start: row.slot_start,
+                    slot_end: row.slot_end,
+                    title: row.title,
+                    group_name: row.group_name,
+                    attendees: row.attendees,
+                    requester_id: row.requester_id,
+                    requester_name: row.requester_name,
+                    status,
+                    approved_by: row.approved_by,
+                    required_capabilities: row
+                        .required_capabilities
+                        .and_then(|v| serde_json::from_value(v).ok()),
+                    created_at: DateTime::from_timestamp(row.created_at.unix_timestamp(), 0)
+                        .unwrap()
+                        .fixed_offset(),
+                }
+            })
+            .collect();
+
+        Ok(bookings)
+    }
+
+    /// Find an approved booking on the same microscope that overlaps the given one
+    pub async fn find_approved_overlap(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        booking: &Booking,
+    ) -> Result<Option<Uuid>, SqlxError> {
+        let time_date =
+            time::Date::from_ordinal_date(booking.date.year(), booking.date.ordinal() as u16)
+                .unwrap();
+
+        let row = sqlx::query!(
+            r#"
+            SELECT id
+            FROM bookings
+            WHERE microscope_id = $1
+              AND date = $2
+              AND status = 'Approved'
+              AND id != $5
+              AND NOT (slot_end <= $3 OR slot_start >= $4)
+            LIMIT 1
+            "#,
+            booking.microscope_id,
+            time_date,
+            booking.slot_start,
+            booking.slot_end,
+            booking.id
+        )
+        .fetch_optional(&mut **tx)
+        .await?;
+
+        Ok(row.map(|row| row.id))
+    }
+
+    pub async fn set_booking_decision(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        booking_id: Uuid,
+        status: BookingStatus,
+        decided_by: Uuid,
+        reason: Option<&str>,
+    ) -> Result<u64, SqlxError> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE bookings
+            SET status = $2::VARCHAR,
+                approved_by = CASE WHEN $2::VARCHAR = 'Approved' THEN $3 ELSE approved_by END,
+                rejection_reason = CASE
+                    WHEN $2::VARCHAR = 'Rejected' THEN COALESCE($4, rejection_reason)
+                    ELSE rejection_reason
+                END
+            WHERE id = $1
+            "#,
+            booking_id,
+            match status {
+                BookingStatus::Pending => "Pending",
+                BookingStatus::Approved => "Approved",
+                BookingStatus::Rejected => "Rejected",
+                BookingStatus::Cancelled => "Cancelled",
+            },
+            decided_by,
+            reason
+        )
+        .execute(&mut **tx)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    pub async fn list_approval_rules(&self) -> Result<Vec<ApprovalRule>, SqlxError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT id, name, priority, action, requester_roles, duration_under_minutes,
+                   duration_over_minutes, weekdays_only, microscope_id, enabled, created_at
+            FROM approval_rules
+            ORDER BY priority, created_at
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let rules = rows
+            .into_iter()
+            .map(|row| {
+                let action = match row.action.as_str() {
+                    "AutoApprove" => ApprovalAction::AutoApprove,
+                    _ => ApprovalAction::RequireApproval,
+                };
+
+                let requester_roles = row
+                    .requester_roles
+                    .iter()
+                    .filter_map(|role| match role.as_str() {
+                        "Student" => Some(UserRole::Student),
+                        "Teacher" => Some(UserRole::Teacher),
+                        "Admin" => Some(UserRole::Admin),
+                        _ => None,
+                    })
+                    .collect();
+
+                ApprovalRule {
+                    id: row.id,
+                    name: row.name,
+                    priority: row.priority,
+                    action,
+                    requester_roles,
+                    duration_under_minutes: row.duration_under_minutes,
+                    duration_over_minutes: row.duration_over_minutes,
+                    weekdays_only: row.weekdays_only,
+                    microscope_id: row.microscope_id,
+                    enabled: row.enabled,
+                    created_at: DateTime::from_timestamp(row.created_at.unix_timestamp(), 0)
+                        .unwrap()
+                        .with_timezone(&Utc),
+                }
+            })
+            .collect();
+
+        Ok(rules)
+    }
+
+    /// Insert the rule, or replace it if a rule with the same ID exists
+    pub async fn upsert_approval_rule(
+        &self,
+        rule: &ApprovalRule,
+    ) -> Result<ApprovalRule, SqlxError> {
+        let requester_roles: Vec<String> = rule
+            .requester_roles
+            .iter()
+            .map(|role| match role {
+                UserRole::Student => "Student".to_string(),
+                UserRole::Teacher => "Teacher".to_string(),
+                UserRole::Admin => "Admin".to_string(),
+            })
+            .collect();
+
+        let row = sqlx::query!(
+            r#"
+            INSERT INTO approval_rules (
+                id, name, priority, action, requester_roles, duration_under_minutes,
+                duration_over_minutes, weekdays_only, microscope_id, enabled
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            ON CONFLICT (id) DO UPDATE SET
+                name = EXCLUDED.name,
+                priority = EXCLUDED.priority,
+                action = EXCLUDED.action,
+                requester_roles = EXCLUDED.requester_roles,
+                duration_under_minutes = EXCLUDED.duration_under_minutes,
+                duration_over_minutes = EXCLUDED.duration_over_minutes,
+                weekdays_only = EXCLUDED.weekdays_only,
+                microscope_id = EXCLUDED.microscope_id,
+                enabled = EXCLUDED.enabled
+            RETURNING id, created_at
+            "#,
+            rule.id,
+            rule.name,
+            rule.priority,
+            match rule.action {
+                ApprovalAction::AutoApprove => "AutoApprove",
+                ApprovalAction::RequireApproval => "RequireApproval",
+            },
+            &requester_roles,
+            rule.duration_under_minutes,
+            rule.duration_over_minutes,
+            rule.weekdays_only,
+            rule.microscope_id,
+            rule.enabled
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(ApprovalRule {
+            id: row.id,
+            created_at: DateTime::from_timestamp(row.created_at.unix_timestamp(), 0)
+                .unwrap()
+                .with_timezone(&Utc),
+            ..rule.clone()
+        })
+    }
+
+    pub async fn approval_rule_exists(&self, rule_id: Uuid) -> Result<bool, SqlxError> {
+        let result = sqlx::query!(
+            "SELECT EXISTS(SELECT 1 FROM approval_rules WHERE id = $1) as exists",
+            rule_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(result.exists.unwrap_or(false))
+    }
+
+    pub async fn delete_approval_rule(&self, rule_id: Uuid) -> Result<u64, SqlxError> {
+        let result = sqlx::query!("DELETE FROM approval_rules WHERE id = $1", rule_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    pub async fn list_retention_policies(&self) -> Result<Vec<RetentionPolicy>, SqlxError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT id, name, priority, owner_roles, microscope_id, retain_days, after_term_end,
+                   keep_starred, keep_exported, enabled, created_at
+            FROM retention_policies
+            ORDER BY priority, created_at
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let policies = rows
+            .into_iter()
+            .map(|row| {
+                let owner_roles = row
+                    .owner_roles
+                    .iter()
+                    .filter_map(|role| match role.as_str() {
+                        "Student" => Some(UserRole::Student),
+                        "Teacher" => Some(UserRole::Teacher),
+                        "Admin" => Some(UserRole::Admin),
+                        _ => None,
+                    })
+                    .collect();
+
+                RetentionPolicy {
+                    id: row.id,
+                    name: row.name,
+                    priority: row.priority,
+                    owner_roles,
+                    microscope_id: row.microscope_id,
+                    retain_days: row.retain_days,
+                    after_term_end: row.after_term_end,
+                    keep_starred: row.keep_starred,
+                    keep_exported: row.keep_exported,
+                    enabled: row.enabled,
+                    created_at: DateTime::from_timestamp(row.created_at.unix_timestamp(), 0)
+                        .unwrap()
+                        .with_timezone(&Utc),
+                }
+            })
+            .collect();
+
+        Ok(policies)
+    }
+
+    /// Insert the policy, or replace it if a policy with the same ID exists
+    pub async fn upsert_retention_policy(
+        &self,
+        policy: &RetentionPolicy,
+    ) -> Result<RetentionPolicy, SqlxError> {
+        let owner_roles: Vec<String> = policy
+            .owner_roles
+            .iter()
+            .map(|role| match role {
+                UserRole::Student => "Student".to_string(),
+                UserRole::Teacher => "Teacher".to_string(),
+                UserRole::Admin => "Admin".to_string(),
+            })
+            .collect();
+
+        let row = sqlx::query!(
+            r#"
+            INSERT INTO retention_policies (
+                id, name, priority, owner_roles, microscope_id, retain_days, after_term_end,
+                keep_starred, keep_exported, enabled
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            ON CONFLICT (id) DO UPDATE SET
+                name = EXCLUDED.name,
+                priority = EXCLUDED.priority,
+                owner_roles = EXCLUDED.owner_roles,
+                microscope_id = EXCLUDED.microscope_id,
+                retain_days = EXCLUDED.retain_days,
+                after_term_end = EXCLUDED.after_term_end,
+                keep_starred = EXCLUDED.keep_starred,
+                keep_exported = EXCLUDED.keep_exported,
+                enabled = EXCLUDED.enabled
+            RETURNING id, created_at
+            "#,
+            policy.id,
+            policy.name,
+            policy.priority,
+            &owner_roles,
+            policy.microscope_id,
+            policy.retain_days,
+            policy.after_term_end,
+            policy.keep_starred,
+            policy.keep_exported,
+            policy.enabled
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(RetentionPolicy {
+            id: row.id,
+            created_at: DateTime::from_timestamp(row.created_at.unix_timestamp(), 0)
+                .unwrap()
+                .with_timezone(&Utc),
+            ..policy.clone()
+        })
+    }
+
+    pub async fn retention_policy_exists(&self, policy_id: Uuid) -> Result<bool, SqlxError> {
+        let result = sqlx::query!(
+            "SELECT EXISTS(SELECT 1 FROM retention_policies WHERE id = $1) as exists",
+            policy_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(result.exists.unwrap_or(false))
+    }
+
+    pub async fn delete_retention_policy(&self, policy_id: Uuid) -> Result<u64, SqlxError> {
+        let result = sqlx::query!("DELETE FROM retention_policies WHERE id = $1", policy_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    pub async fn list_academic_terms(&self) -> Result<Vec<AcademicTerm>, SqlxError> {
+        let rows = sqlx::query!(
+            "SELECT id, name, start_date, end_date FROM academic_terms ORDER BY start_date, name"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| AcademicTerm {
+                id: row.id,
+                name: row.name,
+                start_date: NaiveDate::from_ymd_opt(
+                    row.start_date.year(),
+                    row.start_date.month() as u32,
+                    row.start_date.day() as u32,
+                )
+                .unwrap(),
+                end_date: NaiveDate::from_ymd_opt(
+                    row.end_date.year(),
+                    row.end_date.month() as u32,
+                    row.end_date.day() as u32,
+                )
+                .unwrap(),
+            })
+            .collect())
+    }
+
+    pub async fn create_academic_term(
+        &self,
+        name: &str,
+        start_date: NaiveDate,
+        end_date: NaiveDate,
+    ) -> Result<AcademicTerm, SqlxError> {
+        let id = sqlx::query_scalar!(
+            r#"
+            INSERT INTO academic_terms (name, start_date, end_date)
+            VALUES ($1, $2, $3)
+            RETURNING id
+            "#,
+            name,
+            time::Date::from_ordinal_date(start_date.year(), start_date.ordinal() as u16).unwrap(),
+            time::Date::from_ordinal_date(end_date.year(), end_date.ordinal() as u16).unwrap()
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(AcademicTerm {
+            id,
+            name: name.to_string(),
+            start_date,
+            end_date,
+        })
+    }
+
+    pub async fn delete_academic_term(&self, term_id: Uuid) -> Result<u64, SqlxError> {
+        let result = sqlx::query!("DELETE FROM academic_terms WHERE id = $1", term_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    pub async fn record_approval_decision(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        booking_id: Uuid,
+        rule_id: Option<Uuid>,
+        rule_name: &str,
+        action: ApprovalAction,
+    ) -> Result<ApprovalDecision, SqlxError> {
+        let row = sqlx::query!(
+            r#"
+            INSERT INTO booking_approval_decisions (booking_id, rule_id, rule_name, action)
+            VALUES ($1, $2, $3, $4)
+            RETURNING id, booking_id, rule_id, rule_name, decided_at
+            "#,
+            booking_id,
+            rule_id,
+            rule_name,
+            match action {
+                ApprovalAction::AutoApprove => "AutoApprove",
+                ApprovalAction::RequireApproval => "RequireApproval",
+            }
+        )
+        .fetch_one(&mut **tx)
+        .await?;
+
+        Ok(ApprovalDecision {
+            id: row.id,
+            booking_id: row.booking_id,
+            rule_id: row.rule_id,
+            rule_name: row.rule_name,
+            action,
+            decided_at: DateTime::from_timestamp(row.decided_at.unix_timestamp(), 0)
+                .unwrap()
+                .with_timezone(&Utc),
+        })
+    }
+
+    pub async fn get_approval_decisions(
+        &self,
+        booking_id: Uuid,
+    ) -> Result<Vec<ApprovalDecision>, SqlxError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT id, booking_id, rule_id, rule_name, action, decided_at
+            FROM booking_approval_decisions
+            WHERE booking_id = $1
+            ORDER BY decided_at
+            "#,
+            booking_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let decisions = rows
+            .into_iter()
+            .map(|row| ApprovalDecision {
+                id: row.id,
+                booking_id: row.booking_id,
+                rule_id: row.rule_id,
+                rule_name: row.rule_name,
+                action: match row.action.as_str() {
+                    "AutoApprove" => ApprovalAction::AutoApprove,
+                    _ => ApprovalAction::RequireApproval,
+                },
+                decided_at: DateTime::from_timestamp(row.decided_at.unix_timestamp(), 0)
+                    .unwrap()
+                    .with_timezone(&Utc),
             })
             .collect();
 
-        Ok(images)
+        Ok(decisions)
     }
 
-    pub async fn search_images(
+    pub async fn microscope_exists(&self, microscope_id: &str) -> Result<bool, SqlxError> {
+        let result = sqlx::query!(
+            "SELECT EXISTS(SELECT 1 FROM microscopes WHERE id = $1) as exists",
+            microscope_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(result.exists.unwrap_or(false))
+    }
+
+    pub async fn get_microscope_by_id(
         &self,
-        user_id: Option<Uuid>,
+        microscope_id: &str,
+    ) -> Result<Option<Microscope>, SqlxError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT id, name, location, status, specs
+            FROM microscopes
+            WHERE id = $1
+            "#,
+            microscope_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| {
+            let status = match row.status.as_str() {
+                "Available" => MicroscopeState::Available,
+                "InUse" => MicroscopeState::InUse,
+                "Maintenance" => MicroscopeState::Maintenance,
+                "Offline" => MicroscopeState::Offline,
+                _ => MicroscopeState::Available,
+            };
+
+            Microscope {
+                id: row.id,
+                name: row.name,
+                location: row.location,
+                status,
+                specs: row
+                    .specs
+                    .and_then(|v| serde_json::from_value(v).ok())
+                    .unwrap_or_default(),
+            }
+        }))
+    }
+
+    pub async fn list_microscopes(&self) -> Result<Vec<Microscope>, SqlxError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT id, name, location, status, specs
+            FROM microscopes
+            ORDER BY id
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let microscopes = rows
+            .into_iter()
+            .map(|row| {
+                let status = match row.status.as_str() {
+                    "Available" => MicroscopeState::Available,
+                    "InUse" => MicroscopeState::InUse,
+                    "Maintenance" => MicroscopeState::Maintenance,
+                    "Offline" => MicroscopeState::Offline,
+                    _ => MicroscopeState::Available,
+                };
+
+                Microscope {
+                    id: row.id,
+                    name: row.name,
+                    location: row.location,
+                    status,
+                    specs: row
+                        .specs
+                        .and_then(|v| serde_json::from_value(v).ok())
+                        .unwrap_or_default(),
+                }
+            })
+            .collect();
+
+        Ok(microscopes)
+    }
+
+    /// Bookings still holding a slot on the microscope that overlap the given time range.
+    /// Booking slots are interpreted as local time in `timezone`. The bookings are locked
+    /// until the transaction ends.
+    pub async fn get_bookings_overlapping_range(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        microscope_id: &str,
+        starts_at: DateTime<Utc>,
+        ends_at: DateTime<Utc>,
+        timezone: &str,
+    ) -> Result<Vec<Booking>, SqlxError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT id, microscope_id, date, slot_start, slot_end, title,
+                   group_name, attendees, requester_id, requester_name,
+                   status, approved_by, required_capabilities, created_at
+            FROM bookings
+            WHERE microscope_id = $1
+              AND status IN ('Pending', 'Approved')
+              AND ((date + make_interval(mins => slot_start)) AT TIME ZONE $4) < $3
+              AND ((date + make_interval(mins => slot_end)) AT TIME ZONE $4) > $2
+            ORDER BY date, slot_start
+            FOR UPDATE
+            "#,
+            microscope_id,
+            time::OffsetDateTime::from_unix_timestamp(starts_at.timestamp()).unwrap(),
+            time::OffsetDateTime::from_unix_timestamp(ends_at.timestamp()).unwrap(),
+            timezone
+        )
+        .fetch_all(&mut **tx)
+        .await?;
+
+        let bookings = rows
+            .into_iter()
+            .map(|row| {
+                let status = match row.status.as_str() {
+                    "Pending" => BookingStatus::Pending,
+                    "Approved" => BookingStatus::Approved,
+                    "Rejected" => BookingStatus::Rejected,
+                    "Cancelled" => BookingStatus::Cancelled,
+                    _ => BookingStatus::Pending,
+                };
+
+                let naive_date = NaiveDate::from_ymd_opt(
+                    row.date.year(),
+                    row.date.month() as u32,
+                    row.date.day() as u32,
+                )
+                .unwrap();
+
+                Booking {
+                    id: row.id,
+                    microscope_id: row.microscope_id,
+                    date: naive_date,
+                    slot_start: row.slot_start,
+                    slot_end: row.slot_end,
+                    title: row.title,
+                    group_name: row.group_name,
+                    attendees: row.attendees,
+                    requester_id: row.requester_id,
+                    requester_name: row.requester_name,
+                    status,
+                    approved_by: row.approved_by,
+                    required_capabilities: row
+                        .required_capabilities
+                        .and_then(|v| serde_json::from_value(v).ok()),
+                    created_at: DateTime::from_timestamp(row.created_at.unix_timestamp(), 0)
+                        .unwrap()
+                        .fixed_offset(),
+                }
+            })
+            .collect();
+
+        Ok(bookings)
+    }
+
+    pub async fn create_maintenance_window(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        window: &MaintenanceWindow,
+    ) -> Result<MaintenanceWindow, SqlxError> {
+        let row = sqlx::query_as!(
+            MaintenanceWindowRow,
+            r#"
+            INSERT INTO maintenance_windows (
+                id, microscope_id, kind, starts_at, ends_at, reason, created_by
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING id, microscope_id, kind, starts_at, ends_at, reason,
+                     created_by, cancelled_at, created_at
+            "#,
+            window.id,
+            window.microscope_id,
+            match window.kind {
+                MaintenanceKind::Maintenance => "Maintenance",
+                MaintenanceKind::Offline => "Offline",
+            },
+            time::OffsetDateTime::from_unix_timestamp(window.starts_at.timestamp()).unwrap(),
+            time::OffsetDateTime::from_unix_timestamp(window.ends_at.timestamp()).unwrap(),
+            window.reason,
+            window.created_by
+        )
+        .fetch_one(&mut **tx)
+        .await?;
+
+        Ok(maintenance_window_from_row(row))
+    }
+
+    pub async fn list_maintenance_windows(
+        &self,
+        microscope_id: &str,
+        include_past: bool,
+    ) -> Result<Vec<MaintenanceWindow>, SqlxError> {
+        let rows = sqlx::query_as!(
+            MaintenanceWindowRow,
+            r#"
+            SELECT id, microscope_id, kind, starts_at, ends_at, reason,
+                   created_by, cancelled_at, created_at
+            FROM maintenance_windows
+            WHERE microscope_id = $1
+              AND ($2 OR (cancelled_at IS NULL AND ends_at > NOW()))
+            ORDER BY starts_at
+            "#,
+            microscope_id,
+            include_past
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(maintenance_window_from_row).collect())
+    }
+
+    /// First non-cancelled maintenance window on the microscope overlapping a booking slot.
+    /// Booking slots are interpreted as local time in `timezone`.
+    pub async fn find_maintenance_conflict(
+        &self,
+        microscope_id: &str,
+        date: NaiveDate,
+        slot_start: i32,
+        slot_end: i32,
+        timezone: &str,
+    ) -> Result<Option<MaintenanceWindow>, SqlxError> {
+        let time_date = time::Date::from_ordinal_date(date.year(), date.ordinal() as u16).unwrap();
+
+        let row = sqlx::query_as!(
+            MaintenanceWindowRow,
+            r#"
+            SELECT id, microscope_id, kind, starts_at, ends_at, reason,
+                   created_by, cancelled_at, created_at
+            FROM maintenance_windows
+            WHERE microscope_id = $1
+              AND cancelled_at IS NULL
+              AND starts_at < (($2::date + make_interval(mins => $4)) AT TIME ZONE $5)
+              AND ends_at > (($2::date + make_interval(mins => $3)) AT TIME ZONE $5)
+            ORDER BY starts_at
+            LIMIT 1
+            "#,
+            microscope_id,
+            time_date,
+            slot_start,
+            slot_end,
+            timezone
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(maintenance_window_from_row))
+    }
+
+    /// Maintenance window currently in effect for the microscope, if any
+    pub async fn get_active_maintenance_window(
+        &self,
+        microscope_id: &str,
+    ) -> Result<Option<MaintenanceWindow>, SqlxError> {
+        let row = sqlx::query_as!(
+            MaintenanceWindowRow,
+            r#"
+            SELECT id, microscope_id, kind, starts_at, ends_at, reason,
+                   created_by, cancelled_at, created_at
+            FROM maintenance_windows
+            WHERE microscope_id = $1
+              AND cancelled_at IS NULL
+              AND starts_at <= NOW()
+              AND ends_at > NOW()
+            ORDER BY starts_at
+            LIMIT 1
+            "#,
+            microscope_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(maintenance_window_from_row))
+    }
+
+    pub async fn cancel_maintenance_window(&self, window_id: Uuid) -> Result<u64, SqlxError> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE maintenance_windows
+            SET cancelled_at = NOW()
+            WHERE id = $1 AND cancelled_at IS NULL
+            "#,
+            window_id
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Bring `microscopes.status` in line with the maintenance windows currently in effect.
+    /// Returns the number of microscopes whose status changed.
+    pub async fn sync_microscope_maintenance_status(&self) -> Result<u64, SqlxError> {
+        // Offline takes precedence when windows of both kinds overlap. A Maintenance or
+        // Offline status set by hand is left as it is.
+        let entered = sqlx::query!(
+            r#"
+            UPDATE microscopes m
+            SET status = w.kind, status_source = 'MaintenanceWindow'
+            FROM (
+                SELECT DISTINCT ON (microscope_id) microscope_id, kind
+                FROM maintenance_windows
+                WHERE cancelled_at IS NULL AND starts_at <= NOW() AND ends_at > NOW()
+                ORDER BY microscope_id, (kind = 'Offline') DESC
+            ) w
+            WHERE m.id = w.microscope_id
+              AND m.status <> w.kind
+              AND (m.status NOT IN ('Maintenance', 'Offline') OR m.status_source = 'MaintenanceWindow')
+            "#
+        )
+        .execute(&self.pool)
+        .await?;
+
+        let left = sqlx::query!(
+            r#"
+            UPDATE microscopes m
+            SET status = 'Available', status_source = 'Manual'
+            WHERE m.status IN ('Maintenance', 'Offline')
+              AND m.status_source = 'MaintenanceWindow'
+              AND NOT EXISTS (
+                  SELECT 1 FROM maintenance_windows w
+                  WHERE w.microscope_id = m.id
+                    AND w.cancelled_at IS NULL
+                    AND w.starts_at <= NOW()
+                    AND w.ends_at > NOW()
+              )
+            "#
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(entered.rows_affected() + left.rows_affected())
+    }
+
+    pub async fn create_notification(
+        &self,
+        user_id: Uuid,
+        kind: NotificationKind,
+        message: &str,
+        booking_id: Option<Uuid>,
         session_id: Option<Uuid>,
-        tags: Option<String>,
-        date_from: Option<NaiveDate>,
-        date_to: Option<NaiveDate>,
+    ) -> Result<Notification, SqlxError> {
+        let row = sqlx::query!(
+            r#"
+            INSERT INTO notifications (user_id, kind, message, booking_id, session_id)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING id, user_id, kind, message, booking_id, session_id, read_at, created_at
+            "#,
+            user_id,
+            notification_kind_str(kind),
+            message,
+            booking_id,
+            session_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(Notification {
+            id: row.id,
+            user_id: row.user_id,
+            kind,
+            message: row.message,
+            booking_id: row.booking_id,
+            session_id: row.session_id,
+            read_at: row.read_at.map(|dt| {
+                DateTime::from_timestamp(dt.unix_timestamp(), 0)
+                    .unwrap()
+                    .with_timezone(&Utc)
+            }),
+            created_at: DateTime::from_timestamp(row.created_at.unix_timestamp(), 0)
+                .unwrap()
+                .with_timezone(&Utc),
+        })
+    }
+
+    pub async fn list_notifications(
+        &self,
+        user_id: Uuid,
+        unread_only: bool,
         limit: u64,
         offset: u64,
-    ) -> Result<Vec<Image>, SqlxError> {
-        let mut query = r#"
-            SELECT i.id, i.session_id, i.filename, i.file_path, i.content_type, i.file_size,
-                   i.width, i.height, i.metadata, i.captured_at
-            FROM images i
-            INNER JOIN sessions s ON i.session_id = s.id
-            WHERE 1=1
-        "#
-        .to_string();
+    ) -> Result<Vec<Notification>, SqlxError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT id, user_id, kind, message, booking_id, session_id, read_at, created_at
+            FROM notifications
+            WHERE user_id = $1 AND (NOT $2 OR read_at IS NULL)
+            ORDER BY created_at DESC
+            LIMIT $3 OFFSET $4
+            "#,
+            user_id,
+            unread_only,
+            limit as i64,
+            offset as i64
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let notifications = rows
+            .into_iter()
+            .filter_map(|row| {
+                Some(Notification {
+                    id: row.id,
+                    user_id: row.user_id,
+                    kind: parse_notification_kind(&row.kind)?,
+                    message: row.message,
+                    booking_id: row.booking_id,
+                    session_id: row.session_id,
+                    read_at: row.read_at.map(|dt| {
+                        DateTime::from_timestamp(dt.unix_timestamp(), 0)
+                            .unwrap()
+                            .with_timezone(&Utc)
+                    }),
+                    created_at: DateTime::from_timestamp(row.created_at.unix_timestamp(), 0)
+                        .unwrap()
+                        .with_timezone(&Utc),
+                })
+            })
+            .collect();
+
+        Ok(notifications)
+    }
+
+    pub async fn mark_notification_read(
+        &self,
+        notification_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<u64, SqlxError> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE notifications
+            SET read_at = COALESCE(read_at, NOW())
+            WHERE id = $1 AND user_id = $2
+            "#,
+            notification_id,
+            user_id
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Booked and used time per microscope and lab-local day.
+    ///
+    /// Bookings count on their booking date, sessions on the day they started. Only
+    /// (microscope, day) pairs with any bookings or sessions are returned.
+    pub async fn get_usage_by_microscope_day(
+        &self,
+        filter: &AnalyticsFilter<'_>,
+    ) -> Result<Vec<MicroscopeDayUsage>, SqlxError> {
+        let from = time::Date::from_ordinal_date(filter.from.year(), filter.from.ordinal() as u16)
+            .unwrap();
+        let to =
+            time::Date::from_ordinal_date(filter.to.year(), filter.to.ordinal() as u16).unwrap();
+
+        let rows = sqlx::query!(
+            r#"
+            WITH booked AS (
+                SELECT b.microscope_id, b.date AS day,
+                       COUNT(*) AS bookings,
+                       SUM(b.slot_end - b.slot_start) AS booked_minutes,
+                       COUNT(*) FILTER (
+                           WHERE ((b.date + make_interval(mins => b.slot_end)) AT TIME ZONE $5) < NOW()
+                             AND NOT EXISTS (SELECT 1 FROM sessions s WHERE s.booking_id = b.id)
+                       ) AS no_shows,
+                       COUNT(*) FILTER (
+                           WHERE ((b.date + make_interval(mins => b.slot_end)) AT TIME ZONE $5) < NOW()
+                       ) AS ended_bookings
+                FROM bookings b
+                WHERE b.status = 'Approved'
+                  AND b.date BETWEEN $1 AND $2
+                  AND ($3::VARCHAR IS NULL OR b.microscope_id = $3)
+                  AND ($4::VARCHAR IS NULL OR b.group_name = $4)
+                GROUP BY b.microscope_id, b.date
+            ),
+            used AS (
+                SELECT s.microscope_id, (s.started_at AT TIME ZONE $5)::date AS day,
+                       COUNT(*) AS sessions,
+                       SUM(EXTRACT(EPOCH FROM COALESCE(s.ended_at, NOW()) - s.started_at)) / 60 AS used_minutes,
+                       SUM((SELECT COUNT(*) FROM images i WHERE i.session_id = s.id AND i.deleted_at IS NULL)) AS captures
+                FROM sessions s
+                LEFT JOIN bookings b ON b.id = s.booking_id
+                WHERE (s.started_at AT TIME ZONE $5)::date BETWEEN $1 AND $2
+                  AND ($3::VARCHAR IS NULL OR s.microscope_id = $3)
+                  AND ($4::VARCHAR IS NULL OR b.group_name = $4)
+                GROUP BY 1, 2
+            )
+            SELECT COALESCE(booked.microscope_id, used.microscope_id) AS "microscope_id!",
+                   COALESCE(booked.day, used.day) AS "day!",
+                   COALESCE(booked.bookings, 0) AS "bookings!",
+                   COALESCE(booked.booked_minutes, 0) AS "booked_minutes!",
+                   COALESCE(booked.ended_bookings, 0) AS "ended_bookings!",
+                   COALESCE(booked.no_shows, 0) AS "no_shows!",
+                   COALESCE(used.sessions, 0) AS "sessions!",
+                   COALESCE(ROUND(used.used_minutes), 0)::BIGINT AS "used_minutes!",
+                   COALESCE(used.captures, 0)::BIGINT AS "captures!"
+            FROM booked
+            FULL OUTER JOIN used
+              ON used.microscope_id = booked.microscope_id AND used.day = booked.day
+            ORDER BY 2, 1
+            "#,
+            from,
+            to,
+            filter.microscope_id,
+            filter.group_name,
+            filter.timezone
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| MicroscopeDayUsage {
+                microscope_id: row.microscope_id,
+                date: NaiveDate::from_ymd_opt(
+                    row.day.year(),
+                    row.day.month() as u32,
+                    row.day.day() as u32,
+                )
+                .unwrap(),
+                bookings: row.bookings,
+                booked_minutes: row.booked_minutes,
+                ended_bookings: row.ended_bookings,
+                no_shows: row.no_shows,
+                sessions: row.sessions,
+                used_minutes: row.used_minutes,
+                captures: row.captures,
+            })
+            .collect())
+    }
 
-        let mut param_count = 0;
-        let mut conditions = Vec::new();
+    /// Number of requested bookings (any outcome but cancelled) overlapping each hour of the day
+    pub async fn get_hourly_booking_demand(
+        &self,
+        filter: &AnalyticsFilter<'_>,
+    ) -> Result<Vec<HourlyDemand>, SqlxError> {
+        let from = time::Date::from_ordinal_date(filter.from.year(), filter.from.ordinal() as u16)
+            .unwrap();
+        let to =
+            time::Date::from_ordinal_date(filter.to.year(), filter.to.ordinal() as u16).unwrap();
 
-        if user_id.is_some() {
-            param_count += 1;
-            conditions.push(format!(" AND s.user_id = ${}", param_count));
-        }
+        let rows = sqlx::query!(
+            r#"
+            SELECT h.hour AS "hour!", COUNT(b.id) AS "bookings!"
+            FROM generate_series(0, 23) AS h(hour)
+            LEFT JOIN bookings b
+              ON b.slot_start < (h.hour + 1) * 60
+             AND b.slot_end > h.hour * 60
+             AND b.status <> 'Cancelled'
+             AND b.date BETWEEN $1 AND $2
+             AND ($3::VARCHAR IS NULL OR b.microscope_id = $3)
+             AND ($4::VARCHAR IS NULL OR b.group_name = $4)
+            GROUP BY h.hour
+            ORDER BY h.hour
+            "#,
+            from,
+            to,
+            filter.microscope_id,
+            filter.group_name
+        )
+        .fetch_all(&self.pool)
+        .await?;
 
-        if session_id.is_some() {
-            param_count += 1;
-            conditions.push(format!(" AND i.session_id = ${}", param_count));
-        }
+        Ok(rows
+            .into_iter()
+            .map(|row| HourlyDemand {
+                hour: row.hour,
+                bookings: row.bookings,
+            })
+            .collect())
+    }
 
-        if tags.is_some() {
-            param_count += 1;
-            conditions.push(format!(" AND i.metadata::text ILIKE ${}", param_count));
-        }
+    /// Users with the most booking requests in the period
+    pub async fn get_top_requesters(
+        &self,
+        filter: &AnalyticsFilter<'_>,
+        limit: i64,
+    ) -> Result<Vec<RequesterUsage>, SqlxError> {
+        let from = time::Date::from_ordinal_date(filter.from.year(), filter.from.ordinal() as u16)
+            .unwrap();
+        let to =
+            time::Date::from_ordinal_date(filter.to.year(), filter.to.ordinal() as u16).unwrap();
 
-        if date_from.is_some() {
-            param_count += 1;
-            conditions.push(format!(" AND i.captured_at >= ${}", param_count));
-        }
+        let rows = sqlx::query!(
+            r#"
+            SELECT b.requester_id AS "user_id!", u.name AS "name!",
+                   COUNT(*) AS "bookings!",
+                   COUNT(*) FILTER (WHERE b.status = 'Approved') AS "approved_bookings!",
+                   COALESCE(SUM(b.slot_end - b.slot_start) FILTER (WHERE b.status = 'Approved'), 0) AS "booked_minutes!",
+                   COUNT(*) FILTER (
+                       WHERE b.status = 'Approved'
+                         AND ((b.date + make_interval(mins => b.slot_end)) AT TIME ZONE $5) < NOW()
+                         AND NOT EXISTS (SELECT 1 FROM sessions s WHERE s.booking_id = b.id)
+                   ) AS "no_shows!"
+            FROM bookings b
+            JOIN users u ON u.id = b.requester_id
+            WHERE b.status <> 'Cancelled'
+              AND b.date BETWEEN $1 AND $2
+              AND ($3::VARCHAR IS NULL OR b.microscope_id = $3)
+              AND ($4::VARCHAR IS NULL OR b.group_name = $4)
+            GROUP BY b.requester_id, u.name
+            ORDER BY 3 DESC, 2
+            LIMIT $6
+            "#,
+            from,
+            to,
+            filter.microscope_id,
+            filter.group_name,
+            filter.timezone,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
 
-        if date_to.is_some() {
-            param_count += 1;
-            conditions.push(format!(" AND i.captured_at <= ${}", param_count));
-        }
+        Ok(rows
+            .into_iter()
+            .map(|row| RequesterUsage {
+                user_id: row.user_id,
+                name: row.name,
+                bookings: row.bookings,
+                approved_bookings: row.approved_bookings,
+                booked_minutes: row.booked_minutes,
+                no_shows: row.no_shows,
+            })
+            .collect())
+    }
+}
 
-        for condition in conditions {
-            query.push_str(&condition);
-        }
+/// Filters shared by the usage analytics queries
+#[derive(Debug, Clone)]
+pub struct AnalyticsFilter<'a> {
+    /// First lab-local day, inclusive
+    pub from: NaiveDate,
+    /// Last lab-local day, inclusive
+    pub to: NaiveDate,
+    pub microscope_id: Option<&'a str>,
+    /// Booking group name; sessions without a booking never match
+    pub group_name: Option<&'a str>,
+    /// IANA timezone the lab's booking dates and slots are in
+    pub timezone: &'a str,
+}
 
-        query.push_str(" ORDER BY i.captured_at DESC");
-        param_count += 1;
-        query.push_str(&format!(" LIMIT ${}", param_count));
-        param_count += 1;
-        query.push_str(&format!(" OFFSET ${}", param_count));
+/// Where an image's file is and the checksum recorded for it
+#[derive(Debug, Clone)]
+pub struct ImageFileRecord {
+    pub image_id: Uuid,
+    pub session_id: Uuid,
+    pub file_path: String,
+    pub sha256: Option<String>,
+}
 
-        let mut sql_query = sqlx::query(&query);
+/// An image as seen by the retention policies
+#[derive(Debug, Clone)]
+pub struct RetentionCandidate {
+    pub image_id: Uuid,
+    pub session_id: Uuid,
+    pub filename: String,
+    pub file_size: i64,
+    pub captured_at: DateTime<Utc>,
+    pub starred: bool,
+    /// The session's images were exported at least once
+    pub exported: bool,
+    /// Role of the session owner
+    pub owner_role: UserRole,
+    pub microscope_id: String,
+    pub term_end: Option<NaiveDate>,
+}
 
-        if let Some(uid) = user_id {
-            sql_query = sql_query.bind(uid);
-        }
+/// Booked and used time for one microscope on one lab-local day
+#[derive(Debug, Clone)]
+pub struct MicroscopeDayUsage {
+    pub microscope_id: String,
+    pub date: NaiveDate,
+    /// Approved bookings
+    pub bookings: i64,
+    pub booked_minutes: i64,
+    /// Approved bookings whose slot is over
+    pub ended_bookings: i64,
+    pub no_shows: i64,
+    pub sessions: i64,
+    pub used_minutes: i64,
+    pub captures: i64,
+}
 
-        if let Some(sid) = session_id {
-            sql_query = sql_query.bind(sid);
-        }
+/// Filters for [`DatabaseService::list_bookings`]
+#[derive(Debug, Clone, Default)]
+pub struct BookingFilter<'a> {
+    pub requester_id: Option<Uuid>,
+    pub microscope_id: Option<&'a str>,
+    pub date: Option<NaiveDate>,
+}
 
-        if let Some(tag_filter) = tags {
-            sql_query = sql_query.bind(format!("%{}%", tag_filter));
-        }
+/// Bind the parameters of a [`BookingFilter`] in the order `list_bookings` numbers them
+/// Selects a row's `created_at` in microseconds as `cursor_us`, for lists paged by cursor
+fn cursor_column(prefix: &str) -> String {
+    format!(
+        "(EXTRACT(EPOCH FROM {}created_at) * 1000000)::BIGINT AS cursor_us",
+        prefix
+    )
+}
 
-        if let Some(from_date) = date_from {
-            let from_datetime = from_date
-                .and_hms_opt(0, 0, 0)
-                .unwrap()
-                .and_local_timezone(Utc)
-                .unwrap();
-            sql_query = sql_query.bind(
-                time::OffsetDateTime::from_unix_timestamp(from_datetime.timestamp()).unwrap(),
-            );
-        }
+/// Keyset condition continuing a cursor page after `page.after`. Its two parameters follow
+/// the filter parameters and are bound by `bind_cursor`.
+fn cursor_condition(prefix: &str, page: &PageRequest, param_count: &mut usize) -> String {
+    if page.after.is_none() {
+        return String::new();
+    }
 
-        if let Some(to_date) = date_to {
-            let to_datetime = to_date
-                .and_hms_opt(23, 59, 59)
-                .unwrap()
-                .and_local_timezone(Utc)
-                .unwrap();
-            sql_query = sql_query
-                .bind(time::OffsetDateTime::from_unix_timestamp(to_datetime.timestamp()).unwrap());
-        }
+    *param_count += 2;
+    format!(
+        " AND ({0}created_at, {0}id) > (TIMESTAMPTZ 'epoch' + ${1} * INTERVAL '1 microsecond', ${2})",
+        prefix,
+        *param_count - 1,
+        *param_count
+    )
+}
 
-        sql_query = sql_query.bind(limit as i64).bind(offset as i64);
+fn bind_cursor<'q>(
+    query: sqlx::query::Query<'q, Postgres, sqlx::postgres::PgArguments>,
+    page: &PageRequest,
+) -> sqlx::query::Query<'q, Postgres, sqlx::postgres::PgArguments> {
+    match page.after {
+        Some(after) => query.bind(after.created_at_us).bind(after.id),
+        None => query,
+    }
+}
 
-        let rows = sql_query.fetch_all(&self.pool).await?;
+/// Rows to fetch for a page: one extra on cursor pages, to tell whether another page follows
+fn page_fetch_limit(page: &PageRequest) -> i64 {
+    if page.by_cursor {
+        page.limit as i64 + 1
+    } else {
+        page.limit as i64
+    }
+}
 
-        let images = rows
-            .into_iter()
-            .map(|row| {
-                let metadata: ImageMetadata =
-                    serde_json::from_value(row.get("metadata")).unwrap_or_default();
-                Image {
-                    id: row.get("id"),
-                    session_id: row.get("session_id"),
-                    filename: row.get("filename"),
-                    file_path: row.get("file_path"),
-                    content_type: row.get("content_type"),
-                    file_size: row.get("file_size"),
-                    width: row.get("width"),
-                    height: row.get("height"),
-                    metadata,
-                    captured_at: DateTime::from_timestamp(
-                        row.get::<time::OffsetDateTime, _>("captured_at")
-                            .unix_timestamp(),
-                        0,
-                    )
-                    .unwrap()
-                    .with_timezone(&Utc),
-                }
-            })
-            .collect();
+/// Drop the extra row of a cursor page and return the cursor of the page after it, if any
+fn split_cursor_page(rows: &mut Vec<PgRow>, page: &PageRequest) -> Option<Cursor> {
+    if !page.by_cursor || rows.len() as u64 <= page.limit {
+        return None;
+    }
 
-        Ok(images)
+    rows.truncate(page.limit as usize);
+    rows.last().map(|row| Cursor {
+        created_at_us: row.get("cursor_us"),
+        id: row.get("id"),
+    })
+}
+
+fn bind_booking_filter<'q>(
+    mut query: sqlx::query::Query<'q, Postgres, sqlx::postgres::PgArguments>,
+    filter: &BookingFilter<'q>,
+) -> sqlx::query::Query<'q, Postgres, sqlx::postgres::PgArguments> {
+    if let Some(requester_id) = filter.requester_id {
+        query = query.bind(requester_id);
+    }
+    if let Some(microscope_id) = filter.microscope_id {
+        query = query.bind(microscope_id);
     }
+    if let Some(date) = filter.date {
+        query =
+            query.bind(time::Date::from_ordinal_date(date.year(), date.ordinal() as u16).unwrap());
+    }
+    query
+}
 
-    pub async fn check_booking_conflicts(
-        &self,
-        microscope_id: &str,
-        date: NaiveDate,
-        slot_start: i32,
-        slot_end: i32,
-        exclude_booking_id: Option<Uuid>,
-    ) -> Result<bool, SqlxError> {
-        // Convert chrono NaiveDate to time Date
-        let time_date = time::Date::from_ordinal_date(date.year(), date.ordinal() as u16).unwrap();
+/// Filters for [`DatabaseService::list_sessions`]
+#[derive(Debug, Clone, Default)]
+pub struct SessionFilter<'a> {
+    pub microscope_id: Option<&'a str>,
+    /// Sessions the user owns or participates in
+    pub user_id: Option<Uuid>,
+    pub status: Option<SessionStatus>,
+    pub active_only: bool,
+    pub booking_id: Option<Uuid>,
+    /// Group name of the booking the session was started from
+    pub group_name: Option<&'a str>,
+    /// Inclusive lower bound on `started_at`
+    pub started_from: Option<DateTime<Utc>>,
+    /// Exclusive upper bound on `started_at`
+    pub started_to: Option<DateTime<Utc>>,
+}
 
-        let count = if let Some(exclude_id) = exclude_booking_id {
-            let result = sqlx::query!(
-                r#"
-                SELECT COUNT(*) as count
-                FROM bookings 
-                WHERE microscope_id = $1 
-                  AND date = $2 
-                  AND status IN ('Pending', 'Approved')
-                  AND id != $5
-                  AND NOT (slot_end <= $3 OR slot_start >= $4)
-                "#,
-                microscope_id,
-                time_date,
-                slot_start,
-                slot_end,
-                exclude_id
-            )
-            .fetch_one(&self.pool)
-            .await?;
-            result.count.unwrap_or(0)
-        } else {
-            let result = sqlx::query!(
-                r#"
-                SELECT COUNT(*) as count
-                FROM bookings 
-                WHERE microscope_id = $1 
-                  AND date = $2 
-                  AND status IN ('Pending', 'Approved')
-                  AND NOT (slot_end <= $3 OR slot_start >= $4)
-                "#,
-                microscope_id,
-                time_date,
-                slot_start,
-                slot_end
-            )
-            .fetch_one(&self.pool)
-            .await?;
-            result.count.unwrap_or(0)
+/// Bind the parameters of a [`SessionFilter`] in the order `list_sessions` numbers them
+fn bind_session_filter<'q>(
+    mut query: sqlx::query::Query<'q, Postgres, sqlx::postgres::PgArguments>,
+    filter: &SessionFilter<'q>,
+) -> sqlx::query::Query<'q, Postgres, sqlx::postgres::PgArguments> {
+    if let Some(microscope_id) = filter.microscope_id {
+        query = query.bind(microscope_id);
+    }
+    if let Some(user_id) = filter.user_id {
+        query = query.bind(user_id);
+    }
+    if let Some(status) = &filter.status {
+        let status_str = match status {
+            SessionStatus::Active => "Active",
+            SessionStatus::Completed => "Completed",
+            SessionStatus::Aborted => "Aborted",
         };
-
-        Ok(count > 0)
+        query = query.bind(status_str);
     }
-
-    pub async fn delete_booking(&self, booking_id: Uuid) -> Result<u64, SqlxError> {
-        let result = sqlx::query!(
-            r#"
-            DELETE
-            FROM bookings
-            WHERE id = $1
-        "#,
-            booking_id
-        )
-        .execute(&self.pool)
-        .await?;
-        Ok(result.rows_affected())
+    if let Some(booking_id) = filter.booking_id {
+        query = query.bind(booking_id);
     }
+    if let Some(group_name) = filter.group_name {
+        query = query.bind(group_name);
+    }
+    if let Some(from) = filter.started_from {
+        query = query.bind(time::OffsetDateTime::from_unix_timestamp(from.timestamp()).unwrap());
+    }
+    if let Some(to) = filter.started_to {
+        query = query.bind(time::OffsetDateTime::from_unix_timestamp(to.timestamp()).unwrap());
+    }
+    query
+}
 
-    pub async fn get_booking_owner(&self, booking_id: Uuid) -> Result<Option<Uuid>, SqlxError> {
-        let result = sqlx::query!(
-            "SELECT requester_id FROM bookings WHERE id = $1",
-            booking_id
-        )
-        .fetch_optional(&self.pool)
-        .await?;
+/// Filters for [`DatabaseService::search_images`]
+#[derive(Debug, Clone, Default)]
+pub struct ImageFilter {
+    /// Images from sessions the user owns or participates in
+    pub user_id: Option<Uuid>,
+    pub session_id: Option<Uuid>,
+    /// Substring matched against the image metadata
+    pub tags: Option<String>,
+    pub date_from: Option<NaiveDate>,
+    /// Inclusive; covers the whole day
+    pub date_to: Option<NaiveDate>,
+}
 
-        Ok(result.map(|row| row.requester_id))
+/// Bind the parameters of an [`ImageFilter`] in the order `search_images` numbers them
+fn bind_image_filter<'q>(
+    mut query: sqlx::query::Query<'q, Postgres, sqlx::postgres::PgArguments>,
+    filter: &ImageFilter,
+) -> sqlx::query::Query<'q, Postgres, sqlx::postgres::PgArguments> {
+    if let Some(user_id) = filter.user_id {
+        query = query.bind(user_id);
     }
-
-    pub async fn delete_booking_by_owner(
-        &self,
-        booking_id: Uuid,
-        user_id: Option<Uuid>,
-    ) -> Result<u64, SqlxError> {
-        let result = sqlx::query!(
-            r#"
-            DELETE
-            FROM bookings
-            WHERE id = $1
-            AND requester_id = $2
-        "#,
-            booking_id,
-            user_id
-        )
-        .execute(&self.pool)
-        .await?;
-        Ok(result.rows_affected())
+    if let Some(session_id) = filter.session_id {
+        query = query.bind(session_id);
     }
+    if let Some(tags) = &filter.tags {
+        query = query.bind(format!("%{}%", tags));
+    }
+    if let Some(from_date) = filter.date_from {
+        let from_datetime = from_date
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+            .and_local_timezone(Utc)
+            .unwrap();
+        query = query
+            .bind(time::OffsetDateTime::from_unix_timestamp(from_datetime.timestamp()).unwrap());
+    }
+    if let Some(to_date) = filter.date_to {
+        let to_datetime = to_date
+            .and_hms_opt(23, 59, 59)
+            .unwrap()
+            .and_local_timezone(Utc)
+            .unwrap();
+        query =
+            query.bind(time::OffsetDateTime::from_unix_timestamp(to_datetime.timestamp()).unwrap());
+    }
+    query
+}
+
+/// Active session together with the end of its booking
+#[derive(Debug, Clone)]
+pub struct SessionDeadline {
+    pub session_id: Uuid,
+    pub user_id: Uuid,
+    pub microscope_id: String,
+    pub booking_id: Uuid,
+    pub booking_ends_at: DateTime<Utc>,
+    /// Booking end in lab-local minutes since midnight
+    pub slot_end: i32,
 }
 
 /// User with password hash for authentication
@@ -1214,6 +4143,302 @@ impl Default for ImageMetadata {
             focus_quality: None,
             magnification: None,
             lighting_conditions: None,
+            acquired_at: None,
+            pixel_size_x_um: None,
+            pixel_size_y_um: None,
+            camera: None,
+            exposure_time_s: None,
+        }
+    }
+}
+
+fn notification_kind_str(kind: NotificationKind) -> &'static str {
+    match kind {
+        NotificationKind::MaintenanceConflict => "MaintenanceConflict",
+        NotificationKind::BookingCancelled => "BookingCancelled",
+        NotificationKind::SessionEnded => "SessionEnded",
+        NotificationKind::SessionEndingSoon => "SessionEndingSoon",
+        NotificationKind::SessionHandover => "SessionHandover",
+    }
+}
+
+fn parse_notification_kind(kind: &str) -> Option<NotificationKind> {
+    match kind {
+        "MaintenanceConflict" => Some(NotificationKind::MaintenanceConflict),
+        "BookingCancelled" => Some(NotificationKind::BookingCancelled),
+        "SessionEnded" => Some(NotificationKind::SessionEnded),
+        "SessionEndingSoon" => Some(NotificationKind::SessionEndingSoon),
+        "SessionHandover" => Some(NotificationKind::SessionHandover),
+        _ => None,
+    }
+}
+
+fn booking_event_type_str(event_type: BookingEventType) -> &'static str {
+    match event_type {
+        BookingEventType::Created => "Created",
+        BookingEventType::Updated => "Updated",
+        BookingEventType::Approved => "Approved",
+        BookingEventType::Rejected => "Rejected",
+        BookingEventType::Cancelled => "Cancelled",
+        BookingEventType::Deleted => "Deleted",
+    }
+}
+
+fn parse_booking_event_type(event_type: &str) -> Option<BookingEventType> {
+    match event_type {
+        "Created" => Some(BookingEventType::Created),
+        "Updated" => Some(BookingEventType::Updated),
+        "Approved" => Some(BookingEventType::Approved),
+        "Rejected" => Some(BookingEventType::Rejected),
+        "Cancelled" => Some(BookingEventType::Cancelled),
+        "Deleted" => Some(BookingEventType::Deleted),
+        _ => None,
+    }
+}
+
+/// Field-level diff between two booking snapshots, as `{"field": {"from": ..., "to": ...}}`
+fn booking_changes(
+    before: Option<&serde_json::Value>,
+    after: Option<&serde_json::Value>,
+) -> serde_json::Value {
+    let empty = serde_json::Map::new();
+    let before = before.and_then(|v| v.as_object()).unwrap_or(&empty);
+    let after = after.and_then(|v| v.as_object()).unwrap_or(&empty);
+
+    let mut changes = serde_json::Map::new();
+    for key in before.keys().chain(after.keys()) {
+        let from = before.get(key).unwrap_or(&serde_json::Value::Null);
+        let to = after.get(key).unwrap_or(&serde_json::Value::Null);
+        if from != to && !changes.contains_key(key) {
+            changes.insert(key.clone(), serde_json::json!({ "from": from, "to": to }));
         }
     }
+
+    serde_json::Value::Object(changes)
+}
+
+/// Columns of a `maintenance_windows` row, as read by `maintenance_window_from_row`
+struct MaintenanceWindowRow {
+    id: Uuid,
+    microscope_id: String,
+    kind: String,
+    starts_at: time::OffsetDateTime,
+    ends_at: time::OffsetDateTime,
+    reason: Option<String>,
+    created_by: Uuid,
+    cancelled_at: Option<time::OffsetDateTime>,
+    created_at: time::OffsetDateTime,
+}
+
+fn maintenance_window_from_row(row: MaintenanceWindowRow) -> MaintenanceWindow {
+    let kind = match row.kind.as_str() {
+        "Offline" => MaintenanceKind::Offline,
+        _ => MaintenanceKind::Maintenance,
+    };
+
+    MaintenanceWindow {
+        id: row.id,
+        microscope_id: row.microscope_id,
+        kind,
+        starts_at: DateTime::from_timestamp(row.starts_at.unix_timestamp(), 0)
+            .unwrap()
+            .with_timezone(&Utc),
+        ends_at: DateTime::from_timestamp(row.ends_at.unix_timestamp(), 0)
+            .unwrap()
+            .with_timezone(&Utc),
+        reason: row.reason,
+        created_by: row.created_by,
+        cancelled_at: row.cancelled_at.map(|dt| {
+            DateTime::from_timestamp(dt.unix_timestamp(), 0)
+                .unwrap()
+                .with_timezone(&Utc)
+        }),
+        created_at: DateTime::from_timestamp(row.created_at.unix_timestamp(), 0)
+            .unwrap()
+            .with_timezone(&Utc),
+    }
+}
+
+async fn user_storage_usage<'e, E: PgExecutor<'e>>(
+    executor: E,
+    user_id: Uuid,
+) -> Result<Option<UserStorageUsage>, SqlxError> {
+    let row = sqlx::query!(
+        r#"
+        SELECT u.id, u.name, u.storage_quota_bytes,
+               COUNT(i.id) FILTER (WHERE i.deleted_at IS NULL) AS "images!",
+               COALESCE(SUM(i.file_size), 0)::BIGINT AS "used_bytes!",
+               COALESCE(SUM(i.file_size) FILTER (WHERE i.deleted_at IS NOT NULL), 0)::BIGINT
+                   AS "trashed_bytes!"
+        FROM users u
+        LEFT JOIN sessions s ON s.user_id = u.id
+        LEFT JOIN images i ON i.session_id = s.id
+        WHERE u.id = $1
+        GROUP BY u.id
+        "#,
+        user_id
+    )
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(row.map(|row| UserStorageUsage {
+        user_id: row.id,
+        name: row.name,
+        images: row.images,
+        used_bytes: row.used_bytes,
+        trashed_bytes: row.trashed_bytes,
+        quota_bytes: row.storage_quota_bytes,
+        custom_quota: row.storage_quota_bytes.is_some(),
+    }))
+}
+
+async fn insert_booking_event<'e, E: PgExecutor<'e>>(
+    executor: E,
+    booking_id: Uuid,
+    event_type: BookingEventType,
+    actor_id: Option<Uuid>,
+    note: Option<&str>,
+    before: Option<&Booking>,
+    after: Option<&Booking>,
+) -> Result<BookingEvent, SqlxError> {
+    let before = before.map(|b| serde_json::to_value(b).unwrap_or_default());
+    let after = after.map(|b| serde_json::to_value(b).unwrap_or_default());
+    let changes = booking_changes(before.as_ref(), after.as_ref());
+
+    let row = sqlx::query!(
+        r#"
+        INSERT INTO booking_events (booking_id, event_type, actor_id, note, before, after, changes)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING id, created_at
+        "#,
+        booking_id,
+        booking_event_type_str(event_type),
+        actor_id,
+        note,
+        before,
+        after,
+        changes
+    )
+    .fetch_one(executor)
+    .await?;
+
+    Ok(BookingEvent {
+        id: row.id,
+        booking_id,
+        event_type,
+        actor_id,
+        note: note.map(str::to_string),
+        before,
+        after,
+        changes,
+        created_at: DateTime::from_timestamp(row.created_at.unix_timestamp(), 0)
+            .unwrap()
+            .with_timezone(&Utc),
+    })
+}
+
+fn handover_kind_str(kind: HandoverKind) -> &'static str {
+    match kind {
+        HandoverKind::Transfer => "Transfer",
+        HandoverKind::AddParticipant => "AddParticipant",
+    }
+}
+
+fn parse_handover_kind(kind: &str) -> Option<HandoverKind> {
+    match kind {
+        "Transfer" => Some(HandoverKind::Transfer),
+        "AddParticipant" => Some(HandoverKind::AddParticipant),
+        _ => None,
+    }
+}
+
+async fn insert_session_handover<'e, E: PgExecutor<'e>>(
+    executor: E,
+    session_id: Uuid,
+    kind: HandoverKind,
+    from_user_id: Option<Uuid>,
+    to_user_id: Uuid,
+    performed_by: Uuid,
+    note: Option<&str>,
+) -> Result<SessionHandover, SqlxError> {
+    let row = sqlx::query!(
+        r#"
+        INSERT INTO session_handovers (session_id, kind, from_user_id, to_user_id, performed_by, note)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING id, created_at
+        "#,
+        session_id,
+        handover_kind_str(kind),
+        from_user_id,
+        to_user_id,
+        performed_by,
+        note
+    )
+    .fetch_one(executor)
+    .await?;
+
+    Ok(SessionHandover {
+        id: row.id,
+        session_id,
+        kind,
+        from_user_id,
+        to_user_id,
+        performed_by: Some(performed_by),
+        note: note.map(str::to_string),
+        created_at: DateTime::from_timestamp(row.created_at.unix_timestamp(), 0)
+            .unwrap()
+            .with_timezone(&Utc),
+    })
+}
+
+fn session_note_kind_str(kind: SessionNoteKind) -> &'static str {
+    match kind {
+        SessionNoteKind::Text => "Text",
+        SessionNoteKind::Image => "Image",
+        SessionNoteKind::Measurement => "Measurement",
+    }
+}
+
+fn parse_session_note_kind(kind: &str) -> Option<SessionNoteKind> {
+    match kind {
+        "Text" => Some(SessionNoteKind::Text),
+        "Image" => Some(SessionNoteKind::Image),
+        "Measurement" => Some(SessionNoteKind::Measurement),
+        _ => None,
+    }
+}
+
+fn session_event_type_str(event_type: SessionEventType) -> &'static str {
+    match event_type {
+        SessionEventType::SessionStarted => "SessionStarted",
+        SessionEventType::Command => "Command",
+        SessionEventType::Focus => "Focus",
+        SessionEventType::TrackingStarted => "TrackingStarted",
+        SessionEventType::TrackingStopped => "TrackingStopped",
+        SessionEventType::Capture => "Capture",
+        SessionEventType::NoteAdded => "NoteAdded",
+        SessionEventType::NoteUpdated => "NoteUpdated",
+        SessionEventType::Handover => "Handover",
+        SessionEventType::SessionEnded => "SessionEnded",
+        SessionEventType::ImageDeleted => "ImageDeleted",
+        SessionEventType::ImageRestored => "ImageRestored",
+    }
+}
+
+fn parse_session_event_type(event_type: &str) -> Option<SessionEventType> {
+    match event_type {
+        "SessionStarted" => Some(SessionEventType::SessionStarted),
+        "Command" => Some(SessionEventType::Command),
+        "Focus" => Some(SessionEventType::Focus),
+        "TrackingStarted" => Some(SessionEventType::TrackingStarted),
+        "TrackingStopped" => Some(SessionEventType::TrackingStopped),
+        "Capture" => Some(SessionEventType::Capture),
+        "NoteAdded" => Some(SessionEventType::NoteAdded),
+        "NoteUpdated" => Some(SessionEventType::NoteUpdated),
+        "Handover" => Some(SessionEventType::Handover),
+        "SessionEnded" => Some(SessionEventType::SessionEnded),
+        "ImageDeleted" => Some(SessionEventType::ImageDeleted),
+        "ImageRestored" => Some(SessionEventType::ImageRestored),
+        _ => None,
+    }
 }
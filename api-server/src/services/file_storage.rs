@@ -1,11 +1,22 @@
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use futures::TryStreamExt;
 use mime_guess;
-use std::path::{Path, PathBuf};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
 use thiserror::Error;
-use tokio::fs;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::io::AsyncRead;
+use tokio_util::io::StreamReader;
 use uuid::Uuid;
 
-use crate::config::FileStorageConfig;
+use crate::config::{FileStorageConfig, StorageBackendKind};
+use crate::models::ImageRendition;
+use crate::services::storage_backend::{
+    ByteStream, LocalBackend, ObjectInfo, ObjectStoreBackend, StorageBackend,
+};
 
 #[derive(Error, Debug)]
 pub enum FileStorageError {
@@ -23,30 +34,66 @@ pub enum FileStorageError {
 
     #[error("Invalid file path: {0}")]
     InvalidPath(String),
+
+    #[error("Image processing failed: {0}")]
+    ImageProcessing(String),
+
+    #[error("Storage backend error: {0}")]
+    Backend(String),
+
+    #[error("Storage quota exceeded: {0} of {1} bytes used, a {2} byte file does not fit")]
+    QuotaExceeded(u64, u64, u64),
 }
 
+/// Cached content hashes are dropped wholesale once this many files are tracked
+const MAX_CACHED_HASHES: usize = 10_000;
+
 /// File storage service for handling image uploads and serving
 pub struct FileStorageService {
     config: FileStorageConfig,
-    base_path: PathBuf,
+    backend: Arc<dyn StorageBackend>,
+    /// SHA-256 per file location, valid while the file's size and mtime are unchanged
+    hash_cache: Mutex<HashMap<String, CachedHash>>,
+}
+
+struct CachedHash {
+    size: u64,
+    modified: Option<DateTime<Utc>>,
+    sha256: String,
 }
 
 impl FileStorageService {
+    /// Storage service using the backend selected in `config`
     pub fn new(config: FileStorageConfig) -> Result<Self, FileStorageError> {
-        let base_path = PathBuf::from(&config.base_path);
+        let backend: Arc<dyn StorageBackend> = match config.backend {
+            StorageBackendKind::Local => Arc::new(LocalBackend::new(&config.base_path)?),
+            StorageBackendKind::S3 => Arc::new(ObjectStoreBackend::s3(&config.s3)?),
+            StorageBackendKind::Memory => Arc::new(ObjectStoreBackend::in_memory()),
+        };
 
-        // Create base directory if it doesn't exist
-        std::fs::create_dir_all(&base_path)?;
+        Ok(Self::with_backend(config, backend))
+    }
 
-        Ok(Self { config, base_path })
+    /// Storage service on an explicit backend, ignoring `config.backend`
+    pub fn with_backend(config: FileStorageConfig, backend: Arc<dyn StorageBackend>) -> Self {
+        Self {
+            config,
+            backend,
+            hash_cache: Mutex::new(HashMap::new()),
+        }
     }
 
-    /// Store a file and return the stored file info
+    /// Store a file and return the stored file info.
+    ///
+    /// With content-addressed storage the file is kept once per content under
+    /// `objects/<ab>/<sha256>`; storing a payload that is already there writes nothing.
+    /// Files that would take the owner past their `quota` are rejected with `QuotaExceeded`.
     pub async fn store_file(
         &self,
         filename: &str,
         content: &[u8],
         session_id: Uuid,
+        quota: Option<StorageQuota>,
     ) -> Result<StoredFileInfo, FileStorageError> {
         // Validate file size
         if content.len() as u64 > self.config.max_file_size {
@@ -56,15 +103,20 @@ impl FileStorageService {
             ));
         }
 
-        // Guess MIME type from filename
-        let mime_type = mime_guess::from_path(filename)
-            .first_or_octet_stream()
-            .to_string();
+        if let Some(quota) = quota {
+            if quota.used_bytes + content.len() as u64 > quota.limit_bytes {
+                return Err(FileStorageError::QuotaExceeded(
+                    quota.used_bytes,
+                    quota.limit_bytes,
+                    content.len() as u64,
+                ));
+            }
+        }
 
         // Validate file type
-        if !self.config.allowed_types.contains(&mime_type) {
-            return Err(FileStorageError::InvalidFileType(mime_type));
-        }
+        let mime_type = self.allowed_content_type(filename)?;
+
+        let sha256 = format!("{:x}", Sha256::digest(content));
 
         // Generate unique filename to prevent conflicts
         let file_id = Uuid::new_v4();
@@ -74,76 +126,204 @@ impl FileStorageService {
             .unwrap_or("bin");
         let stored_filename = format!("{}_{}.{}", session_id, file_id, extension);
 
-        // Create session directory
-        let session_dir = self.base_path.join("sessions").join(session_id.to_string());
-        fs::create_dir_all(&session_dir).await?;
-
-        // Full file path
-        let file_path = session_dir.join(&stored_filename);
-
-        // Write file to disk
-        let mut file = fs::File::create(&file_path).await?;
-        file.write_all(content).await?;
-        file.flush().await?;
+        let (file_path, deduplicated) = if self.config.content_addressed {
+            let file_path = self
+                .backend
+                .location(&format!("objects/{}/{}", &sha256[..2], sha256));
 
-        tracing::info!(
-            "Stored file: {} ({} bytes) at {:?}",
-            stored_filename,
-            content.len(),
-            file_path
-        );
+            let deduplicated = self.backend.exists(&file_path).await?;
+            if !deduplicated {
+                self.backend
+                    .store(&file_path, Bytes::copy_from_slice(content))
+                    .await?;
+            }
+            (file_path, deduplicated)
+        } else {
+            let file_path = self
+                .backend
+                .location(&format!("sessions/{}/{}", session_id, stored_filename));
+            self.backend
+                .store(&file_path, Bytes::copy_from_slice(content))
+                .await?;
+            (file_path, false)
+        };
+
+        if deduplicated {
+            tracing::info!(
+                "Stored file: {} ({} bytes) reuses {}",
+                stored_filename,
+                content.len(),
+                file_path
+            );
+        } else {
+            tracing::info!(
+                "Stored file: {} ({} bytes) at {}",
+                stored_filename,
+                content.len(),
+                file_path
+            );
+        }
 
         Ok(StoredFileInfo {
             id: file_id,
             filename: stored_filename,
             original_filename: filename.to_string(),
-            file_path: file_path.to_string_lossy().to_string(),
+            file_path,
             content_type: mime_type,
             file_size: content.len() as u64,
+            sha256,
+            deduplicated,
         })
     }
 
+    /// MIME type for a filename, if it is one of the allowed types
+    pub fn allowed_content_type(&self, filename: &str) -> Result<String, FileStorageError> {
+        let mime_type = mime_guess::from_path(filename)
+            .first_or_octet_stream()
+            .to_string();
+
+        if !self.config.allowed_types.contains(&mime_type) {
+            return Err(FileStorageError::InvalidFileType(mime_type));
+        }
+
+        Ok(mime_type)
+    }
+
     /// Read a file from storage
     pub async fn read_file(&self, file_path: &str) -> Result<Vec<u8>, FileStorageError> {
-        let path = Path::new(file_path);
+        let chunks: Vec<Bytes> = self
+            .read_stream(file_path, None)
+            .await?
+            .try_collect()
+            .await?;
+        Ok(chunks.concat())
+    }
+
+    /// Stream a file from storage, or only the bytes in `range`
+    pub async fn read_stream(
+        &self,
+        file_path: &str,
+        range: Option<Range<u64>>,
+    ) -> Result<ByteStream, FileStorageError> {
+        self.backend.read_stream(file_path, range).await
+    }
 
-        // Security check - ensure path is within base directory
-        if !path.starts_with(&self.base_path) {
-            return Err(FileStorageError::InvalidPath(file_path.to_string()));
+    /// Open a file from storage for streaming
+    pub async fn open_file(
+        &self,
+        file_path: &str,
+    ) -> Result<impl AsyncRead + Unpin + Send, FileStorageError> {
+        Ok(StreamReader::new(self.read_stream(file_path, None).await?))
+    }
+
+    /// Hex SHA-256 of a file's contents, streamed from storage and cached until the file changes
+    pub async fn content_hash(&self, file_path: &str) -> Result<String, FileStorageError> {
+        let metadata = self.backend.metadata(file_path).await?;
+
+        if let Some(cached) = self.hash_cache.lock().unwrap().get(file_path) {
+            if cached.size == metadata.size && cached.modified == metadata.modified {
+                return Ok(cached.sha256.clone());
+            }
         }
 
-        if !path.exists() {
-            return Err(FileStorageError::FileNotFound(file_path.to_string()));
+        let sha256 = self.hash_file(file_path).await?;
+
+        let mut cache = self.hash_cache.lock().unwrap();
+        if cache.len() >= MAX_CACHED_HASHES {
+            cache.clear();
         }
+        cache.insert(
+            file_path.to_string(),
+            CachedHash {
+                size: metadata.size,
+                modified: metadata.modified,
+                sha256: sha256.clone(),
+            },
+        );
+
+        Ok(sha256)
+    }
+
+    /// Hex SHA-256 of a file's contents, always read from storage (for integrity checks)
+    pub async fn hash_file(&self, file_path: &str) -> Result<String, FileStorageError> {
+        let mut stream = self.read_stream(file_path, None).await?;
 
-        let mut file = fs::File::open(path).await?;
-        let mut contents = Vec::new();
-        file.read_to_end(&mut contents).await?;
+        let mut hasher = Sha256::new();
+        while let Some(chunk) = stream.try_next().await? {
+            hasher.update(&chunk);
+        }
 
-        Ok(contents)
+        Ok(format!("{:x}", hasher.finalize()))
     }
 
-    /// Delete a file from storage
+    /// Delete a file from storage, together with any renditions generated from it
     pub async fn delete_file(&self, file_path: &str) -> Result<(), FileStorageError> {
-        let path = Path::new(file_path);
+        for size in [ImageRendition::Thumb, ImageRendition::Preview] {
+            self.backend
+                .delete(&rendition_path(file_path, size))
+                .await?;
+        }
+
+        self.backend.delete(file_path).await?;
+        self.hash_cache.lock().unwrap().remove(file_path);
+        tracing::info!("Deleted file: {}", file_path);
 
-        // Security check - ensure path is within base directory
-        if !path.starts_with(&self.base_path) {
-            return Err(FileStorageError::InvalidPath(file_path.to_string()));
+        Ok(())
+    }
+
+    /// Location of a downscaled WebP rendition of an image, generating and storing it on
+    /// first use
+    pub async fn get_or_create_rendition(
+        &self,
+        file_path: &str,
+        size: ImageRendition,
+    ) -> Result<String, FileStorageError> {
+        let rendition = rendition_path(file_path, size);
+        if self.backend.exists(&rendition).await? {
+            return Ok(rendition);
         }
 
-        if path.exists() {
-            fs::remove_file(path).await?;
-            tracing::info!("Deleted file: {:?}", path);
+        let original = self.read_file(file_path).await?;
+        let max_dimension = rendition_max_dimension(size);
+        let encoded = tokio::task::spawn_blocking(move || render_webp(&original, max_dimension))
+            .await
+            .map_err(|e| FileStorageError::ImageProcessing(e.to_string()))??;
+        let encoded_size = encoded.len();
+
+        self.backend.store(&rendition, Bytes::from(encoded)).await?;
+
+        tracing::info!(
+            "Generated {:?} rendition ({} bytes) at {}",
+            size,
+            encoded_size,
+            rendition
+        );
+
+        Ok(rendition)
+    }
+
+    /// Write `content` to `file_path` again if it is missing from storage, e.g. because a
+    /// content-addressed file was deleted while an upload was reusing it. Returns whether the
+    /// file had to be written.
+    pub async fn restore_file(
+        &self,
+        file_path: &str,
+        content: &[u8],
+    ) -> Result<bool, FileStorageError> {
+        if self.backend.exists(file_path).await? {
+            return Ok(false);
         }
 
-        Ok(())
+        self.backend
+            .store(file_path, Bytes::copy_from_slice(content))
+            .await?;
+        tracing::warn!("Rewrote missing file {}", file_path);
+        Ok(true)
     }
 
     /// Check if a file exists
     pub async fn file_exists(&self, file_path: &str) -> bool {
-        let path = Path::new(file_path);
-        path.exists() && path.is_file()
+        self.backend.exists(file_path).await.unwrap_or(false)
     }
 
     /// Get file metadata
@@ -151,101 +331,139 @@ impl FileStorageService {
         &self,
         file_path: &str,
     ) -> Result<FileMetadata, FileStorageError> {
-        let path = Path::new(file_path);
-
-        // Security check
-        if !path.starts_with(&self.base_path) {
-            return Err(FileStorageError::InvalidPath(file_path.to_string()));
-        }
-
-        if !path.exists() {
-            return Err(FileStorageError::FileNotFound(file_path.to_string()));
-        }
-
-        let metadata = fs::metadata(path).await?;
-        let mime_type = mime_guess::from_path(path)
+        let metadata = self.backend.metadata(file_path).await?;
+        let mime_type = mime_guess::from_path(file_path)
             .first_or_octet_stream()
             .to_string();
 
         Ok(FileMetadata {
-            size: metadata.len(),
+            size: metadata.size,
             content_type: mime_type,
-            modified: metadata
-                .modified()
-                .ok()
-                .map(|time| chrono::DateTime::<chrono::Utc>::from(time)),
+            modified: metadata.modified,
         })
     }
 
-    /// Clean up old files (for maintenance)
-    pub async fn cleanup_old_files(&self, days_old: u64) -> Result<usize, FileStorageError> {
-        use std::time::{Duration, SystemTime};
-
-        let cutoff = SystemTime::now() - Duration::from_secs(days_old * 24 * 60 * 60);
-        let mut deleted_count = 0;
-
-        let mut entries = fs::read_dir(&self.base_path).await?;
-
-        while let Some(entry) = entries.next_entry().await? {
-            let metadata = entry.metadata().await?;
+    /// Delete stored files, at any depth, that are older than `older_than` and not in
+    /// `referenced`. With `dry_run` set, orphans are only reported.
+    ///
+    /// If none of the referenced files are in storage, storage is most likely configured with
+    /// a different location than the one the images were stored under, so nothing is deleted.
+    pub async fn cleanup_old_files(
+        &self,
+        older_than: DateTime<Utc>,
+        referenced: &HashSet<String>,
+        dry_run: bool,
+    ) -> Result<CleanupResult, FileStorageError> {
+        let files = self.list_files().await?;
+
+        let orphans: Vec<ObjectInfo> = files
+            .iter()
+            .filter(|file| !referenced.contains(&file.location))
+            .filter(|file| file.modified.is_some_and(|modified| modified < older_than))
+            .cloned()
+            .collect();
+
+        let storage_matches =
+            referenced.is_empty() || files.iter().any(|file| referenced.contains(&file.location));
+        if !storage_matches {
+            tracing::error!(
+                "None of the referenced files are in storage; not deleting {} unreferenced file(s)",
+                orphans.len()
+            );
+        }
 
-            if metadata.is_file() {
-                if let Ok(modified) = metadata.modified() {
-                    if modified < cutoff {
-                        if let Err(e) = fs::remove_file(entry.path()).await {
-                            tracing::warn!("Failed to delete old file {:?}: {}", entry.path(), e);
-                        } else {
-                            deleted_count += 1;
-                        }
+        let mut deleted = 0;
+        if !dry_run && storage_matches {
+            for orphan in &orphans {
+                match self.backend.delete(&orphan.location).await {
+                    Ok(()) => deleted += 1,
+                    Err(e) => {
+                        tracing::warn!("Failed to delete old file {}: {}", orphan.location, e)
                     }
                 }
             }
         }
 
-        tracing::info!("Cleaned up {} old files", deleted_count);
-        Ok(deleted_count)
+        if deleted > 0 {
+            tracing::info!("Cleaned up {} old files", deleted);
+        }
+        Ok(CleanupResult {
+            files,
+            orphans,
+            deleted,
+        })
+    }
+
+    /// Every stored file, including renditions
+    pub async fn list_files(&self) -> Result<Vec<ObjectInfo>, FileStorageError> {
+        self.backend.list("").await
     }
 
     /// Get storage statistics
     pub async fn get_storage_stats(&self) -> Result<StorageStats, FileStorageError> {
-        let mut total_files = 0;
-        let mut total_size = 0;
-
-        fn calculate_dir_size(path: &Path) -> std::io::Result<(u64, u64)> {
-            let mut file_count = 0;
-            let mut size = 0;
-
-            for entry in std::fs::read_dir(path)? {
-                let entry = entry?;
-                let metadata = entry.metadata()?;
-
-                if metadata.is_file() {
-                    file_count += 1;
-                    size += metadata.len();
-                } else if metadata.is_dir() {
-                    let (sub_files, sub_size) = calculate_dir_size(&entry.path())?;
-                    file_count += sub_files;
-                    size += sub_size;
-                }
-            }
-
-            Ok((file_count, size))
-        }
-
-        if self.base_path.exists() {
-            let (files, size) = calculate_dir_size(&self.base_path)?;
-            total_files = files;
-            total_size = size;
-        }
+        let files = self.list_files().await?;
+        let disk_space = self.backend.disk_space().await?;
 
         Ok(StorageStats {
-            total_files,
-            total_size_bytes: total_size,
-            available_space_bytes: None, // TODO: Implement disk space check
+            total_files: files.len() as u64,
+            total_size_bytes: files.iter().map(|file| file.size).sum(),
+            total_space_bytes: disk_space.map(|space| space.total_bytes),
+            available_space_bytes: disk_space.map(|space| space.available_bytes),
         })
     }
 }
 
+/// Renditions live in a `renditions` directory next to their original
+pub fn rendition_path(original: &str, size: ImageRendition) -> String {
+    let original = Path::new(original);
+    let stem = original
+        .file_stem()
+        .map(|stem| stem.to_string_lossy())
+        .unwrap_or_default();
+    let suffix = size.as_str();
+
+    original
+        .parent()
+        .unwrap_or(Path::new(""))
+        .join("renditions")
+        .join(format!("{}_{}.webp", stem, suffix))
+        .to_string_lossy()
+        .to_string()
+}
+
+fn rendition_max_dimension(size: ImageRendition) -> u32 {
+    match size {
+        ImageRendition::Thumb => 256,
+        ImageRendition::Preview => 1280,
+    }
+}
+
+/// Decode an image, scale it to fit within `max_dimension` (never up) and encode it as WebP
+fn render_webp(original: &[u8], max_dimension: u32) -> Result<Vec<u8>, FileStorageError> {
+    let decoded = image::load_from_memory(original)
+        .map_err(|e| FileStorageError::ImageProcessing(e.to_string()))?;
+
+    let scaled = if decoded.width() > max_dimension || decoded.height() > max_dimension {
+        decoded.thumbnail(max_dimension, max_dimension)
+    } else {
+        decoded
+    };
+
+    // The WebP encoder only takes 8-bit RGB(A); microscope TIFFs are often 16-bit or grey
+    let scaled = if scaled.color().has_alpha() {
+        image::DynamicImage::ImageRgba8(scaled.to_rgba8())
+    } else {
+        image::DynamicImage::ImageRgb8(scaled.to_rgb8())
+    };
+
+    let mut encoded = Vec::new();
+    scaled
+        .write_with_encoder(image::codecs::webp::WebPEncoder::new_lossless(&mut encoded))
+        .map_err(|e| FileStorageError::ImageProcessing(e.to_string()))?;
+
+    Ok(encoded)
+}
+
 #[derive(Debug, Clone)]
 pub struct StoredFileInfo {
     pub id: Uuid,
@@ -254,6 +472,10 @@ pub struct StoredFileInfo {
     pub file_path: String,
     pub content_type: String,
     pub file_size: u64,
+    /// Hex SHA-256 of the content
+    pub sha256: String,
+    /// The content was already stored (content-addressed storage only)
+    pub deduplicated: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -263,9 +485,28 @@ pub struct FileMetadata {
     pub modified: Option<chrono::DateTime<chrono::Utc>>,
 }
 
+/// Outcome of `FileStorageService::cleanup_old_files`
+#[derive(Debug, Clone)]
+pub struct CleanupResult {
+    /// Every file found in storage
+    pub files: Vec<ObjectInfo>,
+    /// Unreferenced files older than the cutoff
+    pub orphans: Vec<ObjectInfo>,
+    pub deleted: u64,
+}
+
 #[derive(Debug, Clone)]
 pub struct StorageStats {
     pub total_files: u64,
     pub total_size_bytes: u64,
+    /// Size of the filesystem files are stored on; None for object stores
+    pub total_space_bytes: Option<u64>,
     pub available_space_bytes: Option<u64>,
 }
+
+/// Storage a user has used and may use, checked by `store_file` before anything is written
+#[derive(Debug, Clone, Copy)]
+pub struct StorageQuota {
+    pub used_bytes: u64,
+    pub limit_bytes: u64,
+}
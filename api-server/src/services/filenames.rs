@@ -0,0 +1,73 @@
+use axum::http::HeaderValue;
+
+/// Longest stem kept from a client-supplied filename
+const MAX_STEM_LENGTH: usize = 100;
+
+/// Reduce a client-supplied filename to a stem of ASCII letters, digits, `-`, `_` and `.`,
+/// so it is safe in storage paths, archives and headers. Other characters become `_`;
+/// a name with nothing usable left becomes `upload`.
+pub fn sanitize_stem(filename: &str) -> String {
+    // Clients may send a full path (older browsers do on Windows)
+    let name = filename.rsplit(['/', '\\']).next().unwrap_or_default();
+    let stem = match name.rsplit_once('.') {
+        Some((stem, _)) if !stem.is_empty() => stem,
+        _ => name,
+    };
+
+    let sanitized: String = stem
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' | '.' => c,
+            _ => '_',
+        })
+        .take(MAX_STEM_LENGTH)
+        .collect();
+    let sanitized = sanitized.trim_matches(['.', '_']);
+
+    if sanitized.is_empty() {
+        "upload".to_string()
+    } else {
+        sanitized.to_string()
+    }
+}
+
+/// `Content-Disposition` value for `filename` (RFC 6266): an ASCII `filename` fallback for
+/// old clients and the exact name as UTF-8 in `filename*`
+pub fn content_disposition(disposition: &'static str, filename: &str) -> HeaderValue {
+    let fallback: String = filename
+        .chars()
+        .map(|c| match c {
+            ' '..='~' if c != '"' && c != '\\' => c,
+            _ => '_',
+        })
+        .collect();
+
+    let mut encoded = String::with_capacity(filename.len());
+    for byte in filename.bytes() {
+        match byte {
+            // attr-char from RFC 5987
+            b'a'..=b'z'
+            | b'A'..=b'Z'
+            | b'0'..=b'9'
+            | b'!'
+            | b'#'
+            | b'$'
+            | b'&'
+            | b'+'
+            | b'-'
+            | b'.'
+            | b'^'
+            | b'_'
+            | b'`'
+            | b'|'
+            | b'~' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+
+    HeaderValue::from_str(&format!(
+        "{}; filename=\"{}\"; filename*=UTF-8''{}",
+        disposition, fallback, encoded
+    ))
+    .unwrap_or(HeaderValue::from_static(disposition))
+}
@@ -1,4 +1,5 @@
 use chrono::Utc;
+use reqwest::multipart::{Form, Part};
 use reqwest::Client;
 use serde_json::{json, Value};
 use std::time::Duration;
@@ -101,6 +102,7 @@ impl IAClient {
                 microscope_id: microscope_id.to_string(),
                 is_connected: true,
                 current_session: Some(Uuid::new_v4()),
+                presence: None,
                 position: Position {
                     x: 150.5,
                     y: 220.3,
@@ -148,54 +150,10 @@ impl IAClient {
         // Return mock data if mock mode is enabled
         if self.mock_mode {
             let timestamp = Utc::now().format("%Y%m%d_%H%M%S");
-            let mock_objects = vec![
-                DetectedObject {
-                    class_name: "cell".to_string(),
-                    confidence: 0.94,
-                    bounding_box: BoundingBox {
-                        x: 120.5,
-                        y: 85.3,
-                        width: 45.2,
-                        height: 48.7,
-                    },
-                },
-                DetectedObject {
-                    class_name: "cell".to_string(),
-                    confidence: 0.88,
-                    bounding_box: BoundingBox {
-                        x: 210.1,
-                        y: 150.6,
-                        width: 42.8,
-                        height: 44.3,
-                    },
-                },
-                DetectedObject {
-                    class_name: "bacteria".to_string(),
-                    confidence: 0.76,
-                    bounding_box: BoundingBox {
-                        x: 320.4,
-                        y: 200.9,
-                        width: 15.2,
-                        height: 18.5,
-                    },
-                },
-            ];
-
             return Ok(CaptureResponse {
                 image_id: Uuid::new_v4(),
                 filename: format!("microscope_{}_{}.jpg", microscope_id, timestamp),
-                metadata: ImageMetadata {
-                    objects_detected: mock_objects.clone(),
-                    classification_tags: vec![
-                        "biological_sample".to_string(),
-                        "cells".to_string(),
-                        "bacteria".to_string(),
-                    ],
-                    confidence_scores: mock_objects.iter().map(|obj| obj.confidence).collect(),
-                    focus_quality: Some(0.91),
-                    magnification: Some("400x".to_string()),
-                    lighting_conditions: Some("optimal".to_string()),
-                },
+                metadata: mock_metadata(),
             });
         }
 
@@ -270,6 +228,50 @@ impl IAClient {
         }
     }
 
+    /// Run object detection and classification on an image that was not captured by the
+    /// IA system (e.g. a manual upload)
+    pub async fn analyze_image(
+        &self,
+        microscope_id: &str,
+        filename: &str,
+        content_type: &str,
+        content: Vec<u8>,
+    ) -> Result<ImageMetadata, IAClientError> {
+        // Return mock data if mock mode is enabled
+        if self.mock_mode {
+            tracing::info!(
+                "Mock: Analyzing image {} for microscope {}",
+                filename,
+                microscope_id
+            );
+            return Ok(mock_metadata());
+        }
+
+        let url = format!("{}/api/microscope/{}/analyze", self.base_url, microscope_id);
+
+        let part = Part::bytes(content)
+            .file_name(filename.to_string())
+            .mime_str(content_type)?;
+        let mut request = self
+            .client
+            .post(&url)
+            .multipart(Form::new().part("file", part));
+
+        if let Some(token) = &self.auth_token {
+            request = request.header("Authorization", format!("Bearer {}", token));
+        }
+
+        let response = request.send().await?;
+
+        if response.status().is_success() {
+            let metadata: ImageMetadata = response.json().await?;
+            Ok(metadata)
+        } else {
+            let error_text = response.text().await?;
+            Err(IAClientError::IAError(error_text))
+        }
+    }
+
     /// Upload image metadata to IA system
     pub async fn upload_metadata(
         &self,
@@ -350,3 +352,53 @@ impl IAClient {
         Ok(())
     }
 }
+
+/// Analysis results returned in mock mode
+fn mock_metadata() -> ImageMetadata {
+    let mock_objects = vec![
+        DetectedObject {
+            class_name: "cell".to_string(),
+            confidence: 0.94,
+            bounding_box: BoundingBox {
+                x: 120.5,
+                y: 85.3,
+                width: 45.2,
+                height: 48.7,
+            },
+        },
+        DetectedObject {
+            class_name: "cell".to_string(),
+            confidence: 0.88,
+            bounding_box: BoundingBox {
+                x: 210.1,
+                y: 150.6,
+                width: 42.8,
+                height: 44.3,
+            },
+        },
+        DetectedObject {
+            class_name: "bacteria".to_string(),
+            confidence: 0.76,
+            bounding_box: BoundingBox {
+                x: 320.4,
+                y: 200.9,
+                width: 15.2,
+                height: 18.5,
+            },
+        },
+    ];
+
+    ImageMetadata {
+        objects_detected: mock_objects.clone(),
+        classification_tags: vec![
+            "biological_sample".to_string(),
+            "cells".to_string(),
+            "bacteria".to_string(),
+        ],
+        confidence_scores: mock_objects.iter().map(|obj| obj.confidence).collect(),
+        focus_quality: Some(0.91),
+        magnification: Some("400x".to_string()),
+        lighting_conditions: Some("optimal".to_string()),
+        ..ImageMetadata::default()
+    }
+}
@@ -0,0 +1,149 @@
+use std::io::Cursor;
+
+use chrono::NaiveDateTime;
+use exif::{In, Tag, Value};
+use image::{ImageFormat, ImageReader};
+use thiserror::Error;
+
+use crate::models::ImageMetadata;
+
+#[derive(Error, Debug)]
+pub enum ImageInfoError {
+    #[error("Unrecognised image format")]
+    UnknownFormat,
+
+    #[error("Content is {actual} but was declared as {declared}")]
+    FormatMismatch { declared: String, actual: String },
+
+    #[error("Image could not be decoded: {0}")]
+    Undecodable(#[from] image::ImageError),
+}
+
+/// Pixel sizes at or above this (600 dpi) are taken as nominal print resolutions
+const MIN_NOMINAL_PIXEL_SIZE_UM: f64 = 25_400.0 / 600.0;
+
+/// What was learned from decoding an image
+#[derive(Debug, Clone, Default)]
+pub struct ImageInfo {
+    pub width: u32,
+    pub height: u32,
+    pub exif: ExifInfo,
+}
+
+/// Acquisition details read from EXIF/TIFF tags
+#[derive(Debug, Clone, Default)]
+pub struct ExifInfo {
+    /// DateTimeOriginal (or DateTime), in the instrument's local time
+    pub acquired_at: Option<NaiveDateTime>,
+    /// Physical size of one pixel in micrometres, from X/YResolution and ResolutionUnit
+    pub pixel_size_x_um: Option<f64>,
+    pub pixel_size_y_um: Option<f64>,
+    /// Make and model of the camera
+    pub camera: Option<String>,
+    pub exposure_time_s: Option<f64>,
+}
+
+impl ExifInfo {
+    /// Record the acquisition details in an image's metadata
+    pub fn apply_to(self, metadata: &mut ImageMetadata) {
+        metadata.acquired_at = self.acquired_at;
+        metadata.pixel_size_x_um = self.pixel_size_x_um;
+        metadata.pixel_size_y_um = self.pixel_size_y_um;
+        metadata.camera = self.camera;
+        metadata.exposure_time_s = self.exposure_time_s;
+    }
+}
+
+/// Image format of `bytes`, from their magic number rather than any filename
+pub fn sniff(bytes: &[u8]) -> Result<ImageFormat, ImageInfoError> {
+    image::guess_format(bytes).map_err(|_| ImageInfoError::UnknownFormat)
+}
+
+/// Decode `bytes`, check they are in the `declared_type` format and read their EXIF tags.
+///
+/// The whole image is decoded, so this is CPU-bound; call it from a blocking task.
+pub fn inspect(bytes: &[u8], declared_type: &str) -> Result<ImageInfo, ImageInfoError> {
+    let format = sniff(bytes)?;
+    let actual_type = format.to_mime_type();
+    if !actual_type.eq_ignore_ascii_case(declared_type) {
+        return Err(ImageInfoError::FormatMismatch {
+            declared: declared_type.to_string(),
+            actual: actual_type.to_string(),
+        });
+    }
+
+    let decoded = ImageReader::with_format(Cursor::new(bytes), format).decode()?;
+
+    Ok(ImageInfo {
+        width: decoded.width(),
+        height: decoded.height(),
+        exif: read_exif(bytes, format),
+    })
+}
+
+/// EXIF is optional, so anything unreadable is simply left out
+fn read_exif(bytes: &[u8], format: ImageFormat) -> ExifInfo {
+    // BMP has no EXIF container
+    if format == ImageFormat::Bmp {
+        return ExifInfo::default();
+    }
+
+    let exif = match exif::Reader::new().read_from_container(&mut Cursor::new(bytes)) {
+        Ok(exif) => exif,
+        Err(e) => {
+            tracing::debug!("No EXIF data read from image: {}", e);
+            return ExifInfo::default();
+        }
+    };
+
+    let ascii = |tag: Tag| -> Option<String> {
+        match &exif.get_field(tag, In::PRIMARY)?.value {
+            Value::Ascii(values) => values
+                .first()
+                .map(|v| String::from_utf8_lossy(v).trim().to_string())
+                .filter(|v| !v.is_empty()),
+            _ => None,
+        }
+    };
+    let rational = |tag: Tag| -> Option<f64> {
+        match &exif.get_field(tag, In::PRIMARY)?.value {
+            Value::Rational(values) => values.first().map(|v| v.to_f64()),
+            _ => None,
+        }
+    };
+
+    let acquired_at = ascii(Tag::DateTimeOriginal)
+        .or_else(|| ascii(Tag::DateTime))
+        .and_then(|raw| NaiveDateTime::parse_from_str(&raw, "%Y:%m:%d %H:%M:%S").ok());
+
+    // ResolutionUnit: 2 = inch, 3 = centimetre; 1 (no unit) gives no physical size
+    let unit_um = match exif
+        .get_field(Tag::ResolutionUnit, In::PRIMARY)
+        .and_then(|f| f.value.get_uint(0))
+        .unwrap_or(2)
+    {
+        2 => Some(25_400.0),
+        3 => Some(10_000.0),
+        _ => None,
+    };
+    let pixel_size = |tag: Tag| {
+        let resolution = rational(tag).filter(|r| r.is_finite() && *r > 0.0)?;
+        let size_um = unit_um? / resolution;
+        // Cameras write a nominal print resolution (72-600 dpi) that says nothing about the sample
+        (size_um < MIN_NOMINAL_PIXEL_SIZE_UM).then_some(size_um)
+    };
+
+    let camera = match (ascii(Tag::Make), ascii(Tag::Model)) {
+        (Some(make), Some(model)) if model.starts_with(&make) => Some(model),
+        (Some(make), Some(model)) => Some(format!("{} {}", make, model)),
+        (make, model) => make.or(model),
+    };
+
+    ExifInfo {
+        acquired_at,
+        pixel_size_x_um: pixel_size(Tag::XResolution),
+        pixel_size_y_um: pixel_size(Tag::YResolution),
+        camera,
+        exposure_time_s: rational(Tag::ExposureTime),
+    }
+}
@@ -0,0 +1,60 @@
+use chrono::{Duration, Utc};
+use uuid::Uuid;
+
+use crate::AppState;
+
+/// Trashed images purged per database round trip
+const BATCH_SIZE: i64 = 100;
+
+/// Permanently delete images that have been in the trash for longer than
+/// `file_storage.trash_retention_days`. Returns the number of images purged.
+pub async fn purge_expired(state: &AppState) -> Result<u64, sqlx::Error> {
+    let cutoff = Utc::now() - Duration::days(state.config.file_storage.trash_retention_days);
+    let mut purged = 0;
+
+    loop {
+        let expired = state.db.list_expired_trash(cutoff, BATCH_SIZE).await?;
+        if expired.is_empty() {
+            break;
+        }
+
+        for image_id in expired {
+            if purge_image(state, image_id).await? {
+                purged += 1;
+            }
+        }
+    }
+
+    if purged > 0 {
+        tracing::info!("Purged {} image(s) from the trash", purged);
+    }
+    Ok(purged)
+}
+
+/// Delete a trashed image's row, and its file once no other image uses it. Returns false if
+/// the image is not in the trash.
+///
+/// A file that cannot be deleted is left for the reconciliation job to remove as an orphan.
+pub async fn purge_image(state: &AppState, image_id: Uuid) -> Result<bool, sqlx::Error> {
+    let mut tx = state.db.begin_transaction().await?;
+    let Some(file_path) = state.db.purge_image(&mut tx, image_id).await? else {
+        return Ok(false);
+    };
+
+    // The file stays locked until commit, so an upload of the same content either reuses it
+    // after this transaction or sees that it is gone and writes it again
+    if !state.db.is_file_referenced(&mut tx, &file_path).await? {
+        if let Err(e) = state.file_store.delete_file(&file_path).await {
+            tracing::warn!(
+                "Failed to delete file {} of purged image {}: {}",
+                file_path,
+                image_id,
+                e
+            );
+        }
+    }
+    tx.commit().await?;
+
+    tracing::info!("Purged image {} from the trash", image_id);
+    Ok(true)
+}
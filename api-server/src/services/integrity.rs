@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+
+use chrono::Utc;
+
+use crate::{
+    models::{IntegrityIssue, IntegrityReport},
+    AppState,
+};
+
+/// Images loaded from the database at a time
+const BATCH_SIZE: i64 = 500;
+
+/// Re-hash every image file and compare it with the checksum recorded for the image.
+///
+/// Files are read from disk rather than the hash cache, so corruption that keeps a file's size
+/// and modification time is still found. Images without a checksum get the current one recorded.
+pub async fn verify_images(state: &AppState) -> Result<IntegrityReport, sqlx::Error> {
+    let started_at = Utc::now();
+    let mut report = IntegrityReport {
+        started_at,
+        finished_at: started_at,
+        checked: 0,
+        ok: 0,
+        hashed: 0,
+        missing: Vec::new(),
+        corrupted: Vec::new(),
+    };
+
+    // With content-addressed storage several images share a file; hash it only once
+    let mut hashes: HashMap<String, Option<String>> = HashMap::new();
+    let mut after = None;
+
+    loop {
+        let batch = state.db.list_image_files(after, BATCH_SIZE).await?;
+        let Some(last_id) = batch.last().map(|record| record.image_id) else {
+            break;
+        };
+        after = Some(last_id);
+
+        for record in batch {
+            report.checked += 1;
+
+            let actual = match hashes.get(&record.file_path) {
+                Some(actual) => actual.clone(),
+                None => {
+                    let actual = match state.file_store.hash_file(&record.file_path).await {
+                        Ok(actual) => Some(actual),
+                        Err(e) => {
+                            tracing::debug!("Could not hash {}: {}", record.file_path, e);
+                            None
+                        }
+                    };
+                    hashes.insert(record.file_path.clone(), actual.clone());
+                    actual
+                }
+            };
+
+            match (actual, record.sha256.as_deref()) {
+                (None, expected) => {
+                    tracing::warn!(
+                        "Image {} is missing its file {}",
+                        record.image_id,
+                        record.file_path
+                    );
+                    report.missing.push(IntegrityIssue {
+                        image_id: record.image_id,
+                        session_id: record.session_id,
+                        file_path: record.file_path,
+                        expected_sha256: expected.map(str::to_string),
+                        actual_sha256: None,
+                    });
+                }
+                (Some(actual), None) => {
+                    state.db.set_image_sha256(record.image_id, &actual).await?;
+                    report.hashed += 1;
+                }
+                (Some(actual), Some(expected)) if actual == expected => report.ok += 1,
+                (Some(actual), Some(expected)) => {
+                    tracing::error!(
+                        "Image {} file {} is corrupted: expected SHA-256 {}, found {}",
+                        record.image_id,
+                        record.file_path,
+                        expected,
+                        actual
+                    );
+                    report.corrupted.push(IntegrityIssue {
+                        image_id: record.image_id,
+                        session_id: record.session_id,
+                        file_path: record.file_path,
+                        expected_sha256: Some(expected.to_string()),
+                        actual_sha256: Some(actual),
+                    });
+                }
+            }
+        }
+    }
+
+    report.finished_at = Utc::now();
+    tracing::info!(
+        "Verified {} image file(s): {} ok, {} newly hashed, {} missing, {} corrupted",
+        report.checked,
+        report.ok,
+        report.hashed,
+        report.missing.len(),
+        report.corrupted.len()
+    );
+
+    Ok(report)
+}
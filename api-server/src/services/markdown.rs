@@ -0,0 +1,73 @@
+use pulldown_cmark::{html, CowStr, Event, Options, Parser, Tag};
+
+/// Render user-written Markdown to HTML that is safe to embed in the UI and reports.
+///
+/// Supports the common subset (emphasis, lists, tables, code, links). Raw HTML is
+/// escaped rather than passed through, and links or images with a script-capable
+/// scheme are neutralised.
+pub fn render(markdown: &str) -> String {
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_TABLES);
+    options.insert(Options::ENABLE_STRIKETHROUGH);
+
+    let events = Parser::new_ext(markdown, options).map(|event| match event {
+        Event::Html(raw) | Event::InlineHtml(raw) => Event::Text(raw),
+        Event::Start(Tag::Link {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) => Event::Start(Tag::Link {
+            link_type,
+            dest_url: safe_url(dest_url),
+            title,
+            id,
+        }),
+        Event::Start(Tag::Image {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) => Event::Start(Tag::Image {
+            link_type,
+            dest_url: safe_url(dest_url),
+            title,
+            id,
+        }),
+        other => other,
+    });
+
+    let mut output = String::with_capacity(markdown.len() * 3 / 2);
+    html::push_html(&mut output, events);
+    output
+}
+
+fn safe_url(url: CowStr<'_>) -> CowStr<'_> {
+    let scheme = url
+        .split_once(':')
+        .map(|(scheme, _)| scheme.trim().to_ascii_lowercase());
+
+    match scheme.as_deref() {
+        // Relative links and fragments have no scheme (or a path containing ':')
+        None => url,
+        Some("http" | "https" | "mailto") => url,
+        Some(s) if s.contains('/') || s.contains('#') || s.contains('?') => url,
+        Some(_) => CowStr::Borrowed("#"),
+    }
+}
+
+/// Escape plain text for embedding in HTML
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
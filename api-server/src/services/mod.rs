@@ -1,6 +1,23 @@
+pub mod analytics;
+pub mod approval_rules;
+pub mod conditional;
 pub mod database;
 pub mod file_storage;
+pub mod filenames;
 pub mod ia_client;
+pub mod image_info;
+pub mod image_trash;
+pub mod integrity;
+pub mod markdown;
+pub mod reconciliation;
+pub mod retention;
+pub mod scheduler;
+pub mod session_export;
+pub mod session_lifecycle;
+pub mod session_timeline;
+pub mod signed_url;
+pub mod storage_backend;
+pub mod storage_quota;
 
 pub use database::DatabaseService;
 pub use file_storage::FileStorageService;
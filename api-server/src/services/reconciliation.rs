@@ -0,0 +1,116 @@
+use std::collections::HashSet;
+
+use chrono::{Duration, Utc};
+
+use crate::{
+    models::{ImageRendition, IntegrityIssue, OrphanFile, ReconciliationReport},
+    services::file_storage::rendition_path,
+    AppError, AppState,
+};
+
+/// Images loaded from the database at a time
+const BATCH_SIZE: i64 = 500;
+
+/// Files are stored before their image row is written, so recent files are never orphans
+const ORPHAN_GRACE_HOURS: i64 = 24;
+
+/// Compare stored files with the images that reference them.
+///
+/// Finds files no image (trashed or not) references, such as those left behind when a
+/// session is deleted, and image rows whose file is gone. Unless `dry_run` is set, orphan
+/// files are deleted and the `stored_files` reference counts are recounted. Image rows with
+/// a missing file are only reported.
+pub async fn reconcile(state: &AppState, dry_run: bool) -> Result<ReconciliationReport, AppError> {
+    let started_at = Utc::now();
+    let grace_cutoff = started_at - Duration::hours(ORPHAN_GRACE_HOURS);
+
+    let references_fixed = if dry_run {
+        0
+    } else {
+        state.db.reconcile_file_references().await?
+    };
+
+    let mut records = Vec::new();
+    let mut referenced: HashSet<String> = HashSet::new();
+    let mut after = None;
+
+    loop {
+        let batch = state.db.list_image_files(after, BATCH_SIZE).await?;
+        let Some(last_id) = batch.last().map(|record| record.image_id) else {
+            break;
+        };
+        after = Some(last_id);
+
+        for record in batch {
+            for size in [ImageRendition::Thumb, ImageRendition::Preview] {
+                referenced.insert(rendition_path(&record.file_path, size));
+            }
+            referenced.insert(record.file_path.clone());
+            records.push(record);
+        }
+    }
+
+    let cleanup = state
+        .file_store
+        .cleanup_old_files(grace_cutoff, &referenced, dry_run)
+        .await?;
+    let stored: HashSet<&str> = cleanup
+        .files
+        .iter()
+        .map(|file| file.location.as_str())
+        .collect();
+
+    let dangling_images: Vec<IntegrityIssue> = records
+        .into_iter()
+        .filter(|record| !stored.contains(record.file_path.as_str()))
+        .map(|record| IntegrityIssue {
+            image_id: record.image_id,
+            session_id: record.session_id,
+            file_path: record.file_path,
+            expected_sha256: record.sha256,
+            actual_sha256: None,
+        })
+        .collect();
+
+    let orphan_files: Vec<OrphanFile> = cleanup
+        .orphans
+        .into_iter()
+        .map(|file| OrphanFile {
+            location: file.location,
+            size: file.size,
+            modified: file.modified,
+        })
+        .collect();
+
+    for image in &dangling_images {
+        tracing::warn!(
+            "Image {} references missing file {}",
+            image.image_id,
+            image.file_path
+        );
+    }
+
+    let report = ReconciliationReport {
+        started_at,
+        finished_at: Utc::now(),
+        dry_run,
+        files_scanned: cleanup.files.len() as u64,
+        orphan_bytes: orphan_files.iter().map(|file| file.size).sum(),
+        orphan_files,
+        deleted_files: cleanup.deleted,
+        dangling_images,
+        references_fixed,
+    };
+
+    tracing::info!(
+        "Reconciled storage{}: {} file(s) scanned, {} orphan(s), {} deleted, {} dangling image(s), {} reference count(s) fixed",
+        if dry_run { " (dry run)" } else { "" },
+        report.files_scanned,
+        report.orphan_files.len(),
+        report.deleted_files,
+        report.dangling_images.len(),
+        report.references_fixed
+    );
+
+    Ok(report)
+}
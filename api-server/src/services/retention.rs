@@ -0,0 +1,194 @@
+use chrono::{DateTime, Duration, Utc};
+
+use crate::{
+    models::{ExpiredImage, RetentionPolicy, RetentionReport, SessionEventType},
+    services::{database::RetentionCandidate, session_timeline},
+    AppError, AppState,
+};
+
+/// Images loaded from the database at a time
+const BATCH_SIZE: i64 = 500;
+
+/// Result of evaluating the retention policies for an image
+#[derive(Debug, Clone, PartialEq)]
+pub enum RetentionOutcome {
+    /// No policy applies, the policy keeps images forever, or the period has not ended
+    Keep,
+    /// Past the retention period, but starred or exported and the policy keeps those
+    Exempt,
+    /// Past the retention period of the policy
+    Expired {
+        policy_index: usize,
+        expired_at: DateTime<Utc>,
+    },
+}
+
+/// Evaluate policies in ascending priority; the first enabled match decides.
+/// Images no policy matches are kept.
+pub fn evaluate(
+    policies: &[RetentionPolicy],
+    candidate: &RetentionCandidate,
+    now: DateTime<Utc>,
+) -> RetentionOutcome {
+    let mut ordered: Vec<(usize, &RetentionPolicy)> = policies
+        .iter()
+        .enumerate()
+        .filter(|(_, p)| p.enabled)
+        .collect();
+    ordered.sort_by_key(|(_, p)| p.priority);
+
+    let Some((policy_index, policy)) = ordered
+        .into_iter()
+        .find(|(_, policy)| policy_matches(policy, candidate))
+    else {
+        return RetentionOutcome::Keep;
+    };
+
+    let Some(expired_at) = expires_at(policy, candidate) else {
+        return RetentionOutcome::Keep;
+    };
+    if expired_at > now {
+        return RetentionOutcome::Keep;
+    }
+
+    if (policy.keep_starred && candidate.starred) || (policy.keep_exported && candidate.exported) {
+        return RetentionOutcome::Exempt;
+    }
+
+    RetentionOutcome::Expired {
+        policy_index,
+        expired_at,
+    }
+}
+
+/// Check every condition set on the policy against the image
+pub fn policy_matches(policy: &RetentionPolicy, candidate: &RetentionCandidate) -> bool {
+    if !policy.owner_roles.is_empty() && !policy.owner_roles.contains(&candidate.owner_role) {
+        return false;
+    }
+
+    if let Some(microscope_id) = &policy.microscope_id {
+        if *microscope_id != candidate.microscope_id {
+            return false;
+        }
+    }
+
+    true
+}
+
+/// End of the image's retention period under the policy, or None if it is kept forever.
+/// Periods counted from the end of term start at midnight UTC after the term's last day.
+pub fn expires_at(
+    policy: &RetentionPolicy,
+    candidate: &RetentionCandidate,
+) -> Option<DateTime<Utc>> {
+    let retain = Duration::days(i64::from(policy.retain_days?));
+
+    let start = if policy.after_term_end {
+        let term_end = candidate.term_end?;
+        term_end.succ_opt()?.and_hms_opt(0, 0, 0)?.and_utc()
+    } else {
+        candidate.captured_at
+    };
+
+    Some(start + retain)
+}
+
+/// Apply the retention policies to every image outside the trash.
+///
+/// Unless `dry_run` is set, expired images are moved to the trash with no user recorded as
+/// deleting them. The trash purge job later deletes their rows and files together, and they
+/// can be restored until then.
+pub async fn apply_policies(state: &AppState, dry_run: bool) -> Result<RetentionReport, AppError> {
+    let started_at = Utc::now();
+    let policies = state.db.list_retention_policies().await?;
+
+    let mut checked = 0;
+    let mut exempted = 0;
+    let mut expired = Vec::new();
+
+    if policies.iter().any(|policy| policy.enabled) {
+        let mut after = None;
+        loop {
+            let batch = state
+                .db
+                .list_retention_candidates(after, BATCH_SIZE, &state.config.booking.timezone)
+                .await?;
+            let Some(last_id) = batch.last().map(|candidate| candidate.image_id) else {
+                break;
+            };
+            after = Some(last_id);
+
+            for candidate in batch {
+                checked += 1;
+                match evaluate(&policies, &candidate, started_at) {
+                    RetentionOutcome::Keep => {}
+                    RetentionOutcome::Exempt => exempted += 1,
+                    RetentionOutcome::Expired {
+                        policy_index,
+                        expired_at,
+                    } => {
+                        let policy = &policies[policy_index];
+                        expired.push(ExpiredImage {
+                            image_id: candidate.image_id,
+                            session_id: candidate.session_id,
+                            filename: candidate.filename,
+                            file_size: candidate.file_size,
+                            captured_at: candidate.captured_at,
+                            policy_id: policy.id,
+                            policy_name: policy.name.clone(),
+                            expired_at,
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    if !dry_run {
+        for image in &expired {
+            if !state.db.trash_image(image.image_id, None).await? {
+                continue;
+            }
+
+            session_timeline::record(
+                state,
+                image.session_id,
+                SessionEventType::ImageDeleted,
+                None,
+                serde_json::json!({
+                    "image_id": image.image_id,
+                    "filename": image.filename,
+                    "retention_policy_id": image.policy_id,
+                    "retention_policy": image.policy_name,
+                }),
+            )
+            .await;
+        }
+    }
+
+    let report = RetentionReport {
+        started_at,
+        finished_at: Utc::now(),
+        dry_run,
+        checked,
+        exempted,
+        expired_bytes: expired
+            .iter()
+            .map(|image| image.file_size.max(0) as u64)
+            .sum(),
+        expired,
+    };
+
+    if dry_run || !report.expired.is_empty() {
+        tracing::info!(
+            "Applied retention policies{}: {} image(s) checked, {} expired, {} exempted",
+            if dry_run { " (dry run)" } else { "" },
+            report.checked,
+            report.expired.len(),
+            report.exempted
+        );
+    }
+
+    Ok(report)
+}
@@ -0,0 +1,188 @@
+use chrono::{Duration as ChronoDuration, Utc};
+use std::time::Duration;
+use tokio::{
+    task::JoinHandle,
+    time::{Instant, MissedTickBehavior},
+};
+use uuid::Uuid;
+
+use crate::{
+    models::{NotificationKind, SessionStatus},
+    services::{
+        image_trash, integrity, reconciliation, retention,
+        session_lifecycle::{self, EndSession},
+    },
+    AppState,
+};
+
+/// Spawn the background job loop, running every `scheduler.interval` seconds
+pub fn spawn(state: AppState) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let period = Duration::from_secs(state.config.scheduler.interval.max(1));
+        let mut ticker = tokio::time::interval(period);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            ticker.tick().await;
+            run_jobs(&state).await;
+        }
+    })
+}
+
+/// Run every scheduled job once. Failures are logged and retried on the next tick.
+pub async fn run_jobs(state: &AppState) {
+    match state.db.sync_microscope_maintenance_status().await {
+        Ok(0) => {}
+        Ok(changed) => tracing::info!("Updated maintenance status for {} microscope(s)", changed),
+        Err(e) => tracing::error!("Failed to sync microscope maintenance status: {}", e),
+    }
+
+    if let Err(e) = warn_sessions_ending_soon(state).await {
+        tracing::error!("Failed to send session end warnings: {}", e);
+    }
+
+    if let Err(e) = end_sessions_past_booking(state).await {
+        tracing::error!("Failed to end sessions past their booking: {}", e);
+    }
+
+    if let Err(e) = abort_idle_sessions(state).await {
+        tracing::error!("Failed to abort idle sessions: {}", e);
+    }
+}
+
+/// Spawn the storage maintenance loop, running every `scheduler.storage_interval` seconds
+/// starting one interval after startup. Returns `None` when storage jobs are disabled.
+pub fn spawn_storage_jobs(state: AppState) -> Option<JoinHandle<()>> {
+    let interval = state.config.scheduler.storage_interval;
+    if interval == 0 {
+        return None;
+    }
+
+    Some(tokio::spawn(async move {
+        let period = Duration::from_secs(interval);
+        let mut ticker = tokio::time::interval_at(Instant::now() + period, period);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            ticker.tick().await;
+            run_storage_jobs(&state).await;
+        }
+    }))
+}
+
+/// Run every storage maintenance job once. These read every stored file, so they run on their
+/// own loop rather than holding up the session jobs.
+pub async fn run_storage_jobs(state: &AppState) {
+    if let Err(e) = retention::apply_policies(state, false).await {
+        tracing::error!("Failed to apply retention policies: {}", e);
+    }
+    if let Err(e) = image_trash::purge_expired(state).await {
+        tracing::error!("Failed to purge the image trash: {}", e);
+    }
+    if let Err(e) = reconciliation::reconcile(state, false).await {
+        tracing::error!("Failed to reconcile stored files: {}", e);
+    }
+    if let Err(e) = integrity::verify_images(state).await {
+        tracing::error!("Failed to verify image files: {}", e);
+    }
+}
+
+/// Notify users whose booking ends within `session_end_warning_minutes`, once per session
+async fn warn_sessions_ending_soon(state: &AppState) -> Result<(), sqlx::Error> {
+    let config = &state.config.scheduler;
+    let now = Utc::now();
+    let cutoff = now + ChronoDuration::minutes(config.session_end_warning_minutes);
+
+    let sessions = state
+        .db
+        .get_sessions_with_booking_ending_before(cutoff, true, &state.config.booking.timezone)
+        .await?;
+
+    // Bookings that already ended are handled by `end_sessions_past_booking` instead
+    for deadline in sessions.into_iter().filter(|d| d.booking_ends_at > now) {
+        let message = format!(
+            "Your booking on {} ends at {:02}:{:02}. The session will be ended {} minute(s) after that.",
+            deadline.microscope_id,
+            deadline.slot_end / 60,
+            deadline.slot_end % 60,
+            config.session_grace_minutes
+        );
+
+        state
+            .db
+            .create_notification(
+                deadline.user_id,
+                NotificationKind::SessionEndingSoon,
+                &message,
+                Some(deadline.booking_id),
+                Some(deadline.session_id),
+            )
+            .await?;
+        state
+            .db
+            .mark_session_end_warning_sent(deadline.session_id)
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// End sessions whose booking ended more than `session_grace_minutes` ago
+async fn end_sessions_past_booking(state: &AppState) -> Result<(), sqlx::Error> {
+    let cutoff = Utc::now() - ChronoDuration::minutes(state.config.scheduler.session_grace_minutes);
+
+    let sessions = state
+        .db
+        .get_sessions_with_booking_ending_before(cutoff, false, &state.config.booking.timezone)
+        .await?;
+
+    for deadline in sessions {
+        let reason = format!(
+            "Booking ended at {:02}:{:02}",
+            deadline.slot_end / 60,
+            deadline.slot_end % 60
+        );
+        end_session(state, deadline.session_id, SessionStatus::Completed, reason).await;
+    }
+
+    Ok(())
+}
+
+/// Abort sessions with no microscope command or capture for `session_idle_minutes`
+async fn abort_idle_sessions(state: &AppState) -> Result<(), sqlx::Error> {
+    let idle_minutes = state.config.scheduler.session_idle_minutes;
+    if idle_minutes <= 0 {
+        return Ok(());
+    }
+
+    let idle_since = Utc::now() - ChronoDuration::minutes(idle_minutes);
+    for session_id in state.db.get_idle_session_ids(idle_since).await? {
+        let reason = format!("No microscope activity for {} minutes", idle_minutes);
+        end_session(state, session_id, SessionStatus::Aborted, reason).await;
+    }
+
+    Ok(())
+}
+
+/// End a session through the same path as the API, logging rather than propagating failures
+async fn end_session(state: &AppState, session_id: Uuid, status: SessionStatus, reason: String) {
+    let session = match state.db.get_session_by_id(session_id).await {
+        Ok(Some(session)) => session,
+        Ok(None) => return,
+        Err(e) => {
+            tracing::error!("Failed to load session {}: {}", session_id, e);
+            return;
+        }
+    };
+
+    let end = EndSession {
+        status,
+        notes: None,
+        ended_by: None,
+        reason: Some(reason),
+    };
+
+    if let Err(e) = session_lifecycle::end_session(state, &session, end).await {
+        tracing::error!("Failed to end session {}: {}", session_id, e);
+    }
+}
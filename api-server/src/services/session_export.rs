@@ -0,0 +1,405 @@
+use std::fmt::Write as _;
+use std::path::Path;
+
+use async_zip::error::ZipError;
+use async_zip::tokio::write::ZipFileWriter;
+use async_zip::{Compression, ZipEntryBuilder};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use thiserror::Error;
+use tokio::io::AsyncWrite;
+use tokio_util::compat::FuturesAsyncWriteCompatExt;
+use uuid::Uuid;
+
+use crate::{
+    models::{Image, ImageMetadata, Session, SessionNote, SessionNoteKind},
+    services::{
+        file_storage::{FileStorageError, FileStorageService},
+        markdown::escape_html,
+    },
+};
+
+#[derive(Error, Debug)]
+pub enum ExportError {
+    #[error("ZIP error: {0}")]
+    Zip(#[from] ZipError),
+
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+}
+
+/// Everything that goes into a session export
+#[derive(Debug, Clone)]
+pub struct SessionExport {
+    pub session: Session,
+    pub owner_name: String,
+    /// Co-participants and previous owners
+    pub participant_names: Vec<String>,
+    pub images: Vec<Image>,
+    pub notes: Vec<SessionNote>,
+}
+
+/// Image entry in `metadata.json`
+#[derive(Debug, Serialize)]
+struct ImageRecord<'a> {
+    image_id: Uuid,
+    /// Path of the image inside the archive (None if the file is missing from storage)
+    file: Option<String>,
+    content_type: &'a str,
+    file_size: i64,
+    sha256: Option<&'a str>,
+    width: Option<i32>,
+    height: Option<i32>,
+    captured_at: DateTime<Utc>,
+    metadata: &'a ImageMetadata,
+}
+
+/// Write the export as a ZIP archive to `writer`.
+///
+/// Image files are copied from storage one at a time, so memory use does not grow with the
+/// size of the session. The archive contains:
+/// - `images/` with every stored image file
+/// - `metadata.json` and `metadata.csv` with the image metadata
+/// - `notes.md` and `notes.json` with the notebook entries
+/// - `report.html`, a self-contained summary linking to the images
+pub async fn write_zip<W>(
+    writer: W,
+    file_store: &FileStorageService,
+    export: &SessionExport,
+) -> Result<(), ExportError>
+where
+    W: AsyncWrite + Unpin,
+{
+    let mut zip = ZipFileWriter::with_tokio(writer);
+    let mut archived: Vec<Option<String>> = Vec::with_capacity(export.images.len());
+
+    for image in &export.images {
+        let name = archive_image_name(image);
+        let mut file = match file_store.open_file(&image.file_path).await {
+            Ok(file) => file,
+            Err(FileStorageError::FileNotFound(_)) => {
+                tracing::warn!(
+                    "Image file {} for image {} is missing; leaving it out of the export",
+                    image.file_path,
+                    image.id
+                );
+                archived.push(None);
+                continue;
+            }
+            Err(e) => {
+                tracing::warn!(
+                    "Failed to open image file {} for export: {}",
+                    image.file_path,
+                    e
+                );
+                archived.push(None);
+                continue;
+            }
+        };
+
+        // Images are already compressed, so store them as-is
+        let entry = ZipEntryBuilder::new(name.clone().into(), Compression::Stored);
+        let mut entry_writer = zip.write_entry_stream(entry).await?.compat_write();
+        tokio::io::copy(&mut file, &mut entry_writer).await?;
+        entry_writer.into_inner().close().await?;
+        archived.push(Some(name));
+    }
+
+    let records: Vec<ImageRecord> = export
+        .images
+        .iter()
+        .zip(&archived)
+        .map(|(image, file)| ImageRecord {
+            image_id: image.id,
+            file: file.clone(),
+            content_type: &image.content_type,
+            file_size: image.file_size,
+            sha256: image.sha256.as_deref(),
+            width: image.width,
+            height: image.height,
+            captured_at: image.captured_at,
+            metadata: &image.metadata,
+        })
+        .collect();
+
+    write_text(
+        &mut zip,
+        "metadata.json",
+        &serde_json::to_string_pretty(&records)?,
+    )
+    .await?;
+    write_text(&mut zip, "metadata.csv", &metadata_csv(&records)).await?;
+    write_text(
+        &mut zip,
+        "notes.json",
+        &serde_json::to_string_pretty(&export.notes)?,
+    )
+    .await?;
+    write_text(&mut zip, "notes.md", &notes_markdown(export)).await?;
+    write_text(&mut zip, "report.html", &report_html(export, &archived)).await?;
+
+    zip.close().await?;
+    Ok(())
+}
+
+async fn write_text<W>(
+    zip: &mut ZipFileWriter<W>,
+    name: &str,
+    content: &str,
+) -> Result<(), ExportError>
+where
+    W: AsyncWrite + Unpin,
+{
+    let entry = ZipEntryBuilder::new(name.into(), Compression::Deflate);
+    zip.write_entry_whole(entry, content.as_bytes()).await?;
+    Ok(())
+}
+
+fn archive_image_name(image: &Image) -> String {
+    // Stored filenames are unique per session; strip any directory components
+    let filename = Path::new(&image.filename)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or("image");
+    format!("images/{}", filename)
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn metadata_csv(records: &[ImageRecord]) -> String {
+    let mut csv = String::from(
+        "image_id,file,captured_at,width,height,focus_quality,magnification,lighting_conditions,classification_tags,object_count,detected_classes\n",
+    );
+
+    for record in records {
+        let metadata = record.metadata;
+        let classes: Vec<&str> = metadata
+            .objects_detected
+            .iter()
+            .map(|o| o.class_name.as_str())
+            .collect();
+
+        let fields = [
+            record.image_id.to_string(),
+            record.file.clone().unwrap_or_default(),
+            record.captured_at.to_rfc3339(),
+            record.width.map(|w| w.to_string()).unwrap_or_default(),
+            record.height.map(|h| h.to_string()).unwrap_or_default(),
+            metadata
+                .focus_quality
+                .map(|f| f.to_string())
+                .unwrap_or_default(),
+            metadata.magnification.clone().unwrap_or_default(),
+            metadata.lighting_conditions.clone().unwrap_or_default(),
+            metadata.classification_tags.join(";"),
+            metadata.objects_detected.len().to_string(),
+            classes.join(";"),
+        ];
+
+        let line: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+        csv.push_str(&line.join(","));
+        csv.push('\n');
+    }
+
+    csv
+}
+
+fn note_heading(note: &SessionNote) -> String {
+    let kind = match note.kind {
+        SessionNoteKind::Text => "Note",
+        SessionNoteKind::Image => "Image note",
+        SessionNoteKind::Measurement => "Measurement",
+    };
+    format!(
+        "{} — {}",
+        kind,
+        note.created_at.format("%Y-%m-%d %H:%M:%S UTC")
+    )
+}
+
+fn measurement_text(note: &SessionNote) -> Option<String> {
+    note.measurement.as_ref().map(|m| match &m.unit {
+        Some(unit) => format!("{}: {} {}", m.name, m.value, unit),
+        None => format!("{}: {}", m.name, m.value),
+    })
+}
+
+fn notes_markdown(export: &SessionExport) -> String {
+    let mut md = format!(
+        "# Notebook — session {} on {}\n\n",
+        export.session.id, export.session.microscope_id
+    );
+
+    if export.notes.is_empty() {
+        md.push_str("_No notebook entries._\n");
+    }
+
+    for note in &export.notes {
+        let _ = writeln!(md, "## {}\n", note_heading(note));
+        if let Some(measurement) = measurement_text(note) {
+            let _ = writeln!(md, "**{}**\n", measurement);
+        }
+        if let Some(image_id) = note.image_id {
+            let _ = writeln!(md, "Image: `{}`\n", image_id);
+        }
+        if !note.body.trim().is_empty() {
+            let _ = writeln!(md, "{}\n", note.body.trim_end());
+        }
+    }
+
+    md
+}
+
+fn report_html(export: &SessionExport, archived: &[Option<String>]) -> String {
+    let session = &export.session;
+    let mut html = String::new();
+
+    let _ = write!(
+        html,
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>Session report {id}</title>
+<style>
+body {{ font-family: sans-serif; margin: 2em; color: #222; }}
+table {{ border-collapse: collapse; margin-bottom: 1.5em; }}
+th, td {{ border: 1px solid #ccc; padding: 4px 8px; text-align: left; vertical-align: top; }}
+.note {{ border-left: 3px solid #4a7; padding-left: 1em; margin-bottom: 1em; }}
+.meta {{ color: #666; font-size: 0.9em; }}
+img.thumb {{ max-width: 160px; max-height: 120px; }}
+</style>
+</head>
+<body>
+<h1>Session report</h1>
+<table>
+<tr><th>Session</th><td>{id}</td></tr>
+<tr><th>Microscope</th><td>{microscope}</td></tr>
+<tr><th>User</th><td>{owner}</td></tr>
+<tr><th>Participants</th><td>{participants}</td></tr>
+<tr><th>Status</th><td>{status:?}</td></tr>
+<tr><th>Started</th><td>{started}</td></tr>
+<tr><th>Ended</th><td>{ended}</td></tr>
+<tr><th>Images</th><td>{image_count}</td></tr>
+<tr><th>Notebook entries</th><td>{note_count}</td></tr>
+</table>
+"#,
+        id = session.id,
+        microscope = escape_html(&session.microscope_id),
+        owner = escape_html(&export.owner_name),
+        participants = if export.participant_names.is_empty() {
+            "—".to_string()
+        } else {
+            escape_html(&export.participant_names.join(", "))
+        },
+        status = session.status,
+        started = session.started_at.format("%Y-%m-%d %H:%M:%S UTC"),
+        ended = session
+            .ended_at
+            .map(|t| t.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+            .unwrap_or_else(|| "—".to_string()),
+        image_count = export.images.len(),
+        note_count = export.notes.len(),
+    );
+
+    if let Some(notes) = &session.notes {
+        let _ = write!(html, "<h2>Summary</h2>\n<p>{}</p>\n", escape_html(notes));
+    }
+    if let Some(reason) = &session.end_reason {
+        let _ = writeln!(
+            html,
+            "<p class=\"meta\">End reason: {}</p>",
+            escape_html(reason)
+        );
+    }
+
+    html.push_str("<h2>Notebook</h2>\n");
+    if export.notes.is_empty() {
+        html.push_str("<p class=\"meta\">No notebook entries.</p>\n");
+    }
+    for note in &export.notes {
+        let _ = write!(
+            html,
+            "<div class=\"note\">\n<p class=\"meta\">{}</p>\n",
+            escape_html(&note_heading(note))
+        );
+        if let Some(measurement) = measurement_text(note) {
+            let _ = writeln!(
+                html,
+                "<p><strong>{}</strong></p>",
+                escape_html(&measurement)
+            );
+        }
+        if let Some(file) = note.image_id.and_then(|id| {
+            export
+                .images
+                .iter()
+                .position(|image| image.id == id)
+                .and_then(|i| archived[i].as_ref())
+        }) {
+            let file = escape_html(file);
+            let _ = writeln!(
+                html,
+                "<p><a href=\"{file}\"><img class=\"thumb\" src=\"{file}\" alt=\"\"></a></p>"
+            );
+        }
+        html.push_str(&note.body_html);
+        html.push_str("</div>\n");
+    }
+
+    html.push_str("<h2>Images</h2>\n");
+    if export.images.is_empty() {
+        html.push_str("<p class=\"meta\">No images captured.</p>\n");
+    } else {
+        html.push_str(
+            "<table>\n<tr><th>Image</th><th>Captured</th><th>Focus</th><th>Tags</th><th>Detected objects</th></tr>\n",
+        );
+        for (image, file) in export.images.iter().zip(archived) {
+            let preview = match file {
+                Some(file) => {
+                    let file = escape_html(file);
+                    format!("<a href=\"{file}\"><img class=\"thumb\" src=\"{file}\" alt=\"\"></a>")
+                }
+                None => "<span class=\"meta\">file missing</span>".to_string(),
+            };
+            let objects: Vec<String> = image
+                .metadata
+                .objects_detected
+                .iter()
+                .map(|o| format!("{} ({:.0}%)", o.class_name, o.confidence * 100.0))
+                .collect();
+
+            let _ = writeln!(
+                html,
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                preview,
+                image.captured_at.format("%H:%M:%S"),
+                image
+                    .metadata
+                    .focus_quality
+                    .map(|f| format!("{:.2}", f))
+                    .unwrap_or_else(|| "—".to_string()),
+                escape_html(&image.metadata.classification_tags.join(", ")),
+                escape_html(&objects.join(", ")),
+            );
+        }
+        html.push_str("</table>\n");
+    }
+
+    let _ = write!(
+        html,
+        "<p class=\"meta\">Generated {}</p>\n</body>\n</html>\n",
+        Utc::now().format("%Y-%m-%d %H:%M:%S UTC")
+    );
+
+    html
+}
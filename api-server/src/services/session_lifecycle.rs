@@ -0,0 +1,113 @@
+use uuid::Uuid;
+
+use crate::{
+    models::{NotificationKind, Session, SessionEventType, SessionStatus},
+    services::session_timeline,
+    AppError, AppState,
+};
+
+/// How and by whom a session is being ended
+#[derive(Debug, Clone)]
+pub struct EndSession {
+    /// Final status: `Completed` for a normal end, `Aborted` when cut short
+    pub status: SessionStatus,
+    pub notes: Option<String>,
+    /// None when ended by the system (e.g. the scheduler)
+    pub ended_by: Option<Uuid>,
+    pub reason: Option<String>,
+}
+
+/// End an active session and tell the IA system the microscope is free.
+///
+/// This is the single path for ending sessions, used by the API and background jobs.
+/// The session owner is notified when someone else (or the system) ends it.
+/// Returns `Ok(None)` if the session was no longer active.
+pub async fn end_session(
+    state: &AppState,
+    session: &Session,
+    end: EndSession,
+) -> Result<Option<Session>, AppError> {
+    let Some(ended_session) = state
+        .db
+        .end_session(
+            session.id,
+            end.status,
+            end.notes,
+            end.ended_by,
+            end.reason.as_deref(),
+        )
+        .await?
+    else {
+        return Ok(None);
+    };
+
+    // Update microscope status in IA system
+    if let Err(e) = state
+        .ia_client
+        .update_session_status(&ended_session.microscope_id, None, false)
+        .await
+    {
+        tracing::warn!(
+            "Failed to update microscope status in IA system: {}. Session ended in database.",
+            e
+        );
+    }
+
+    session_timeline::record(
+        state,
+        ended_session.id,
+        SessionEventType::SessionEnded,
+        end.ended_by,
+        serde_json::json!({
+            "status": ended_session.status,
+            "reason": ended_session.end_reason,
+        }),
+    )
+    .await;
+
+    if end.ended_by != Some(ended_session.user_id) {
+        let action = match ended_session.status {
+            SessionStatus::Aborted => "aborted",
+            _ => "ended",
+        };
+        let message = match &ended_session.end_reason {
+            Some(reason) => format!(
+                "Your session on {} was {}: {}",
+                ended_session.microscope_id, action, reason
+            ),
+            None => format!(
+                "Your session on {} was {}.",
+                ended_session.microscope_id, action
+            ),
+        };
+
+        if let Err(e) = state
+            .db
+            .create_notification(
+                ended_session.user_id,
+                NotificationKind::SessionEnded,
+                &message,
+                ended_session.booking_id,
+                Some(ended_session.id),
+            )
+            .await
+        {
+            tracing::warn!(
+                "Failed to notify user {} about session {}: {}",
+                ended_session.user_id,
+                ended_session.id,
+                e
+            );
+        }
+    }
+
+    tracing::info!(
+        "Ended session {} on microscope {} as {:?} (ended by: {:?})",
+        ended_session.id,
+        ended_session.microscope_id,
+        ended_session.status,
+        ended_session.ended_by
+    );
+
+    Ok(Some(ended_session))
+}
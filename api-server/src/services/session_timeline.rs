@@ -0,0 +1,56 @@
+use uuid::Uuid;
+
+use crate::{models::SessionEventType, AppState};
+
+/// Append an event to a session's timeline.
+///
+/// The timeline is a record of what happened, so failing to write it is logged
+/// rather than failing the action itself.
+pub async fn record(
+    state: &AppState,
+    session_id: Uuid,
+    event_type: SessionEventType,
+    actor_id: Option<Uuid>,
+    details: serde_json::Value,
+) {
+    if let Err(e) = state
+        .db
+        .record_session_event(session_id, event_type, actor_id, details)
+        .await
+    {
+        tracing::warn!(
+            "Failed to record {:?} event for session {}: {}",
+            event_type,
+            session_id,
+            e
+        );
+    }
+}
+
+/// Append an event to the timeline of the microscope's active session, if there is one
+pub async fn record_on_microscope(
+    state: &AppState,
+    microscope_id: &str,
+    event_type: SessionEventType,
+    actor_id: Option<Uuid>,
+    details: serde_json::Value,
+) {
+    match state
+        .db
+        .record_microscope_session_event(microscope_id, event_type, actor_id, details)
+        .await
+    {
+        Ok(true) => {}
+        Ok(false) => tracing::debug!(
+            "No active session on microscope {} to record {:?} event",
+            microscope_id,
+            event_type
+        ),
+        Err(e) => tracing::warn!(
+            "Failed to record {:?} event on microscope {}: {}",
+            event_type,
+            microscope_id,
+            e
+        ),
+    }
+}
@@ -0,0 +1,63 @@
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use uuid::Uuid;
+
+use crate::models::ImageRendition;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Key for download URLs when `FILE_URL_SECRET` is not set, derived from the JWT secret so
+/// a signed URL can never double as a token signature (or the other way round)
+pub fn derive_secret(jwt_secret: &str) -> String {
+    let mut mac =
+        HmacSha256::new_from_slice(jwt_secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(b"file-urls");
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Signature over an image download: the image, which rendition (if any) and when it expires
+pub fn sign(
+    secret: &str,
+    image_id: Uuid,
+    size: Option<ImageRendition>,
+    expires: DateTime<Utc>,
+) -> String {
+    hex::encode(
+        mac(secret, image_id, size, expires.timestamp())
+            .finalize()
+            .into_bytes(),
+    )
+}
+
+/// Check a signature produced by [`sign`] and that it has not expired
+pub fn verify(
+    secret: &str,
+    image_id: Uuid,
+    size: Option<ImageRendition>,
+    expires: i64,
+    signature: &str,
+    now: DateTime<Utc>,
+) -> bool {
+    if expires < now.timestamp() {
+        return false;
+    }
+
+    let Ok(signature) = hex::decode(signature) else {
+        return false;
+    };
+
+    // verify_slice compares in constant time
+    mac(secret, image_id, size, expires)
+        .verify_slice(&signature)
+        .is_ok()
+}
+
+fn mac(secret: &str, image_id: Uuid, size: Option<ImageRendition>, expires: i64) -> HmacSha256 {
+    let size = size.map_or("original", |size| size.as_str());
+
+    let mut mac =
+        HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(format!("{}:{}:{}", image_id, size, expires).as_bytes());
+    mac
+}
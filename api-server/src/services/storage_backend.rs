@@ -0,0 +1,374 @@
+use std::io::SeekFrom;
+use std::ops::Range;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use futures::stream::{BoxStream, StreamExt, TryStreamExt};
+use object_store::{
+    aws::AmazonS3Builder, memory::InMemory, path::Path as ObjectPath, GetOptions, GetRange,
+    ObjectStore, PutPayload,
+};
+use tokio::fs;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tokio_util::io::ReaderStream;
+use uuid::Uuid;
+
+use crate::config::S3Config;
+use crate::services::file_storage::FileStorageError;
+
+/// File contents, streamed in chunks
+pub type ByteStream = BoxStream<'static, Result<Bytes, std::io::Error>>;
+
+/// A stored file as reported by [`StorageBackend::metadata`] and [`StorageBackend::list`]
+#[derive(Debug, Clone)]
+pub struct ObjectInfo {
+    pub location: String,
+    pub size: u64,
+    pub modified: Option<DateTime<Utc>>,
+}
+
+/// Size of the filesystem a backend stores files on
+#[derive(Debug, Clone, Copy)]
+pub struct DiskSpace {
+    pub total_bytes: u64,
+    /// Space available to the server (excludes blocks reserved for root)
+    pub available_bytes: u64,
+}
+
+/// Where stored files live.
+///
+/// Files are addressed by their *location*, the string recorded in `images.file_path`. New
+/// locations come from [`StorageBackend::location`], so each backend decides what they look
+/// like (an absolute path for the local filesystem, an object key for S3).
+#[async_trait]
+pub trait StorageBackend: Send + Sync {
+    /// Location for a file at `key`, a relative `/`-separated path such as `sessions/<id>/a.jpg`
+    fn location(&self, key: &str) -> String;
+
+    /// Write `content` to `location`, replacing any file there. Readers never see a partial file.
+    async fn store(&self, location: &str, content: Bytes) -> Result<(), FileStorageError>;
+
+    /// Stream the file's contents, or only the bytes in `range`
+    async fn read_stream(
+        &self,
+        location: &str,
+        range: Option<Range<u64>>,
+    ) -> Result<ByteStream, FileStorageError>;
+
+    /// Delete the file; deleting a file that does not exist is not an error
+    async fn delete(&self, location: &str) -> Result<(), FileStorageError>;
+
+    async fn exists(&self, location: &str) -> Result<bool, FileStorageError>;
+
+    /// Size and modification time of the file (`FileNotFound` if it does not exist)
+    async fn metadata(&self, location: &str) -> Result<ObjectInfo, FileStorageError>;
+
+    /// Every file under `prefix` (a key, `""` for everything), including subdirectories
+    async fn list(&self, prefix: &str) -> Result<Vec<ObjectInfo>, FileStorageError>;
+
+    /// Total and free space where files are stored, if the backend has such a limit
+    async fn disk_space(&self) -> Result<Option<DiskSpace>, FileStorageError> {
+        Ok(None)
+    }
+}
+
+/// Files on the local filesystem under a base directory
+pub struct LocalBackend {
+    base_path: PathBuf,
+}
+
+impl LocalBackend {
+    pub fn new(base_path: impl Into<PathBuf>) -> Result<Self, FileStorageError> {
+        let base_path = base_path.into();
+
+        // Create base directory if it doesn't exist
+        std::fs::create_dir_all(&base_path)?;
+
+        Ok(Self { base_path })
+    }
+
+    /// Path for a location, which must be within the base directory
+    fn resolve(&self, location: &str) -> Result<PathBuf, FileStorageError> {
+        let path = Path::new(location);
+        if !path.starts_with(&self.base_path)
+            || path.components().any(|c| c == Component::ParentDir)
+        {
+            return Err(FileStorageError::InvalidPath(location.to_string()));
+        }
+        Ok(path.to_path_buf())
+    }
+}
+
+/// `FileNotFound` for missing files, so callers can tell them from other I/O failures
+fn io_error(location: &str, e: std::io::Error) -> FileStorageError {
+    match e.kind() {
+        std::io::ErrorKind::NotFound => FileStorageError::FileNotFound(location.to_string()),
+        _ => FileStorageError::IoError(e),
+    }
+}
+
+fn modified_time(metadata: &std::fs::Metadata) -> Option<DateTime<Utc>> {
+    metadata.modified().ok().map(DateTime::<Utc>::from)
+}
+
+#[async_trait]
+impl StorageBackend for LocalBackend {
+    fn location(&self, key: &str) -> String {
+        self.base_path.join(key).to_string_lossy().to_string()
+    }
+
+    async fn store(&self, location: &str, content: Bytes) -> Result<(), FileStorageError> {
+        let path = self.resolve(location)?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).await?;
+        }
+
+        // Write under a temporary name and rename, which is atomic on the same filesystem
+        let temp_path = path.with_file_name(format!(
+            "{}.{}.tmp",
+            path.file_name().unwrap_or_default().to_string_lossy(),
+            Uuid::new_v4()
+        ));
+        let mut file = fs::File::create(&temp_path).await?;
+        file.write_all(&content).await?;
+        file.flush().await?;
+        fs::rename(&temp_path, &path).await?;
+
+        Ok(())
+    }
+
+    async fn read_stream(
+        &self,
+        location: &str,
+        range: Option<Range<u64>>,
+    ) -> Result<ByteStream, FileStorageError> {
+        let path = self.resolve(location)?;
+        let mut file = fs::File::open(&path)
+            .await
+            .map_err(|e| io_error(location, e))?;
+
+        let stream = match range {
+            Some(range) => {
+                file.seek(SeekFrom::Start(range.start)).await?;
+                ReaderStream::new(file.take(range.end.saturating_sub(range.start))).boxed()
+            }
+            None => ReaderStream::new(file).boxed(),
+        };
+        Ok(stream)
+    }
+
+    async fn delete(&self, location: &str) -> Result<(), FileStorageError> {
+        let path = self.resolve(location)?;
+        match fs::remove_file(&path).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn exists(&self, location: &str) -> Result<bool, FileStorageError> {
+        let path = self.resolve(location)?;
+        match fs::metadata(&path).await {
+            Ok(metadata) => Ok(metadata.is_file()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn metadata(&self, location: &str) -> Result<ObjectInfo, FileStorageError> {
+        let path = self.resolve(location)?;
+        let metadata = fs::metadata(&path)
+            .await
+            .map_err(|e| io_error(location, e))?;
+        if !metadata.is_file() {
+            return Err(FileStorageError::FileNotFound(location.to_string()));
+        }
+
+        Ok(ObjectInfo {
+            location: location.to_string(),
+            size: metadata.len(),
+            modified: modified_time(&metadata),
+        })
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<ObjectInfo>, FileStorageError> {
+        let root = self.resolve(&self.location(prefix))?;
+        let mut files = Vec::new();
+        let mut pending = vec![root];
+
+        while let Some(dir) = pending.pop() {
+            let mut entries = match fs::read_dir(&dir).await {
+                Ok(entries) => entries,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            };
+
+            while let Some(entry) = entries.next_entry().await? {
+                let metadata = entry.metadata().await?;
+                if metadata.is_dir() {
+                    pending.push(entry.path());
+                } else if metadata.is_file() {
+                    files.push(ObjectInfo {
+                        location: entry.path().to_string_lossy().to_string(),
+                        size: metadata.len(),
+                        modified: modified_time(&metadata),
+                    });
+                }
+            }
+        }
+
+        Ok(files)
+    }
+
+    async fn disk_space(&self) -> Result<Option<DiskSpace>, FileStorageError> {
+        let base_path = self.base_path.clone();
+        let stats = tokio::task::spawn_blocking(move || rustix::fs::statvfs(&base_path))
+            .await
+            .map_err(|e| FileStorageError::Backend(e.to_string()))?
+            .map_err(std::io::Error::from)?;
+
+        Ok(Some(DiskSpace {
+            total_bytes: stats.f_blocks * stats.f_frsize,
+            available_bytes: stats.f_bavail * stats.f_frsize,
+        }))
+    }
+}
+
+/// Files in an object store: an S3-compatible service (AWS, MinIO, ...) or in memory
+pub struct ObjectStoreBackend {
+    store: Arc<dyn ObjectStore>,
+}
+
+impl ObjectStoreBackend {
+    pub fn new(store: Arc<dyn ObjectStore>) -> Self {
+        Self { store }
+    }
+
+    /// Bucket on an S3-compatible service. Settings left out of `config` are taken from the
+    /// usual `AWS_*` environment variables.
+    pub fn s3(config: &S3Config) -> Result<Self, FileStorageError> {
+        let mut builder = AmazonS3Builder::from_env()
+            .with_bucket_name(&config.bucket)
+            .with_region(&config.region)
+            .with_allow_http(config.allow_http);
+
+        if let Some(endpoint) = &config.endpoint {
+            // Self-hosted services such as MinIO usually only support path-style requests
+            builder = builder
+                .with_endpoint(endpoint)
+                .with_virtual_hosted_style_request(false);
+        }
+        if let Some(access_key_id) = &config.access_key_id {
+            builder = builder.with_access_key_id(access_key_id);
+        }
+        if let Some(secret_access_key) = &config.secret_access_key {
+            builder = builder.with_secret_access_key(secret_access_key);
+        }
+
+        let store = builder
+            .build()
+            .map_err(|e| FileStorageError::Backend(e.to_string()))?;
+        Ok(Self::new(Arc::new(store)))
+    }
+
+    /// Store kept in memory and lost on restart, for tests and local development
+    pub fn in_memory() -> Self {
+        Self::new(Arc::new(InMemory::new()))
+    }
+
+    fn path(location: &str) -> Result<ObjectPath, FileStorageError> {
+        ObjectPath::parse(location).map_err(|_| FileStorageError::InvalidPath(location.to_string()))
+    }
+}
+
+fn object_store_error(location: &str, e: object_store::Error) -> FileStorageError {
+    match e {
+        object_store::Error::NotFound { .. } => {
+            FileStorageError::FileNotFound(location.to_string())
+        }
+        e => FileStorageError::Backend(e.to_string()),
+    }
+}
+
+#[async_trait]
+impl StorageBackend for ObjectStoreBackend {
+    fn location(&self, key: &str) -> String {
+        key.trim_start_matches('/').to_string()
+    }
+
+    async fn store(&self, location: &str, content: Bytes) -> Result<(), FileStorageError> {
+        self.store
+            .put(&Self::path(location)?, PutPayload::from(content))
+            .await
+            .map_err(|e| object_store_error(location, e))?;
+        Ok(())
+    }
+
+    async fn read_stream(
+        &self,
+        location: &str,
+        range: Option<Range<u64>>,
+    ) -> Result<ByteStream, FileStorageError> {
+        let options = GetOptions {
+            range: range.map(GetRange::Bounded),
+            ..GetOptions::default()
+        };
+        let result = self
+            .store
+            .get_opts(&Self::path(location)?, options)
+            .await
+            .map_err(|e| object_store_error(location, e))?;
+
+        Ok(result.into_stream().map_err(std::io::Error::other).boxed())
+    }
+
+    async fn delete(&self, location: &str) -> Result<(), FileStorageError> {
+        match self.store.delete(&Self::path(location)?).await {
+            Ok(()) | Err(object_store::Error::NotFound { .. }) => Ok(()),
+            Err(e) => Err(object_store_error(location, e)),
+        }
+    }
+
+    async fn exists(&self, location: &str) -> Result<bool, FileStorageError> {
+        match self.store.head(&Self::path(location)?).await {
+            Ok(_) => Ok(true),
+            Err(object_store::Error::NotFound { .. }) => Ok(false),
+            Err(e) => Err(object_store_error(location, e)),
+        }
+    }
+
+    async fn metadata(&self, location: &str) -> Result<ObjectInfo, FileStorageError> {
+        let meta = self
+            .store
+            .head(&Self::path(location)?)
+            .await
+            .map_err(|e| object_store_error(location, e))?;
+
+        Ok(ObjectInfo {
+            location: location.to_string(),
+            size: meta.size,
+            modified: Some(meta.last_modified),
+        })
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<ObjectInfo>, FileStorageError> {
+        let prefix = match prefix.trim_matches('/') {
+            "" => None,
+            prefix => Some(Self::path(prefix)?),
+        };
+
+        self.store
+            .list(prefix.as_ref())
+            .map_ok(|meta| ObjectInfo {
+                location: meta.location.to_string(),
+                size: meta.size,
+                modified: Some(meta.last_modified),
+            })
+            .try_collect()
+            .await
+            .map_err(|e| FileStorageError::Backend(e.to_string()))
+    }
+}
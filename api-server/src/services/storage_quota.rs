@@ -0,0 +1,70 @@
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    models::{Image, UserStorageUsage},
+    services::file_storage::{FileStorageError, StorageQuota},
+    AppState,
+};
+
+/// Apply the `USER_STORAGE_QUOTA` default to a user without a quota of their own
+pub fn with_default_quota(state: &AppState, usage: UserStorageUsage) -> UserStorageUsage {
+    let quota_bytes = usage.quota_bytes.or(state
+        .config
+        .file_storage
+        .user_quota_bytes
+        .map(|quota| quota as i64));
+
+    UserStorageUsage {
+        quota_bytes,
+        ..usage
+    }
+}
+
+/// Quota that new images in the session count against: the session owner's. None when the
+/// owner has no quota or the session does not exist.
+pub async fn quota_for_session(
+    state: &AppState,
+    session_id: Uuid,
+) -> Result<Option<StorageQuota>, sqlx::Error> {
+    let Some(session) = state.db.get_session_by_id(session_id).await? else {
+        return Ok(None);
+    };
+    let Some(usage) = state.db.get_user_storage_usage(session.user_id).await? else {
+        return Ok(None);
+    };
+
+    let usage = with_default_quota(state, usage);
+    Ok(usage.quota_bytes.map(|limit| StorageQuota {
+        used_bytes: usage.used_bytes.max(0) as u64,
+        limit_bytes: limit.max(0) as u64,
+    }))
+}
+
+/// Save a stored image, checking the owner's quota again while their user row is locked.
+/// `store_file` checks against usage read before the upload, so without this two concurrent
+/// uploads could both fit into the same remaining space.
+pub async fn create_image(state: &AppState, image: &Image) -> Result<Image, AppError> {
+    let mut tx = state.db.begin_transaction().await?;
+
+    let usage = state
+        .db
+        .lock_session_owner_storage_usage(&mut tx, image.session_id)
+        .await?
+        .map(|usage| with_default_quota(state, usage));
+    if let Some(usage) = usage {
+        if let Some(limit) = usage.quota_bytes {
+            let used = usage.used_bytes.max(0) as u64;
+            let limit = limit.max(0) as u64;
+            let size = image.file_size.max(0) as u64;
+            if used + size > limit {
+                return Err(FileStorageError::QuotaExceeded(used, limit, size).into());
+            }
+        }
+    }
+
+    let image = state.db.create_image(&mut tx, image).await?;
+    tx.commit().await?;
+
+    Ok(image)
+}
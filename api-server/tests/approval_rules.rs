@@ -0,0 +1,205 @@
+use bam::models::{ApprovalAction, ApprovalRule, Booking, BookingStatus, UserRole};
+use bam::services::approval_rules::{evaluate, rule_matches, DEFAULT_RULE_NAME};
+use chrono::{NaiveDate, Utc};
+use uuid::Uuid;
+
+/// Helper function to create an enabled rule with no conditions
+fn rule(priority: i32, action: ApprovalAction) -> ApprovalRule {
+    ApprovalRule {
+        id: Uuid::new_v4(),
+        name: format!("Rule {}", priority),
+        priority,
+        action,
+        requester_roles: vec![],
+        duration_under_minutes: None,
+        duration_over_minutes: None,
+        weekdays_only: false,
+        microscope_id: None,
+        enabled: true,
+        created_at: Utc::now(),
+    }
+}
+
+/// Helper function to create a booking on bio-1 starting at 9AM
+fn booking(date: NaiveDate, minutes: i32) -> Booking {
+    Booking {
+        id: Uuid::new_v4(),
+        microscope_id: "bio-1".to_string(),
+        date,
+        slot_start: 540,
+        slot_end: 540 + minutes,
+        title: "Cell imaging".to_string(),
+        group_name: None,
+        attendees: None,
+        requester_id: Uuid::new_v4(),
+        requester_name: "Test User".to_string(),
+        status: BookingStatus::Pending,
+        approved_by: None,
+        required_capabilities: None,
+        created_at: Utc::now().fixed_offset(),
+    }
+}
+
+fn monday() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2026, 3, 2).unwrap()
+}
+
+fn saturday() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2026, 3, 7).unwrap()
+}
+
+#[test]
+fn test_lowest_priority_match_decides() {
+    let rules = vec![
+        rule(20, ApprovalAction::AutoApprove),
+        rule(10, ApprovalAction::RequireApproval),
+    ];
+
+    let outcome = evaluate(&rules, &booking(monday(), 60), UserRole::Student);
+    assert_eq!(outcome.action, ApprovalAction::RequireApproval);
+    assert_eq!(outcome.rule_id, Some(rules[1].id));
+    assert_eq!(outcome.rule_name, "Rule 10");
+}
+
+#[test]
+fn test_disabled_rules_are_skipped() {
+    let mut disabled = rule(10, ApprovalAction::RequireApproval);
+    disabled.enabled = false;
+    let rules = vec![disabled, rule(20, ApprovalAction::AutoApprove)];
+
+    let outcome = evaluate(&rules, &booking(monday(), 60), UserRole::Student);
+    assert_eq!(outcome.action, ApprovalAction::AutoApprove);
+    assert_eq!(outcome.rule_id, Some(rules[1].id));
+}
+
+#[test]
+fn test_no_match_requires_approval() {
+    let mut teachers = rule(10, ApprovalAction::AutoApprove);
+    teachers.requester_roles = vec![UserRole::Teacher];
+
+    let outcome = evaluate(&[teachers], &booking(monday(), 60), UserRole::Student);
+    assert_eq!(outcome.action, ApprovalAction::RequireApproval);
+    assert_eq!(outcome.rule_id, None);
+    assert_eq!(outcome.rule_name, DEFAULT_RULE_NAME);
+}
+
+#[test]
+fn test_role_matching() {
+    let mut staff = rule(10, ApprovalAction::AutoApprove);
+    staff.requester_roles = vec![UserRole::Teacher, UserRole::Admin];
+    let anyone = rule(10, ApprovalAction::AutoApprove);
+
+    let booking = booking(monday(), 60);
+    assert!(rule_matches(&staff, &booking, UserRole::Teacher));
+    assert!(rule_matches(&staff, &booking, UserRole::Admin));
+    assert!(!rule_matches(&staff, &booking, UserRole::Student));
+    assert!(rule_matches(&anyone, &booking, UserRole::Student));
+}
+
+#[test]
+fn test_duration_bounds_are_strict() {
+    let mut short = rule(10, ApprovalAction::AutoApprove);
+    short.duration_under_minutes = Some(60);
+    let mut long = rule(10, ApprovalAction::RequireApproval);
+    long.duration_over_minutes = Some(120);
+
+    assert!(rule_matches(
+        &short,
+        &booking(monday(), 59),
+        UserRole::Student
+    ));
+    assert!(!rule_matches(
+        &short,
+        &booking(monday(), 60),
+        UserRole::Student
+    ));
+
+    assert!(!rule_matches(
+        &long,
+        &booking(monday(), 120),
+        UserRole::Student
+    ));
+    assert!(rule_matches(
+        &long,
+        &booking(monday(), 121),
+        UserRole::Student
+    ));
+}
+
+#[test]
+fn test_weekdays_only() {
+    let mut weekdays = rule(10, ApprovalAction::AutoApprove);
+    weekdays.weekdays_only = true;
+
+    assert!(rule_matches(
+        &weekdays,
+        &booking(monday(), 30),
+        UserRole::Student
+    ));
+    assert!(!rule_matches(
+        &weekdays,
+        &booking(saturday(), 30),
+        UserRole::Student
+    ));
+    assert!(!rule_matches(
+        &weekdays,
+        &booking(saturday().succ_opt().unwrap(), 30),
+        UserRole::Student
+    ));
+}
+
+#[test]
+fn test_microscope_matching() {
+    let mut bio2 = rule(10, ApprovalAction::AutoApprove);
+    bio2.microscope_id = Some("bio-2".to_string());
+
+    let mut booking = booking(monday(), 30);
+    assert!(!rule_matches(&bio2, &booking, UserRole::Student));
+    booking.microscope_id = "bio-2".to_string();
+    assert!(rule_matches(&bio2, &booking, UserRole::Student));
+}
+
+/// The rules seeded by migration 004
+fn seeded_rules() -> Vec<ApprovalRule> {
+    let mut staff = rule(10, ApprovalAction::AutoApprove);
+    staff.requester_roles = vec![UserRole::Teacher, UserRole::Admin];
+
+    let mut long = rule(5, ApprovalAction::RequireApproval);
+    long.duration_over_minutes = Some(120);
+
+    let mut short_student = rule(30, ApprovalAction::AutoApprove);
+    short_student.requester_roles = vec![UserRole::Student];
+    short_student.duration_under_minutes = Some(60);
+    short_student.weekdays_only = true;
+
+    vec![staff, long, short_student]
+}
+
+#[test]
+fn test_seeded_rules_require_approval_for_long_staff_bookings() {
+    let rules = seeded_rules();
+
+    let long = evaluate(&rules, &booking(monday(), 180), UserRole::Teacher);
+    assert_eq!(long.action, ApprovalAction::RequireApproval);
+    assert_eq!(long.rule_id, Some(rules[1].id));
+
+    let short = evaluate(&rules, &booking(monday(), 60), UserRole::Admin);
+    assert_eq!(short.action, ApprovalAction::AutoApprove);
+    assert_eq!(short.rule_id, Some(rules[0].id));
+}
+
+#[test]
+fn test_seeded_rules_for_students() {
+    let rules = seeded_rules();
+
+    let weekday = evaluate(&rules, &booking(monday(), 30), UserRole::Student);
+    assert_eq!(weekday.action, ApprovalAction::AutoApprove);
+    assert_eq!(weekday.rule_id, Some(rules[2].id));
+
+    let weekend = evaluate(&rules, &booking(saturday(), 30), UserRole::Student);
+    assert_eq!(weekend.action, ApprovalAction::RequireApproval);
+    assert_eq!(weekend.rule_id, None);
+
+    let long = evaluate(&rules, &booking(monday(), 180), UserRole::Student);
+    assert_eq!(long.rule_id, Some(rules[1].id));
+}
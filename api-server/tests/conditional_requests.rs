@@ -0,0 +1,127 @@
+use std::time::{Duration, SystemTime};
+
+use axum::http::{header, HeaderMap, HeaderName};
+use bam::services::conditional::{if_range_matches, is_not_modified, parse_byte_range};
+
+const ETAG: &str = "\"abc123\"";
+
+fn modified() -> SystemTime {
+    SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000)
+}
+
+fn headers(values: &[(HeaderName, &str)]) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    for (name, value) in values {
+        headers.insert(name.clone(), value.parse().unwrap());
+    }
+    headers
+}
+
+#[test]
+fn test_parse_byte_range() {
+    let cases: &[(&str, u64, Option<Result<(u64, u64), ()>>)] = &[
+        ("bytes=0-99", 1000, Some(Ok((0, 99)))),
+        ("bytes=900-", 1000, Some(Ok((900, 999)))),
+        ("bytes=500-2000", 1000, Some(Ok((500, 999)))),
+        (" bytes=0-0 ", 1000, Some(Ok((0, 0)))),
+        // Suffix ranges
+        ("bytes=-100", 1000, Some(Ok((900, 999)))),
+        ("bytes=-2000", 1000, Some(Ok((0, 999)))),
+        ("bytes=-0", 1000, Some(Err(()))),
+        ("bytes=-5", 0, Some(Err(()))),
+        // Start at or past the end
+        ("bytes=1000-", 1000, Some(Err(()))),
+        ("bytes=1000-1200", 1000, Some(Err(()))),
+        ("bytes=0-", 0, Some(Err(()))),
+        // Ignored: several ranges, other units, malformed
+        ("bytes=0-1,5-6", 1000, None),
+        ("items=0-1", 1000, None),
+        ("bytes=5-1", 1000, None),
+        ("bytes=abc", 1000, None),
+        ("bytes=a-b", 1000, None),
+    ];
+
+    for (value, size, expected) in cases {
+        assert_eq!(
+            parse_byte_range(value, *size),
+            *expected,
+            "{} of {} bytes",
+            value,
+            size
+        );
+    }
+}
+
+#[test]
+fn test_is_not_modified() {
+    let cases: &[(&[(HeaderName, &str)], bool)] = &[
+        (&[], false),
+        (&[(header::IF_NONE_MATCH, "\"abc123\"")], true),
+        (&[(header::IF_NONE_MATCH, "W/\"abc123\"")], true),
+        (&[(header::IF_NONE_MATCH, "\"other\", \"abc123\"")], true),
+        (&[(header::IF_NONE_MATCH, "*")], true),
+        (&[(header::IF_NONE_MATCH, "\"other\"")], false),
+        (
+            &[(header::IF_MODIFIED_SINCE, "Tue, 14 Nov 2023 22:13:20 GMT")],
+            true,
+        ),
+        (
+            &[(header::IF_MODIFIED_SINCE, "Wed, 15 Nov 2023 00:00:00 GMT")],
+            true,
+        ),
+        (
+            &[(header::IF_MODIFIED_SINCE, "Tue, 14 Nov 2023 22:13:19 GMT")],
+            false,
+        ),
+        (&[(header::IF_MODIFIED_SINCE, "yesterday")], false),
+        // If-None-Match takes precedence over If-Modified-Since
+        (
+            &[
+                (header::IF_NONE_MATCH, "\"other\""),
+                (header::IF_MODIFIED_SINCE, "Wed, 15 Nov 2023 00:00:00 GMT"),
+            ],
+            false,
+        ),
+    ];
+
+    for (values, expected) in cases {
+        assert_eq!(
+            is_not_modified(&headers(values), ETAG, Some(modified())),
+            *expected,
+            "{:?}",
+            values
+        );
+    }
+
+    let since = headers(&[(header::IF_MODIFIED_SINCE, "Wed, 15 Nov 2023 00:00:00 GMT")]);
+    assert!(!is_not_modified(&since, ETAG, None));
+}
+
+#[test]
+fn test_if_range_matches() {
+    let cases: &[(&[(HeaderName, &str)], bool)] = &[
+        (&[], true),
+        (&[(header::IF_RANGE, "\"abc123\"")], true),
+        (&[(header::IF_RANGE, "\"other\"")], false),
+        // If-Range requires a strong comparison
+        (&[(header::IF_RANGE, "W/\"abc123\"")], false),
+        (&[(header::IF_RANGE, "Tue, 14 Nov 2023 22:13:20 GMT")], true),
+        (
+            &[(header::IF_RANGE, "Wed, 15 Nov 2023 00:00:00 GMT")],
+            false,
+        ),
+        (&[(header::IF_RANGE, "not a date")], false),
+    ];
+
+    for (values, expected) in cases {
+        assert_eq!(
+            if_range_matches(&headers(values), ETAG, Some(modified())),
+            *expected,
+            "{:?}",
+            values
+        );
+    }
+
+    let by_date = headers(&[(header::IF_RANGE, "Tue, 14 Nov 2023 22:13:20 GMT")]);
+    assert!(!if_range_matches(&by_date, ETAG, None));
+}
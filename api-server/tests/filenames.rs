@@ -0,0 +1,58 @@
+use bam::services::filenames::{content_disposition, sanitize_stem};
+
+#[test]
+fn test_sanitize_stem() {
+    let cases = [
+        ("cells.png", "cells"),
+        ("slide-01_day.2.tiff", "slide-01_day.2"),
+        ("no extension", "no_extension"),
+        ("../../etc/passwd", "passwd"),
+        ("C:\\Users\\lab\\mitosis.jpg", "mitosis"),
+        ("\"quoted\"; name.png", "quoted___name"),
+        ("zellkern-ü.png", "zellkern-"),
+        ("細胞.jpg", "upload"),
+        (".hidden", "hidden"),
+        ("...", "upload"),
+        ("", "upload"),
+    ];
+
+    for (filename, expected) in cases {
+        assert_eq!(sanitize_stem(filename), expected, "{:?}", filename);
+    }
+
+    assert_eq!(
+        sanitize_stem(&format!("{}.png", "a".repeat(300))).len(),
+        100
+    );
+}
+
+#[test]
+fn test_content_disposition() {
+    let cases = [
+        (
+            "cells.png",
+            "inline; filename=\"cells.png\"; filename*=UTF-8''cells.png",
+        ),
+        (
+            "day 1 \"a\".png",
+            "inline; filename=\"day 1 _a_.png\"; filename*=UTF-8''day%201%20%22a%22.png",
+        ),
+        (
+            "zellkern-ü.png",
+            "inline; filename=\"zellkern-_.png\"; filename*=UTF-8''zellkern-%C3%BC.png",
+        ),
+        (
+            "a\r\nSet-Cookie: x.png",
+            "inline; filename=\"a__Set-Cookie: x.png\"; filename*=UTF-8''a%0D%0ASet-Cookie%3A%20x.png",
+        ),
+    ];
+
+    for (filename, expected) in cases {
+        assert_eq!(
+            content_disposition("inline", filename).to_str().unwrap(),
+            expected,
+            "{:?}",
+            filename
+        );
+    }
+}
@@ -28,12 +28,39 @@ fn create_test_app() -> Router {
             base_path: "/tmp/bam-test".to_string(),
             max_file_size: 10485760, // 10MB
             allowed_types: vec!["image/jpeg".to_string(), "image/png".to_string()],
+            url_signing_secret: "test-secret-key".to_string(),
+            signed_url_ttl: 300,
+            content_addressed: false,
+            backend: bam::config::StorageBackendKind::Local,
+            s3: bam::config::S3Config {
+                bucket: String::new(),
+                region: "us-east-1".to_string(),
+                endpoint: None,
+                access_key_id: None,
+                secret_access_key: None,
+                allow_http: false,
+            },
+            trash_retention_days: 30,
+            user_quota_bytes: None,
         },
         ia: bam::config::IAConfig {
             base_url: "http://localhost:8080".to_string(),
             timeout: 30,
             auth_token: None,
         },
+        booking: bam::config::BookingConfig {
+            timezone: "UTC".to_string(),
+        },
+        scheduler: bam::config::SchedulerConfig {
+            interval: 60,
+            session_end_warning_minutes: 10,
+            session_grace_minutes: 5,
+            session_idle_minutes: 30,
+            storage_interval: 86400,
+        },
+        session: bam::config::SessionConfig {
+            presence_timeout: 90,
+        },
     });
 
     let state = AppState {
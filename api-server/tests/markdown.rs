@@ -0,0 +1,70 @@
+use bam::services::markdown::{escape_html, render};
+
+#[test]
+fn test_renders_common_markdown() {
+    let html = render("Cells in **metaphase**\n\n- slide 1\n- slide 2");
+
+    assert!(html.contains("<strong>metaphase</strong>"));
+    assert!(html.contains("<li>slide 1</li>"));
+}
+
+#[test]
+fn test_raw_html_is_escaped() {
+    let block = render("<script>alert(1)</script>");
+    assert!(!block.contains("<script>"));
+    assert!(block.contains("&lt;script&gt;"));
+
+    let inline = render("Focus <img src=x onerror=alert(1)> here");
+    assert!(!inline.contains("<img"));
+    assert!(inline.contains("&lt;img src=x onerror=alert(1)&gt;"));
+}
+
+#[test]
+fn test_script_capable_links_are_neutralised() {
+    for url in [
+        "javascript:alert(1)",
+        "JavaScript:alert(1)",
+        " javascript:alert(1)",
+        "data:text/html;base64,PHNjcmlwdD4=",
+        "vbscript:msgbox(1)",
+    ] {
+        let html = render(&format!("[click](<{}>)", url));
+        assert!(
+            html.contains(r##"<a href="#">"##),
+            "{} rendered as {}",
+            url,
+            html
+        );
+    }
+
+    let image = render("![cells](data:image/svg+xml;base64,PHN2Zz4=)");
+    assert!(image.contains(r##"<img src="#""##), "{}", image);
+}
+
+#[test]
+fn test_safe_links_are_kept() {
+    for url in [
+        "https://example.com/slides",
+        "http://example.com",
+        "mailto:lab@example.com",
+        "/api/images/1/file",
+        "#results",
+        "notes/day1:2",
+    ] {
+        let html = render(&format!("[link]({})", url));
+        assert!(
+            html.contains(&format!(r#"<a href="{}">"#, url)),
+            "{} rendered as {}",
+            url,
+            html
+        );
+    }
+}
+
+#[test]
+fn test_escape_html() {
+    assert_eq!(
+        escape_html(r#"<a href="x">Tom & Jerry's</a>"#),
+        "&lt;a href=&quot;x&quot;&gt;Tom &amp; Jerry&#39;s&lt;/a&gt;"
+    );
+}
@@ -0,0 +1,75 @@
+use bam::models::{Cursor, PageRequest, Paginated};
+use uuid::Uuid;
+
+#[test]
+fn test_page_defaults_and_limit_clamp() {
+    let page = PageRequest::new(None, None, None, 20, 100).unwrap();
+    assert_eq!((page.page, page.limit, page.offset), (1, 20, 0));
+
+    let page = PageRequest::new(Some(0), Some(0), None, 20, 100).unwrap();
+    assert_eq!((page.page, page.limit, page.offset), (1, 1, 0));
+
+    let page = PageRequest::new(Some(3), Some(500), None, 20, 100).unwrap();
+    assert_eq!((page.page, page.limit, page.offset), (3, 100, 200));
+}
+
+#[test]
+fn test_page_offset_overflow_is_rejected() {
+    assert!(PageRequest::new(Some(u64::MAX), Some(100), None, 20, 100).is_err());
+
+    // Offsets past i64::MAX cannot be bound as BIGINT
+    let last = i64::MAX as u64 / 100 + 1;
+    assert!(PageRequest::new(Some(last), Some(100), None, 20, 100).is_ok());
+    assert!(PageRequest::new(Some(last + 1), Some(100), None, 20, 100).is_err());
+}
+
+#[test]
+fn test_cursor_round_trip() {
+    let cursor = Cursor {
+        created_at_us: 1_767_225_600_123_456,
+        id: Uuid::new_v4(),
+    };
+
+    assert_eq!(Cursor::decode(&cursor.encode()), Some(cursor));
+    assert_eq!(Cursor::decode("not a cursor"), None);
+    assert_eq!(Cursor::decode(&hex::encode("12:not-a-uuid")), None);
+}
+
+#[test]
+fn test_cursor_requests() {
+    let start = PageRequest::new(None, Some(10), Some(""), 20, 100).unwrap();
+    assert!(start.by_cursor);
+    assert_eq!((start.offset, start.after), (0, None));
+
+    let cursor = Cursor {
+        created_at_us: 42,
+        id: Uuid::new_v4(),
+    };
+    let next = PageRequest::new(None, None, Some(&cursor.encode()), 20, 100).unwrap();
+    assert_eq!(next.after, Some(cursor));
+    assert_eq!(next.limit, 20);
+
+    assert!(PageRequest::new(None, None, Some("zz"), 20, 100).is_err());
+    assert!(PageRequest::new(Some(2), None, Some(""), 20, 100).is_err());
+    assert!(
+        !PageRequest::new(Some(2), None, None, 20, 100)
+            .unwrap()
+            .by_cursor
+    );
+}
+
+#[test]
+fn test_next_cursor_is_only_set_when_given() {
+    let request = PageRequest::new(None, Some(2), Some(""), 20, 100).unwrap();
+    let cursor = Cursor {
+        created_at_us: 7,
+        id: Uuid::new_v4(),
+    };
+
+    let page = Paginated::new(vec![1, 2], 5, request).with_next_cursor(Some(cursor));
+    assert_eq!(page.next_cursor, Some(cursor.encode()));
+    assert_eq!(page.map(|n| n * 2).next_cursor, Some(cursor.encode()));
+
+    let last = Paginated::new(vec![5], 5, request).with_next_cursor(None);
+    assert_eq!(last.next_cursor, None);
+}
@@ -0,0 +1,138 @@
+use bam::models::{RetentionPolicy, UserRole};
+use bam::services::database::RetentionCandidate;
+use bam::services::retention::{evaluate, RetentionOutcome};
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use uuid::Uuid;
+
+/// Helper function to create an enabled policy
+fn policy(priority: i32, owner_roles: Vec<UserRole>, retain_days: Option<i32>) -> RetentionPolicy {
+    RetentionPolicy {
+        id: Uuid::new_v4(),
+        name: format!("Policy {}", priority),
+        priority,
+        owner_roles,
+        microscope_id: None,
+        retain_days,
+        after_term_end: false,
+        keep_starred: true,
+        keep_exported: true,
+        enabled: true,
+        created_at: Utc::now(),
+    }
+}
+
+/// Helper function to create an image captured on 2025-01-15
+fn candidate(owner_role: UserRole) -> RetentionCandidate {
+    RetentionCandidate {
+        image_id: Uuid::new_v4(),
+        session_id: Uuid::new_v4(),
+        filename: "capture.png".to_string(),
+        file_size: 1024,
+        captured_at: Utc.with_ymd_and_hms(2025, 1, 15, 10, 0, 0).unwrap(),
+        starred: false,
+        exported: false,
+        owner_role,
+        microscope_id: "bio-1".to_string(),
+        term_end: NaiveDate::from_ymd_opt(2025, 3, 1),
+    }
+}
+
+fn at(year: i32, month: u32, day: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(year, month, day, 0, 0, 0).unwrap()
+}
+
+#[test]
+fn test_first_matching_policy_decides() {
+    let policies = vec![
+        policy(20, vec![], Some(30)),
+        policy(10, vec![UserRole::Teacher], None),
+    ];
+
+    assert_eq!(
+        evaluate(&policies, &candidate(UserRole::Teacher), at(2026, 1, 1)),
+        RetentionOutcome::Keep
+    );
+    assert!(matches!(
+        evaluate(&policies, &candidate(UserRole::Student), at(2026, 1, 1)),
+        RetentionOutcome::Expired {
+            policy_index: 0,
+            ..
+        }
+    ));
+}
+
+#[test]
+fn test_disabled_and_unmatched_policies_keep_images() {
+    let mut disabled = policy(10, vec![], Some(1));
+    disabled.enabled = false;
+    let mut other_microscope = policy(20, vec![], Some(1));
+    other_microscope.microscope_id = Some("bio-2".to_string());
+
+    assert_eq!(
+        evaluate(
+            &[disabled, other_microscope],
+            &candidate(UserRole::Student),
+            at(2026, 1, 1)
+        ),
+        RetentionOutcome::Keep
+    );
+}
+
+#[test]
+fn test_retention_counted_from_term_end() {
+    let mut after_term = policy(10, vec![UserRole::Student], Some(180));
+    after_term.after_term_end = true;
+    let policies = vec![after_term];
+    let image = candidate(UserRole::Student);
+
+    // Term ends 2025-03-01, so the period starts 2025-03-02 and ends 180 days later
+    assert_eq!(
+        evaluate(&policies, &image, at(2025, 8, 28)),
+        RetentionOutcome::Keep
+    );
+    assert_eq!(
+        evaluate(&policies, &image, at(2025, 8, 29)),
+        RetentionOutcome::Expired {
+            policy_index: 0,
+            expired_at: at(2025, 8, 29),
+        }
+    );
+
+    let outside_terms = RetentionCandidate {
+        term_end: None,
+        ..image
+    };
+    assert_eq!(
+        evaluate(&policies, &outside_terms, at(2030, 1, 1)),
+        RetentionOutcome::Keep
+    );
+}
+
+#[test]
+fn test_starred_and_exported_images_are_exempt() {
+    let starred = RetentionCandidate {
+        starred: true,
+        ..candidate(UserRole::Student)
+    };
+    let exported = RetentionCandidate {
+        exported: true,
+        ..candidate(UserRole::Student)
+    };
+
+    let keeping = vec![policy(10, vec![], Some(30))];
+    assert_eq!(
+        evaluate(&keeping, &starred, at(2026, 1, 1)),
+        RetentionOutcome::Exempt
+    );
+    assert_eq!(
+        evaluate(&keeping, &exported, at(2026, 1, 1)),
+        RetentionOutcome::Exempt
+    );
+
+    let mut strict = policy(10, vec![], Some(30));
+    strict.keep_starred = false;
+    assert!(matches!(
+        evaluate(&[strict], &starred, at(2026, 1, 1)),
+        RetentionOutcome::Expired { .. }
+    ));
+}
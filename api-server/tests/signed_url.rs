@@ -0,0 +1,92 @@
+use bam::models::ImageRendition;
+use bam::services::signed_url::{derive_secret, sign, verify};
+use chrono::{Duration, TimeZone, Utc};
+use uuid::Uuid;
+
+const SECRET: &str = "download-secret";
+
+#[test]
+fn test_valid_signature_until_expiry() {
+    let image_id = Uuid::new_v4();
+    let now = Utc.with_ymd_and_hms(2026, 3, 2, 9, 0, 0).unwrap();
+    let expires = now + Duration::minutes(5);
+    let signature = sign(SECRET, image_id, None, expires);
+
+    let check = |at| verify(SECRET, image_id, None, expires.timestamp(), &signature, at);
+    assert!(check(now));
+    assert!(check(expires));
+    assert!(!check(expires + Duration::seconds(1)));
+}
+
+#[test]
+fn test_tampered_urls_are_rejected() {
+    let image_id = Uuid::new_v4();
+    let now = Utc.with_ymd_and_hms(2026, 3, 2, 9, 0, 0).unwrap();
+    let expires = now + Duration::minutes(5);
+    let signature = sign(SECRET, image_id, Some(ImageRendition::Thumb), expires);
+    let expiry = expires.timestamp();
+
+    assert!(verify(
+        SECRET,
+        image_id,
+        Some(ImageRendition::Thumb),
+        expiry,
+        &signature,
+        now
+    ));
+
+    // Another rendition, or the original, of the same image
+    assert!(!verify(SECRET, image_id, None, expiry, &signature, now));
+    assert!(!verify(
+        SECRET,
+        image_id,
+        Some(ImageRendition::Preview),
+        expiry,
+        &signature,
+        now
+    ));
+    // Another image
+    assert!(!verify(
+        SECRET,
+        Uuid::new_v4(),
+        Some(ImageRendition::Thumb),
+        expiry,
+        &signature,
+        now
+    ));
+    // Extended expiry
+    assert!(!verify(
+        SECRET,
+        image_id,
+        Some(ImageRendition::Thumb),
+        expiry + 3600,
+        &signature,
+        now
+    ));
+    // Another key, or a signature that is not hex
+    assert!(!verify(
+        "other-secret",
+        image_id,
+        Some(ImageRendition::Thumb),
+        expiry,
+        &signature,
+        now
+    ));
+    assert!(!verify(
+        SECRET,
+        image_id,
+        Some(ImageRendition::Thumb),
+        expiry,
+        "not-hex",
+        now
+    ));
+}
+
+#[test]
+fn test_derived_secret_differs_from_jwt_secret() {
+    let derived = derive_secret("jwt-secret");
+
+    assert_ne!(derived, "jwt-secret");
+    assert_eq!(derived, derive_secret("jwt-secret"));
+    assert_ne!(derived, derive_secret("another-jwt-secret"));
+}
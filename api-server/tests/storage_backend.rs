@@ -0,0 +1,321 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use bam::config::{FileStorageConfig, S3Config, StorageBackendKind};
+use bam::models::ImageRendition;
+use bam::services::file_storage::{FileStorageError, FileStorageService, StorageQuota};
+use bam::services::storage_backend::{LocalBackend, ObjectStoreBackend, StorageBackend};
+use chrono::{Duration, Utc};
+use uuid::Uuid;
+
+/// Helper function to create a storage config
+fn test_config(base_path: &str, content_addressed: bool) -> FileStorageConfig {
+    FileStorageConfig {
+        base_path: base_path.to_string(),
+        max_file_size: 1024 * 1024,
+        allowed_types: vec!["image/png".to_string()],
+        url_signing_secret: "test-secret-key".to_string(),
+        signed_url_ttl: 300,
+        content_addressed,
+        backend: StorageBackendKind::Memory,
+        s3: S3Config {
+            bucket: String::new(),
+            region: "us-east-1".to_string(),
+            endpoint: None,
+            access_key_id: None,
+            secret_access_key: None,
+            allow_http: false,
+        },
+        trash_retention_days: 30,
+        user_quota_bytes: None,
+    }
+}
+
+/// Helper function to create a storage service on each backend
+fn test_services(content_addressed: bool) -> Vec<FileStorageService> {
+    let base_path = std::env::temp_dir().join(format!("bam-storage-test-{}", Uuid::new_v4()));
+    let base_path = base_path.to_string_lossy().to_string();
+
+    let local: Arc<dyn StorageBackend> = Arc::new(LocalBackend::new(&base_path).unwrap());
+    let memory: Arc<dyn StorageBackend> = Arc::new(ObjectStoreBackend::in_memory());
+
+    vec![
+        FileStorageService::with_backend(test_config(&base_path, content_addressed), local),
+        FileStorageService::with_backend(test_config(&base_path, content_addressed), memory),
+    ]
+}
+
+fn test_png() -> Vec<u8> {
+    let image = image::RgbImage::from_pixel(300, 200, image::Rgb([40, 120, 200]));
+    let mut encoded = std::io::Cursor::new(Vec::new());
+    image
+        .write_to(&mut encoded, image::ImageFormat::Png)
+        .unwrap();
+    encoded.into_inner()
+}
+
+#[tokio::test]
+async fn test_store_read_and_delete() {
+    for store in test_services(false) {
+        let content = test_png();
+        let stored = store
+            .store_file("capture.png", &content, Uuid::new_v4(), None)
+            .await
+            .unwrap();
+
+        assert!(stored.file_path.contains("sessions/"));
+        assert!(store.file_exists(&stored.file_path).await);
+        assert_eq!(store.read_file(&stored.file_path).await.unwrap(), content);
+
+        let metadata = store.get_file_metadata(&stored.file_path).await.unwrap();
+        assert_eq!(metadata.size, content.len() as u64);
+        assert_eq!(metadata.content_type, "image/png");
+        assert_eq!(
+            store.content_hash(&stored.file_path).await.unwrap(),
+            stored.sha256
+        );
+
+        store.delete_file(&stored.file_path).await.unwrap();
+        assert!(!store.file_exists(&stored.file_path).await);
+        assert!(matches!(
+            store.read_file(&stored.file_path).await,
+            Err(FileStorageError::FileNotFound(_))
+        ));
+    }
+}
+
+#[tokio::test]
+async fn test_read_range() {
+    for store in test_services(false) {
+        let content = test_png();
+        let stored = store
+            .store_file("capture.png", &content, Uuid::new_v4(), None)
+            .await
+            .unwrap();
+
+        let chunks: Vec<bytes::Bytes> = futures::TryStreamExt::try_collect(
+            store
+                .read_stream(&stored.file_path, Some(10..20))
+                .await
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(chunks.concat(), &content[10..20]);
+    }
+}
+
+#[tokio::test]
+async fn test_rejects_invalid_files() {
+    for store in test_services(false) {
+        assert!(matches!(
+            store
+                .store_file("notes.txt", b"hello", Uuid::new_v4(), None)
+                .await,
+            Err(FileStorageError::InvalidFileType(_))
+        ));
+        assert!(matches!(
+            store
+                .store_file(
+                    "huge.png",
+                    &vec![0u8; 2 * 1024 * 1024],
+                    Uuid::new_v4(),
+                    None
+                )
+                .await,
+            Err(FileStorageError::FileTooLarge(..))
+        ));
+    }
+}
+
+#[tokio::test]
+async fn test_content_addressed_deduplication() {
+    for store in test_services(true) {
+        let content = test_png();
+        let first = store
+            .store_file("a.png", &content, Uuid::new_v4(), None)
+            .await
+            .unwrap();
+        let second = store
+            .store_file("b.png", &content, Uuid::new_v4(), None)
+            .await
+            .unwrap();
+
+        assert!(first
+            .file_path
+            .contains(&format!("objects/{}/", &first.sha256[..2])));
+        assert_eq!(first.file_path, second.file_path);
+        assert!(!first.deduplicated);
+        assert!(second.deduplicated);
+
+        let stats = store.get_storage_stats().await.unwrap();
+        assert_eq!(stats.total_files, 1);
+        assert_eq!(stats.total_size_bytes, content.len() as u64);
+    }
+}
+
+#[tokio::test]
+async fn test_restore_file_rewrites_deleted_objects() {
+    for store in test_services(true) {
+        let content = test_png();
+        let first = store
+            .store_file("a.png", &content, Uuid::new_v4(), None)
+            .await
+            .unwrap();
+        let second = store
+            .store_file("b.png", &content, Uuid::new_v4(), None)
+            .await
+            .unwrap();
+        assert!(second.deduplicated);
+
+        // The object is deleted between the second upload reusing it and referencing it
+        store.delete_file(&first.file_path).await.unwrap();
+        assert!(!store.file_exists(&second.file_path).await);
+
+        assert!(store
+            .restore_file(&second.file_path, &content)
+            .await
+            .unwrap());
+        assert!(!store
+            .restore_file(&second.file_path, &content)
+            .await
+            .unwrap());
+        assert_eq!(
+            store.hash_file(&second.file_path).await.unwrap(),
+            second.sha256
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_renditions_are_stored_and_deleted() {
+    for store in test_services(false) {
+        let stored = store
+            .store_file("capture.png", &test_png(), Uuid::new_v4(), None)
+            .await
+            .unwrap();
+
+        let thumb = store
+            .get_or_create_rendition(&stored.file_path, ImageRendition::Thumb)
+            .await
+            .unwrap();
+        assert!(thumb.ends_with("_thumb.webp"));
+
+        let decoded = image::load_from_memory(&store.read_file(&thumb).await.unwrap()).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (256, 171));
+
+        store.delete_file(&stored.file_path).await.unwrap();
+        assert!(!store.file_exists(&thumb).await);
+    }
+}
+
+#[tokio::test]
+async fn test_local_backend_rejects_paths_outside_base() {
+    let base_path = std::env::temp_dir().join(format!("bam-storage-test-{}", Uuid::new_v4()));
+    let backend = LocalBackend::new(&base_path).unwrap();
+
+    let escaped = format!("{}/../escaped.png", base_path.to_string_lossy());
+    assert!(matches!(
+        backend.exists(&escaped).await,
+        Err(FileStorageError::InvalidPath(_))
+    ));
+    assert!(matches!(
+        backend.exists("/etc/passwd").await,
+        Err(FileStorageError::InvalidPath(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_cleanup_old_files_skips_referenced_files() {
+    for store in test_services(false) {
+        let kept = store
+            .store_file("kept.png", &test_png(), Uuid::new_v4(), None)
+            .await
+            .unwrap();
+        let orphan = store
+            .store_file("orphan.png", &test_png(), Uuid::new_v4(), None)
+            .await
+            .unwrap();
+        let referenced = HashSet::from([kept.file_path.clone()]);
+        let cutoff = Utc::now() + Duration::hours(1);
+
+        let dry_run = store
+            .cleanup_old_files(cutoff, &referenced, true)
+            .await
+            .unwrap();
+        assert_eq!(dry_run.files.len(), 2);
+        assert_eq!(dry_run.orphans.len(), 1);
+        assert_eq!(dry_run.orphans[0].location, orphan.file_path);
+        assert_eq!(dry_run.deleted, 0);
+        assert!(store.file_exists(&orphan.file_path).await);
+
+        let cleanup = store
+            .cleanup_old_files(cutoff, &referenced, false)
+            .await
+            .unwrap();
+        assert_eq!(cleanup.deleted, 1);
+        assert!(!store.file_exists(&orphan.file_path).await);
+        assert!(store.file_exists(&kept.file_path).await);
+    }
+}
+
+#[tokio::test]
+async fn test_cleanup_old_files_keeps_files_when_references_are_missing() {
+    for store in test_services(false) {
+        let stored = store
+            .store_file("capture.png", &test_png(), Uuid::new_v4(), None)
+            .await
+            .unwrap();
+        let referenced = HashSet::from(["elsewhere/capture.png".to_string()]);
+
+        let cleanup = store
+            .cleanup_old_files(Utc::now() + Duration::hours(1), &referenced, false)
+            .await
+            .unwrap();
+        assert_eq!(cleanup.orphans.len(), 1);
+        assert_eq!(cleanup.deleted, 0);
+        assert!(store.file_exists(&stored.file_path).await);
+    }
+}
+
+#[tokio::test]
+async fn test_store_file_enforces_quota() {
+    for store in test_services(false) {
+        let content = test_png();
+        let size = content.len() as u64;
+
+        let fits = StorageQuota {
+            used_bytes: 100,
+            limit_bytes: 100 + size,
+        };
+        assert!(store
+            .store_file("capture.png", &content, Uuid::new_v4(), Some(fits))
+            .await
+            .is_ok());
+
+        let full = StorageQuota {
+            used_bytes: 101,
+            limit_bytes: 100 + size,
+        };
+        assert!(matches!(
+            store
+                .store_file("capture.png", &content, Uuid::new_v4(), Some(full))
+                .await,
+            Err(FileStorageError::QuotaExceeded(101, limit, stored)) if limit == 100 + size && stored == size
+        ));
+        assert_eq!(store.get_storage_stats().await.unwrap().total_files, 1);
+    }
+}
+
+#[tokio::test]
+async fn test_local_backend_reports_disk_space() {
+    let base_path = std::env::temp_dir().join(format!("bam-storage-test-{}", Uuid::new_v4()));
+    let backend = LocalBackend::new(&base_path).unwrap();
+
+    let space = backend.disk_space().await.unwrap().unwrap();
+    assert!(space.total_bytes > 0);
+    assert!(space.available_bytes <= space.total_bytes);
+
+    let memory = ObjectStoreBackend::in_memory();
+    assert!(memory.disk_space().await.unwrap().is_none());
+}